@@ -44,7 +44,7 @@ pub struct TaskDefinition {
 
 /// Typed parameter map. String keys, string values.
 /// Complex values use claim-check pattern (store externally, pass ref).
-#[derive(Debug, Clone, Default, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, Default, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub struct TaskParams {
     #[serde(flatten)]
     pub entries: HashMap<String, String>,
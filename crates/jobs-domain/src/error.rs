@@ -13,6 +13,9 @@ pub enum JobsDomainError {
     #[error("invalid task type: {0}")]
     InvalidTaskType(String),
 
+    #[error("invalid state: {0}")]
+    InvalidState(String),
+
     #[error("invalid state transition: {from} -> {to}")]
     InvalidTransition { from: String, to: String },
 
@@ -22,6 +25,13 @@ pub enum JobsDomainError {
     #[error("job definition contains cyclic dependencies")]
     CyclicDependency,
 
+    #[error("input '{param}' from '{reference}': {reason}")]
+    InputResolution {
+        param: String,
+        reference: String,
+        reason: String,
+    },
+
     #[error("job definition validation: {0}")]
     ValidationFailed(String),
 }
@@ -0,0 +1,278 @@
+//! Resolution of `TaskDefinition::input_from` refs against upstream outcomes.
+
+use std::collections::HashMap;
+
+use serde_json::Value;
+
+use crate::definition::TaskParams;
+use crate::error::JobsDomainError;
+use crate::outcome::TaskOutcome;
+
+/// How a resolved value that is not a JSON string becomes a param.
+///
+/// Params are strings; anything large belongs behind a claim-check ref
+/// (e.g. the upstream `result_ref`), so only `Json` accepts arrays and objects.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum InputStrategy {
+    /// Only strings resolve; any other value is an error.
+    #[default]
+    Strict,
+    /// Numbers and booleans resolve to their JSON text as well.
+    Scalars,
+    /// Every non-null value resolves: strings as-is, anything else
+    /// JSON-encoded.
+    Json,
+}
+
+/// Resolve `input_from` against the completed upstream `outcomes` (by task
+/// name) and merge the values into a copy of `params`, overriding static
+/// params of the same name.
+///
+/// A ref is `{task}.{path}`, walked through the task's completed outcome
+/// seen as `{ "output": [..], "result_ref": .., "data": .. }`; numeric
+/// segments index arrays. So `fetch.data.url` is the `url` key of fetch's
+/// `data` and `fetch.result_ref` its claim-check ref.
+///
+/// # Errors
+/// Returns `JobsDomainError::InputResolution` naming the param and ref if a
+/// source task has no completed outcome, the path does not exist, or the
+/// value is null or not accepted by `strategy`.
+pub fn resolve_inputs(
+    params: &TaskParams,
+    input_from: &HashMap<String, String>,
+    outcomes: &HashMap<&str, &TaskOutcome>,
+    strategy: InputStrategy,
+) -> Result<TaskParams, JobsDomainError> {
+    let mut resolved = params.clone();
+    let mut refs: Vec<_> = input_from.iter().collect();
+    refs.sort();
+    for (param, reference) in refs {
+        let value = resolve(reference, outcomes, strategy).map_err(|reason| {
+            JobsDomainError::InputResolution {
+                param: param.clone(),
+                reference: reference.clone(),
+                reason,
+            }
+        })?;
+        resolved.entries.insert(param.clone(), value);
+    }
+    Ok(resolved)
+}
+
+fn resolve(
+    reference: &str,
+    outcomes: &HashMap<&str, &TaskOutcome>,
+    strategy: InputStrategy,
+) -> Result<String, String> {
+    let mut segments = reference.split('.');
+    let task = segments.next().unwrap_or_default();
+    let path: Vec<&str> = segments.collect();
+    if path.is_empty() {
+        return Err(format!(
+            "ref must name a field of the outcome, e.g. '{task}.data.<key>'"
+        ));
+    }
+
+    let Some(TaskOutcome::Completed {
+        output,
+        result_ref,
+        data,
+    }) = outcomes.get(task)
+    else {
+        return Err(format!("task '{task}' has no completed outcome"));
+    };
+    let root = serde_json::json!({
+        "output": output,
+        "result_ref": result_ref,
+        "data": data,
+    });
+
+    let mut current = &root;
+    let mut at = task.to_string();
+    for segment in path {
+        let next = match current {
+            Value::Object(map) => map.get(segment),
+            Value::Array(items) => segment.parse::<usize>().ok().and_then(|i| items.get(i)),
+            _ => {
+                return Err(format!(
+                    "'{at}' is {}, not an object or array",
+                    kind(current)
+                ));
+            }
+        };
+        let Some(next) = next else {
+            return Err(format!(
+                "path not found: no '{segment}' in '{at}' ({})",
+                kind(current)
+            ));
+        };
+        current = next;
+        at = format!("{at}.{segment}");
+    }
+
+    match (current, strategy) {
+        (Value::String(s), _) => Ok(s.clone()),
+        (Value::Null, _) => Err(format!("'{at}' is null")),
+        (Value::Bool(_) | Value::Number(_), InputStrategy::Scalars | InputStrategy::Json)
+        | (_, InputStrategy::Json) => Ok(current.to_string()),
+        (Value::Bool(_) | Value::Number(_), InputStrategy::Strict) => Err(format!(
+            "'{at}' is {}, not a string; use InputStrategy::Scalars or ::Json to accept it",
+            kind(current)
+        )),
+        _ => Err(format!(
+            "'{at}' is {}; pass a claim-check ref (such as result_ref) instead, or use InputStrategy::Json",
+            kind(current)
+        )),
+    }
+}
+
+fn kind(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "a boolean",
+        Value::Number(_) => "a number",
+        Value::String(_) => "a string",
+        Value::Array(_) => "an array",
+        Value::Object(_) => "an object",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fetch_outcome() -> TaskOutcome {
+        TaskOutcome::Completed {
+            output: vec!["row1".to_string()],
+            result_ref: Some("s3://bucket/fetch.csv".to_string()),
+            data: Some(serde_json::json!({
+                "url": "https://example.com/report",
+                "rows": 42,
+                "partial": false,
+                "items": [{"id": "a"}, {"id": "b"}],
+                "meta": {"source": "billing-api"},
+                "cursor": null,
+            })),
+        }
+    }
+
+    fn resolve_one(reference: &str, strategy: InputStrategy) -> Result<String, JobsDomainError> {
+        let outcome = fetch_outcome();
+        let failed = TaskOutcome::Failed {
+            exit_code: 1,
+            error: "boom".to_string(),
+        };
+        let outcomes = HashMap::from([("fetch", &outcome), ("flaky", &failed)]);
+        let input_from = HashMap::from([("value".to_string(), reference.to_string())]);
+        resolve_inputs(&TaskParams::default(), &input_from, &outcomes, strategy)
+            .map(|params| params.entries["value"].clone())
+    }
+
+    fn reason(err: JobsDomainError) -> String {
+        match err {
+            JobsDomainError::InputResolution { reason, .. } => reason,
+            other => panic!("expected InputResolution, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn resolves_strings_and_overrides_static_params() {
+        let outcome = fetch_outcome();
+        let outcomes = HashMap::from([("fetch", &outcome)]);
+        let mut params = TaskParams::default();
+        params
+            .entries
+            .insert("url".to_string(), "placeholder".to_string());
+        params
+            .entries
+            .insert("format".to_string(), "csv".to_string());
+        let input_from = HashMap::from([
+            ("url".to_string(), "fetch.data.url".to_string()),
+            ("input".to_string(), "fetch.result_ref".to_string()),
+            ("first".to_string(), "fetch.data.items.0.id".to_string()),
+        ]);
+
+        let resolved =
+            resolve_inputs(&params, &input_from, &outcomes, InputStrategy::default()).unwrap();
+        assert_eq!(resolved.entries["url"], "https://example.com/report");
+        assert_eq!(resolved.entries["input"], "s3://bucket/fetch.csv");
+        assert_eq!(resolved.entries["first"], "a");
+        assert_eq!(resolved.entries["format"], "csv");
+    }
+
+    #[test]
+    fn missing_paths_name_where_the_walk_stopped() {
+        let err = resolve_one("fetch.data.missing", InputStrategy::Strict).unwrap_err();
+        assert!(err.to_string().contains("input 'value'"));
+        assert!(err.to_string().contains("'fetch.data.missing'"));
+        assert_eq!(
+            reason(err),
+            "path not found: no 'missing' in 'fetch.data' (an object)"
+        );
+
+        let err = resolve_one("fetch.data.items.5", InputStrategy::Strict).unwrap_err();
+        assert_eq!(
+            reason(err),
+            "path not found: no '5' in 'fetch.data.items' (an array)"
+        );
+
+        let err = resolve_one("fetch.data.url.host", InputStrategy::Strict).unwrap_err();
+        assert_eq!(
+            reason(err),
+            "'fetch.data.url' is a string, not an object or array"
+        );
+    }
+
+    #[test]
+    fn source_without_completed_outcome_is_an_error() {
+        let err = resolve_one("flaky.data.url", InputStrategy::Strict).unwrap_err();
+        assert_eq!(reason(err), "task 'flaky' has no completed outcome");
+
+        let err = resolve_one("absent.data.url", InputStrategy::Strict).unwrap_err();
+        assert_eq!(reason(err), "task 'absent' has no completed outcome");
+
+        let err = resolve_one("fetch", InputStrategy::Strict).unwrap_err();
+        assert!(reason(err).contains("must name a field"));
+    }
+
+    #[test]
+    fn non_string_values_follow_the_strategy() {
+        let err = resolve_one("fetch.data.rows", InputStrategy::Strict).unwrap_err();
+        assert!(reason(err).starts_with("'fetch.data.rows' is a number, not a string"));
+        assert_eq!(
+            resolve_one("fetch.data.rows", InputStrategy::Scalars).unwrap(),
+            "42"
+        );
+        assert_eq!(
+            resolve_one("fetch.data.partial", InputStrategy::Scalars).unwrap(),
+            "false"
+        );
+
+        for strategy in [InputStrategy::Strict, InputStrategy::Scalars] {
+            let err = resolve_one("fetch.data.meta", strategy).unwrap_err();
+            assert!(reason(err).contains("is an object; pass a claim-check ref"));
+            let err = resolve_one("fetch.output", strategy).unwrap_err();
+            assert!(reason(err).contains("is an array"));
+        }
+        assert_eq!(
+            resolve_one("fetch.data.meta", InputStrategy::Json).unwrap(),
+            r#"{"source":"billing-api"}"#
+        );
+        assert_eq!(
+            resolve_one("fetch.output", InputStrategy::Json).unwrap(),
+            r#"["row1"]"#
+        );
+    }
+
+    #[test]
+    fn null_is_an_error_under_every_strategy() {
+        for strategy in [
+            InputStrategy::Strict,
+            InputStrategy::Scalars,
+            InputStrategy::Json,
+        ] {
+            let err = resolve_one("fetch.data.cursor", strategy).unwrap_err();
+            assert_eq!(reason(err), "'fetch.data.cursor' is null");
+        }
+    }
+}
@@ -6,6 +6,12 @@ pub fn job_key(job_type: &str, job_id: &str) -> String {
     format!("gbe.state.jobs.{job_type}.{job_id}")
 }
 
+/// Scan prefix for all job records: `gbe.state.jobs.`
+#[must_use]
+pub fn jobs_prefix() -> String {
+    "gbe.state.jobs.".to_string()
+}
+
 /// Task state record key: `gbe.state.tasks.{task_type}.{task_id}`
 /// Follows existing KV pattern from kv-state-store.md.
 #[must_use]
@@ -13,6 +19,12 @@ pub fn task_key(task_type: &str, task_id: &str) -> String {
     format!("gbe.state.tasks.{task_type}.{task_id}")
 }
 
+/// Scan prefix for all task records: `gbe.state.tasks.`
+#[must_use]
+pub fn tasks_prefix() -> String {
+    "gbe.state.tasks.".to_string()
+}
+
 /// Index key mapping job -> task by name: `gbe.idx.jobs.{job_id}.tasks.{task_name}`
 #[must_use]
 pub fn job_task_index_key(job_id: &str, task_name: &str) -> String {
@@ -25,7 +37,30 @@ pub fn job_tasks_prefix(job_id: &str) -> String {
     format!("gbe.idx.jobs.{job_id}.tasks.")
 }
 
+/// Presence record key: `gbe.state.presence.{component}.{instance_id}`
+#[must_use]
+pub fn presence_key(component: &str, instance_id: &str) -> String {
+    format!("gbe.state.presence.{component}.{instance_id}")
+}
+
+/// Scan prefix for the instances of a component: `gbe.state.presence.{component}.`
+#[must_use]
+pub fn presence_prefix(component: &str) -> String {
+    format!("gbe.state.presence.{component}.")
+}
+
+/// Scan prefix for all presence records: `gbe.state.presence.`
+#[must_use]
+pub fn presences_prefix() -> String {
+    "gbe.state.presence.".to_string()
+}
+
 /// Field name constants for type-safe KV access.
+///
+/// Timestamp fields (`*_at`, `last_seen`) hold unix millis as decimal
+/// strings. These stay 13 digits wide until the year 2286, so the
+/// byte-wise `ScanOp::Lt`/`Gt` comparisons of scan filters order them
+/// numerically.
 pub mod fields {
     pub mod job {
         pub const STATE: &str = "state";
@@ -60,6 +95,27 @@ pub mod fields {
         pub const RETRY_COUNT: &str = "retry_count";
         pub const MAX_RETRIES: &str = "max_retries";
         pub const DEPENDS_ON: &str = "depends_on";
+        pub const JOB_TYPE: &str = "job_type";
+        pub const PARAMS: &str = "params";
+        pub const INPUT_FROM: &str = "input_from";
+        pub const TIMEOUT_SECS: &str = "timeout_secs";
+        /// JSON `TaskOutcome`, written by the worker with the terminal state.
+        pub const OUTCOME: &str = "outcome";
+    }
+
+    pub mod presence {
+        pub const COMPONENT: &str = "component";
+        pub const INSTANCE_ID: &str = "instance_id";
+        pub const STATUS: &str = "status";
+        /// Time (unix millis) of the event that set the current status.
+        pub const STATUS_AT: &str = "status_at";
+        /// Time (unix millis) of the latest event from the instance.
+        pub const LAST_SEEN: &str = "last_seen";
+        pub const STARTED_AT: &str = "started_at";
+        pub const VERSION: &str = "version";
+        pub const UPTIME_SECS: &str = "uptime_secs";
+        /// Reason given with the last `degraded` or `stopped`.
+        pub const REASON: &str = "reason";
     }
 }
 
@@ -75,6 +131,11 @@ mod tests {
         );
     }
 
+    #[test]
+    fn jobs_prefix_matches_job_keys() {
+        assert!(job_key("daily-report", "job_abc123").starts_with(&jobs_prefix()));
+    }
+
     #[test]
     fn task_key_format() {
         assert_eq!(
@@ -83,6 +144,19 @@ mod tests {
         );
     }
 
+    #[test]
+    fn tasks_prefix_matches_task_keys() {
+        assert!(task_key("email-send", "task_xyz789").starts_with(&tasks_prefix()));
+    }
+
+    #[test]
+    fn presence_key_format() {
+        let key = presence_key("operative", "opr-abc123");
+        assert_eq!(key, "gbe.state.presence.operative.opr-abc123");
+        assert!(key.starts_with(&presence_prefix("operative")));
+        assert!(key.starts_with(&presences_prefix()));
+    }
+
     #[test]
     fn index_key_format() {
         assert_eq!(
@@ -1,6 +1,7 @@
 pub mod definition;
 pub mod error;
 pub mod ids;
+pub mod input;
 pub mod keys;
 pub mod lifecycle;
 pub mod outcome;
@@ -11,6 +12,10 @@ pub mod subjects;
 pub use definition::{JobDefinition, TaskDefinition, TaskParams};
 pub use error::JobsDomainError;
 pub use ids::{JobId, OrgId, TaskId, TaskType};
-pub use lifecycle::{ComponentDegraded, ComponentStarted, ComponentStopped, Heartbeat};
+pub use input::{InputStrategy, resolve_inputs};
+pub use lifecycle::{
+    ComponentDegraded, ComponentStarted, ComponentStopped, Heartbeat, PresenceChanged,
+    PresenceStatus,
+};
 pub use outcome::TaskOutcome;
 pub use state::{JobState, TaskState};
@@ -1,3 +1,5 @@
+use crate::error::JobsDomainError;
+
 // -- Lifecycle payloads --
 // Published to gbe.events.lifecycle.{component}.* subjects.
 // Wrap in DomainPayload<T> from gbe-nexus before publishing.
@@ -42,6 +44,79 @@ pub struct ComponentDegraded {
     pub reason: String,
 }
 
+// -- Presence --
+// Tracked from the lifecycle events by the presence registry.
+
+/// Status of a component instance as seen by the presence registry.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PresenceStatus {
+    /// Announced itself; no heartbeat yet.
+    Started,
+    /// Heartbeating.
+    Healthy,
+    /// Heartbeating but reported itself unhealthy.
+    Degraded,
+    /// Missed too many heartbeats; may be gone.
+    Stale,
+    /// Shut down. Final: instance IDs are not reused.
+    Stopped,
+}
+
+impl PresenceStatus {
+    /// Started, healthy or degraded.
+    #[must_use]
+    pub fn is_alive(self) -> bool {
+        matches!(self, Self::Started | Self::Healthy | Self::Degraded)
+    }
+
+    #[must_use]
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Started => "started",
+            Self::Healthy => "healthy",
+            Self::Degraded => "degraded",
+            Self::Stale => "stale",
+            Self::Stopped => "stopped",
+        }
+    }
+}
+
+impl std::fmt::Display for PresenceStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl std::str::FromStr for PresenceStatus {
+    type Err = JobsDomainError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "started" => Ok(Self::Started),
+            "healthy" => Ok(Self::Healthy),
+            "degraded" => Ok(Self::Degraded),
+            "stale" => Ok(Self::Stale),
+            "stopped" => Ok(Self::Stopped),
+            _ => Err(JobsDomainError::InvalidState(s.to_string())),
+        }
+    }
+}
+
+/// An instance changed presence status, e.g. went stale.
+/// Subject: `gbe.events.presence.{component}.changed`
+#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+pub struct PresenceChanged {
+    pub component: String,
+    pub instance_id: String,
+    /// `None` for an instance seen for the first time.
+    pub from: Option<PresenceStatus>,
+    pub to: PresenceStatus,
+    pub changed_at: u64,
+    /// Last time (unix millis) the instance was heard from.
+    pub last_seen: u64,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -98,4 +173,36 @@ mod tests {
         let back: ComponentStopped = serde_json::from_str(&json).unwrap();
         assert_eq!(back.reason, "SIGTERM");
     }
+
+    #[test]
+    fn presence_status_parses_from_as_str() {
+        for status in [
+            PresenceStatus::Started,
+            PresenceStatus::Healthy,
+            PresenceStatus::Degraded,
+            PresenceStatus::Stale,
+            PresenceStatus::Stopped,
+        ] {
+            assert_eq!(status.as_str().parse::<PresenceStatus>().unwrap(), status);
+        }
+        assert!("gone".parse::<PresenceStatus>().is_err());
+        assert!(PresenceStatus::Degraded.is_alive());
+        assert!(!PresenceStatus::Stale.is_alive());
+    }
+
+    #[test]
+    fn presence_changed_round_trip() {
+        let payload = PresenceChanged {
+            component: "operative".to_string(),
+            instance_id: "opr-abc123".to_string(),
+            from: Some(PresenceStatus::Healthy),
+            to: PresenceStatus::Stale,
+            changed_at: 1_707_936_090_000,
+            last_seen: 1_707_936_000_000,
+        };
+        let json = serde_json::to_string(&payload).unwrap();
+        assert!(json.contains(r#""to":"stale""#));
+        let back: PresenceChanged = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.from, Some(PresenceStatus::Healthy));
+    }
 }
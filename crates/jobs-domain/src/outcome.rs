@@ -1,6 +1,6 @@
 /// Outcome reported by an operative after executing a task.
 /// Published on the terminal stream: `gbe.tasks.{task_type}.terminal`
-#[derive(Debug, Clone, serde::Serialize, serde::Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 pub enum TaskOutcome {
     Completed {
         output: Vec<String>,
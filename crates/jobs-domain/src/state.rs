@@ -60,9 +60,25 @@ impl std::fmt::Display for JobState {
     }
 }
 
+impl std::str::FromStr for JobState {
+    type Err = JobsDomainError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "pending" => Ok(Self::Pending),
+            "running" => Ok(Self::Running),
+            "completed" => Ok(Self::Completed),
+            "failed" => Ok(Self::Failed),
+            "cancelled" => Ok(Self::Cancelled),
+            _ => Err(JobsDomainError::InvalidState(s.to_string())),
+        }
+    }
+}
+
 /// Task-level state machine.
 /// Compatible with watcher expectations: terminal states are "completed", "failed", "cancelled".
-/// Watcher resets non-terminal stuck tasks to "pending".
+/// Watcher resets non-terminal stuck tasks to "pending", or fails them once
+/// their retries are exhausted.
 #[derive(Debug, Clone, Copy, PartialEq, Eq, serde::Serialize, serde::Deserialize)]
 #[serde(rename_all = "snake_case")]
 pub enum TaskState {
@@ -89,6 +105,8 @@ impl TaskState {
                     Self::Completed | Self::Failed | Self::Cancelled
                 )
                 | (Self::Blocked, Self::Cancelled)
+                // Watcher gives up on a stuck claim with no retries left
+                | (Self::Claimed, Self::Failed)
         )
     }
 
@@ -130,6 +148,23 @@ impl std::fmt::Display for TaskState {
     }
 }
 
+impl std::str::FromStr for TaskState {
+    type Err = JobsDomainError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "blocked" => Ok(Self::Blocked),
+            "pending" => Ok(Self::Pending),
+            "claimed" => Ok(Self::Claimed),
+            "running" => Ok(Self::Running),
+            "completed" => Ok(Self::Completed),
+            "failed" => Ok(Self::Failed),
+            "cancelled" => Ok(Self::Cancelled),
+            _ => Err(JobsDomainError::InvalidState(s.to_string())),
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -215,6 +250,13 @@ mod tests {
         assert!(TaskState::Running.can_transition_to(TaskState::Pending));
     }
 
+    #[test]
+    fn task_watcher_fails_exhausted() {
+        assert!(TaskState::Claimed.can_transition_to(TaskState::Failed));
+        assert!(TaskState::Running.can_transition_to(TaskState::Failed));
+        assert!(!TaskState::Pending.can_transition_to(TaskState::Failed));
+    }
+
     #[test]
     fn task_cancel_from_any_non_terminal() {
         assert!(TaskState::Blocked.can_transition_to(TaskState::Cancelled));
@@ -262,6 +304,20 @@ mod tests {
         assert!(TaskState::Cancelled.is_terminal());
     }
 
+    // -- Parsing --
+
+    #[test]
+    fn states_parse_from_as_str() {
+        for state in [JobState::Pending, JobState::Running, JobState::Cancelled] {
+            assert_eq!(state.as_str().parse::<JobState>().unwrap(), state);
+        }
+        for state in [TaskState::Blocked, TaskState::Claimed, TaskState::Failed] {
+            assert_eq!(state.as_str().parse::<TaskState>().unwrap(), state);
+        }
+        assert!("Running".parse::<JobState>().is_err());
+        assert!("done".parse::<TaskState>().is_err());
+    }
+
     // -- Serde --
 
     #[test]
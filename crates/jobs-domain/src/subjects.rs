@@ -81,6 +81,19 @@ pub mod lifecycle {
     }
 }
 
+pub mod presence {
+    #[must_use]
+    pub fn changed(component: &str) -> String {
+        format!("gbe.events.presence.{component}.changed")
+    }
+
+    /// Wildcard for presence changes across all components.
+    #[must_use]
+    pub fn all_components() -> String {
+        "gbe.events.presence.*.changed".to_string()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -140,4 +153,13 @@ mod tests {
         );
         assert_eq!(lifecycle::all_components(), "gbe.events.lifecycle.*.*");
     }
+
+    #[test]
+    fn presence_subjects() {
+        assert_eq!(
+            presence::changed("operative"),
+            "gbe.events.presence.operative.changed"
+        );
+        assert_eq!(presence::all_components(), "gbe.events.presence.*.changed");
+    }
 }
@@ -0,0 +1,119 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use bytes::Bytes;
+
+use gbe_jobs_domain::keys::fields;
+use gbe_jobs_domain::payloads::TaskFailed;
+use gbe_jobs_domain::subjects::tasks;
+use gbe_jobs_domain::{TaskOutcome, TaskState};
+use gbe_nexus::{EventEmitter, now_millis};
+use gbe_state_store::StateStore;
+
+use crate::driver::publish_queued;
+use crate::error::JobsError;
+use crate::record::{TaskRecord, settle_attempt};
+
+/// Settle a failed attempt at `task`, which was read in `task.state`.
+///
+/// While retries remain the task moves back to `pending` and `TaskQueued`
+/// is published again with `retry_count + 1`; otherwise it moves to
+/// `failed`. Either way `error` (and `outcome`, if given) are written to the
+/// record and a `TaskFailed` carrying the attempt's `retry_count` goes to
+/// the task's terminal stream, so watchers and workers report failures the
+/// same way.
+///
+/// The fields are written in the same atomic step as the state, and only
+/// while the attempt is still the one in `task` (see `settle_attempt`), so
+/// an attempt settled by someone else is left as they wrote it. If the
+/// publish or the event fails after that, the watcher sends it again: it
+/// republishes `pending` tasks left idle, and re-sends `TaskFailed` for
+/// `failed` tasks whose job is still running.
+///
+/// Returns the state the task was moved to, or `None` if the attempt was
+/// already settled.
+///
+/// # Errors
+/// Returns an error if the record cannot be updated or an event cannot be
+/// published.
+pub async fn fail_attempt(
+    store: &Arc<dyn StateStore>,
+    emitter: &EventEmitter,
+    task: &TaskRecord,
+    error: &str,
+    outcome: Option<&TaskOutcome>,
+) -> Result<Option<TaskState>, JobsError> {
+    let now = now_millis();
+    let exhausted = task.retry_count >= task.max_retries;
+    let next = if exhausted {
+        TaskState::Failed
+    } else {
+        TaskState::Pending
+    };
+
+    let mut updates = HashMap::from([(
+        fields::task::ERROR.to_string(),
+        Bytes::from(error.to_string()),
+    )]);
+    if let Some(outcome) = outcome {
+        updates.insert(
+            fields::task::OUTCOME.to_string(),
+            Bytes::from(serde_json::to_vec(outcome)?),
+        );
+    }
+    if !exhausted {
+        updates.insert(
+            fields::task::RETRY_COUNT.to_string(),
+            Bytes::from((task.retry_count + 1).to_string()),
+        );
+    }
+    if !settle_attempt(store, task, next, now, updates).await? {
+        return Ok(None);
+    }
+
+    if exhausted {
+        tracing::warn!(task_id = %task.task_id, retries = task.retry_count, "task failed: {error}");
+    } else {
+        let retry = TaskRecord {
+            state: TaskState::Pending,
+            retry_count: task.retry_count + 1,
+            ..task.clone()
+        };
+        publish_queued(emitter.transport().as_ref(), &retry).await?;
+        tracing::info!(task_id = %task.task_id, retry_count = retry.retry_count, "requeued failed task: {error}");
+    }
+    emit_failed(emitter, task, error, now).await?;
+    Ok(Some(next))
+}
+
+/// Send `TaskFailed` for the attempt at `task` to its terminal stream.
+///
+/// The idempotency key is per attempt, so sending it again is safe.
+///
+/// # Errors
+/// Returns an error if the event cannot be published.
+pub async fn emit_failed(
+    emitter: &EventEmitter,
+    task: &TaskRecord,
+    error: &str,
+    failed_at: u64,
+) -> Result<(), JobsError> {
+    emitter
+        .emit_traced(
+            &tasks::terminal(task.task_type.as_str()),
+            1,
+            format!("{}-failed-{}", task.task_id, task.retry_count),
+            TaskFailed {
+                task_id: task.task_id.clone(),
+                job_id: task.job_id.clone(),
+                task_type: task.task_type.clone(),
+                failed_at,
+                error: error.to_string(),
+                retry_count: task.retry_count,
+                max_retries: task.max_retries,
+            },
+            task.job_id.as_str(),
+        )
+        .await?;
+    Ok(())
+}
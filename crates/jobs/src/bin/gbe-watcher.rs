@@ -0,0 +1,56 @@
+//! `gbe-watcher` binary: recovers timed-out tasks on Redis until SIGINT or
+//! SIGTERM, publishing its lifecycle events.
+//!
+//! Environment:
+//! - `REDIS_URL` (default `redis://127.0.0.1:6379`), for both the transport
+//!   and the state store
+//! - `WATCH_INTERVAL_SECS` (default 30)
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use gbe_jobs::{WATCHER_COMPONENT, Watcher, WatcherConfig};
+use gbe_lifecycle::{Lifecycle, LifecycleConfig};
+use gbe_nexus::EventEmitter;
+use gbe_nexus_redis::{RedisTransport, RedisTransportConfig};
+use gbe_state_store::StateStoreConfig;
+use gbe_state_store_redis::RedisStateStore;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tracing_subscriber::fmt::init();
+
+    let mut transport_config = RedisTransportConfig::default();
+    if let Ok(url) = std::env::var("REDIS_URL") {
+        transport_config.url = url;
+    }
+    let mut config = WatcherConfig::default();
+    if let Ok(secs) = std::env::var("WATCH_INTERVAL_SECS") {
+        config.interval = Duration::from_secs(secs.parse()?);
+    }
+
+    let store = RedisStateStore::connect(StateStoreConfig {
+        url: transport_config.url.clone(),
+    })
+    .await?;
+    let transport = Arc::new(RedisTransport::connect(transport_config).await?);
+    let emitter = EventEmitter::new(
+        transport.clone(),
+        WATCHER_COMPONENT,
+        config.instance_id.clone(),
+    );
+    let lifecycle = Arc::new(Lifecycle::new(
+        emitter,
+        LifecycleConfig {
+            version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            ..Default::default()
+        },
+    ));
+    let watcher = Watcher::new(transport, Arc::new(store), config);
+
+    lifecycle.stop_on_signals();
+    tracing::info!("watcher started");
+    tokio::join!(lifecycle.run(), watcher.run(lifecycle.token()));
+    tracing::info!("watcher stopped");
+    Ok(())
+}
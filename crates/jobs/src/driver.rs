@@ -0,0 +1,530 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+
+use gbe_jobs_domain::keys::{self, fields};
+use gbe_jobs_domain::payloads::{JobCompleted, JobCreated, JobFailed, TaskQueued};
+use gbe_jobs_domain::subjects::{jobs, tasks};
+use gbe_jobs_domain::{
+    InputStrategy, JobDefinition, JobId, JobState, OrgId, TaskId, TaskOutcome, TaskState, TaskType,
+    resolve_inputs,
+};
+use gbe_nexus::{
+    DomainPayload, Envelope, EventEmitter, HandlerOutcome, PublishOpts, StartPosition,
+    SubscribeOpts, Subscription, Transport, TypedHandler, TypedMessageHandler, now_millis,
+};
+use gbe_state_store::StateStore;
+
+use crate::error::JobsError;
+use crate::record::{JobRecord, TaskRecord, transition_job, transition_task};
+
+/// Component name used in lifecycle events.
+pub const DRIVER_COMPONENT: &str = "driver";
+
+/// Configuration for a `JobDriver`.
+pub struct DriverConfig {
+    /// Consumer group reading the terminal streams.
+    pub group: String,
+    /// Instance ID reported in emitted events.
+    pub instance_id: String,
+    /// Retry limit for tasks whose definition sets none.
+    pub default_max_retries: u32,
+    /// Timeout for tasks whose definition sets none.
+    pub default_timeout: Duration,
+    /// How `input_from` values that are not strings become params.
+    pub input_strategy: InputStrategy,
+}
+
+impl Default for DriverConfig {
+    fn default() -> Self {
+        Self {
+            group: "driver".to_string(),
+            instance_id: format!("drv-{}", ulid::Ulid::new().to_string().to_lowercase()),
+            default_max_retries: 3,
+            default_timeout: Duration::from_secs(300),
+            input_strategy: InputStrategy::default(),
+        }
+    }
+}
+
+/// Runs jobs: instantiates a `JobDefinition` as job and task records, queues
+/// its root tasks, and queues each dependant once everything it depends on
+/// has completed, with its `input_from` refs resolved against the upstream
+/// outcomes (see `resolve_inputs`). A ref that does not resolve fails the job.
+///
+/// Progress is driven by `gbe.tasks.*.terminal`. The driver reads the task
+/// record named by each terminal event rather than trusting the event, and
+/// every state change is a `compare_and_swap`, so any number of drivers can
+/// share the consumer group and redelivered events are harmless. Job events
+/// are published once, by whichever driver made the transition.
+pub struct JobDriver {
+    transport: Arc<dyn Transport>,
+    store: Arc<dyn StateStore>,
+    emitter: EventEmitter,
+    config: DriverConfig,
+}
+
+impl JobDriver {
+    pub fn new(
+        transport: Arc<dyn Transport>,
+        store: Arc<dyn StateStore>,
+        config: DriverConfig,
+    ) -> Self {
+        let emitter = EventEmitter::new(
+            transport.clone(),
+            DRIVER_COMPONENT,
+            config.instance_id.clone(),
+        );
+        Self {
+            transport,
+            store,
+            emitter,
+            config,
+        }
+    }
+
+    /// Instantiate `definition` for `org_id`: write the task records and
+    /// their job index entries, then the job record, publish `JobCreated`
+    /// and queue the root tasks.
+    ///
+    /// # Errors
+    /// Returns an error if the definition is invalid, or a record cannot be
+    /// written or an event published.
+    pub async fn create_job(
+        &self,
+        definition: &JobDefinition,
+        org_id: OrgId,
+        definition_ref: Option<String>,
+    ) -> Result<JobId, JobsError> {
+        definition.validate()?;
+        let now = now_millis();
+        let job_id = JobId::new(&new_id("job"))?;
+
+        let mut records = Vec::with_capacity(definition.tasks.len());
+        for task in &definition.tasks {
+            let state = if task.depends_on.is_empty() {
+                TaskState::Pending
+            } else {
+                TaskState::Blocked
+            };
+            records.push(TaskRecord {
+                task_id: TaskId::new(&new_id("task"))?,
+                task_type: task.task_type.clone(),
+                task_name: task.name.clone(),
+                job_id: job_id.clone(),
+                job_type: definition.job_type.clone(),
+                org_id: org_id.clone(),
+                state,
+                depends_on: task.depends_on.clone(),
+                params: task.params.clone(),
+                input_from: task.input_from.clone(),
+                retry_count: 0,
+                max_retries: task.max_retries.unwrap_or(self.config.default_max_retries),
+                timeout_secs: task
+                    .timeout_secs
+                    .unwrap_or(self.config.default_timeout.as_secs()),
+                timeout_at: None,
+                worker: None,
+                error: None,
+                outcome: None,
+                created_at: now,
+                updated_at: now,
+            });
+        }
+        for task in &records {
+            self.store.put(&task.key(), task.to_record()?, None).await?;
+            self.store
+                .put(
+                    &keys::job_task_index_key(job_id.as_str(), &task.task_name),
+                    task.index_record(),
+                    None,
+                )
+                .await?;
+        }
+
+        // The job record goes last, so a job never exists without its tasks.
+        #[allow(clippy::cast_possible_truncation)] // task counts are far below u32::MAX
+        let job = JobRecord {
+            job_id: job_id.clone(),
+            job_type: definition.job_type.clone(),
+            org_id: org_id.clone(),
+            state: JobState::Pending,
+            task_count: records.len() as u32,
+            completed_count: 0,
+            failed_count: 0,
+            created_at: now,
+            updated_at: now,
+            error: None,
+            definition_ref: definition_ref.clone(),
+        };
+        self.store.put(&job.key(), job.to_record(), None).await?;
+        transition_job(
+            &self.store,
+            &job.key(),
+            JobState::Pending,
+            JobState::Running,
+            now_millis(),
+        )
+        .await?;
+
+        self.emit(
+            &jobs::created(&definition.job_type),
+            "created",
+            &job_id,
+            JobCreated {
+                job_id: job_id.clone(),
+                org_id,
+                job_type: definition.job_type.clone(),
+                task_count: job.task_count,
+                task_ids: records.iter().map(|t| t.task_id.clone()).collect(),
+                created_at: now,
+                definition_ref,
+            },
+        )
+        .await?;
+
+        for task in records.iter().filter(|t| t.state == TaskState::Pending) {
+            publish_queued(self.transport.as_ref(), task).await?;
+        }
+        tracing::info!(job_id = %job_id, job_type = %definition.job_type, "created job");
+        Ok(job_id)
+    }
+
+    /// Consume `gbe.tasks.*.terminal` until the returned subscription is
+    /// unsubscribed.
+    ///
+    /// # Errors
+    /// Returns an error if the subscription fails.
+    pub async fn start(self: &Arc<Self>) -> Result<Box<dyn Subscription>, JobsError> {
+        let subscription = self
+            .transport
+            .subscribe(
+                &tasks::terminal("*"),
+                &self.config.group,
+                TypedHandler::boxed(TerminalHandler {
+                    driver: self.clone(),
+                }),
+                Some(SubscribeOpts {
+                    start_from: StartPosition::Earliest,
+                    ..Default::default()
+                }),
+            )
+            .await?;
+        Ok(subscription)
+    }
+
+    /// React to a terminal event for `task_id`: unblock its dependants and
+    /// complete the job once every task has completed, or fail the job if
+    /// the task failed for good.
+    ///
+    /// # Errors
+    /// Returns an error if a record cannot be read or updated, or an event
+    /// cannot be published; handling the event again is safe.
+    pub async fn on_terminal(
+        &self,
+        task_type: &TaskType,
+        task_id: &TaskId,
+    ) -> Result<(), JobsError> {
+        let Some(task) = TaskRecord::load(&self.store, task_type, task_id).await? else {
+            tracing::warn!(task_id = %task_id, "terminal event for unknown task");
+            return Ok(());
+        };
+        if !matches!(task.state, TaskState::Completed | TaskState::Failed) {
+            // A failure with retries left has already been queued again;
+            // cancelled tasks belong to a job that is already over.
+            tracing::debug!(task_id = %task_id, state = %task.state, "ignoring terminal event");
+            return Ok(());
+        }
+        let Some(job) = JobRecord::load(&self.store, &task.job_type, &task.job_id).await? else {
+            tracing::warn!(job_id = %task.job_id, task_id = %task_id, "task of unknown job");
+            return Ok(());
+        };
+        let tasks = TaskRecord::load_for_job(&self.store, &job.job_id).await?;
+
+        if task.state == TaskState::Completed {
+            self.advance(&job, &tasks, &task).await
+        } else {
+            let error = task.error.clone().unwrap_or_default();
+            self.fail_job(&job, &tasks, &task, error).await
+        }
+    }
+
+    async fn advance(
+        &self,
+        job: &JobRecord,
+        tasks: &[TaskRecord],
+        completed: &TaskRecord,
+    ) -> Result<(), JobsError> {
+        let done: HashSet<&str> = tasks
+            .iter()
+            .filter(|t| t.state == TaskState::Completed)
+            .map(|t| t.task_name.as_str())
+            .collect();
+        self.store
+            .set_field(
+                &job.key(),
+                fields::job::COMPLETED_COUNT,
+                done.len().to_string().into(),
+            )
+            .await?;
+        if job.state != JobState::Running {
+            return Ok(());
+        }
+
+        let outcomes: HashMap<&str, &TaskOutcome> = tasks
+            .iter()
+            .filter_map(|t| Some((t.task_name.as_str(), t.outcome.as_ref()?)))
+            .collect();
+        for task in tasks.iter().filter(|t| {
+            t.depends_on.contains(&completed.task_name)
+                && t.depends_on.iter().all(|d| done.contains(d.as_str()))
+        }) {
+            let ready = match task.state {
+                TaskState::Blocked => {
+                    let params = match resolve_inputs(
+                        &task.params,
+                        &task.input_from,
+                        &outcomes,
+                        self.config.input_strategy,
+                    ) {
+                        Ok(params) => params,
+                        Err(e) => {
+                            let error = e.to_string();
+                            self.store
+                                .set_field(&task.key(), fields::task::ERROR, error.clone().into())
+                                .await?;
+                            return self.fail_job(job, tasks, task, error).await;
+                        }
+                    };
+                    // Stored while still blocked, so every later publish
+                    // (including watcher retries) carries the resolved params.
+                    if params != task.params {
+                        self.store
+                            .set_field(
+                                &task.key(),
+                                fields::task::PARAMS,
+                                serde_json::to_string(&params)?.into(),
+                            )
+                            .await?;
+                    }
+                    transition_task(
+                        &self.store,
+                        &task.key(),
+                        TaskState::Blocked,
+                        TaskState::Pending,
+                        now_millis(),
+                    )
+                    .await?
+                    .then(|| TaskRecord {
+                        state: TaskState::Pending,
+                        params,
+                        ..task.clone()
+                    })
+                }
+                // Unblocked on an earlier delivery of this event whose
+                // publish may have failed; the idempotency key makes the
+                // repeat a no-op otherwise.
+                TaskState::Pending if task.retry_count == 0 => Some(task.clone()),
+                _ => None,
+            };
+            if let Some(ready) = ready {
+                publish_queued(self.transport.as_ref(), &ready).await?;
+            }
+        }
+
+        if done.len() == tasks.len()
+            && transition_job(
+                &self.store,
+                &job.key(),
+                JobState::Running,
+                JobState::Completed,
+                now_millis(),
+            )
+            .await?
+        {
+            self.emit(
+                &jobs::completed(&job.job_type),
+                "completed",
+                &job.job_id,
+                JobCompleted {
+                    job_id: job.job_id.clone(),
+                    org_id: job.org_id.clone(),
+                    job_type: job.job_type.clone(),
+                    completed_at: now_millis(),
+                    result_ref: None,
+                },
+            )
+            .await?;
+            tracing::info!(job_id = %job.job_id, "job completed");
+        }
+        Ok(())
+    }
+
+    async fn fail_job(
+        &self,
+        job: &JobRecord,
+        tasks: &[TaskRecord],
+        failed: &TaskRecord,
+        error: String,
+    ) -> Result<(), JobsError> {
+        let failed_count = tasks
+            .iter()
+            .filter(|t| t.state == TaskState::Failed)
+            .count();
+        self.store
+            .set_field(
+                &job.key(),
+                fields::job::FAILED_COUNT,
+                failed_count.to_string().into(),
+            )
+            .await?;
+
+        let won = transition_job(
+            &self.store,
+            &job.key(),
+            JobState::Running,
+            JobState::Failed,
+            now_millis(),
+        )
+        .await?;
+        if won {
+            self.store
+                .set_field(
+                    &job.key(),
+                    fields::job::ERROR,
+                    format!("task {} failed: {error}", failed.task_name).into(),
+                )
+                .await?;
+        }
+
+        // Cancel whatever has not been claimed yet, on every delivery, in
+        // case an earlier attempt stopped halfway.
+        if won || job.state == JobState::Failed {
+            for task in tasks
+                .iter()
+                .filter(|t| matches!(t.state, TaskState::Blocked | TaskState::Pending))
+            {
+                transition_task(
+                    &self.store,
+                    &task.key(),
+                    task.state,
+                    TaskState::Cancelled,
+                    now_millis(),
+                )
+                .await?;
+            }
+        }
+
+        if won {
+            self.emit(
+                &jobs::failed(&job.job_type),
+                "failed",
+                &job.job_id,
+                JobFailed {
+                    job_id: job.job_id.clone(),
+                    org_id: job.org_id.clone(),
+                    job_type: job.job_type.clone(),
+                    failed_at: now_millis(),
+                    failed_task_id: failed.task_id.clone(),
+                    error,
+                },
+            )
+            .await?;
+            tracing::warn!(job_id = %job.job_id, task = %failed.task_name, "job failed");
+        }
+        Ok(())
+    }
+
+    async fn emit<T: serde::Serialize>(
+        &self,
+        subject: &str,
+        event: &str,
+        job_id: &JobId,
+        data: T,
+    ) -> Result<(), JobsError> {
+        self.emitter
+            .emit_traced(
+                subject,
+                1,
+                format!("{job_id}-{event}"),
+                data,
+                job_id.as_str(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+/// Publish `TaskQueued` for `task` to `gbe.tasks.{task_type}.queue`, traced
+/// by its job ID. The publish is idempotent per task and `retry_count`.
+pub(crate) async fn publish_queued(
+    transport: &dyn Transport,
+    task: &TaskRecord,
+) -> Result<String, JobsError> {
+    let key = format!("{}-queued-{}", task.task_id, task.retry_count);
+    let payload = DomainPayload::new(
+        1,
+        key.clone(),
+        TaskQueued {
+            task_id: task.task_id.clone(),
+            job_id: task.job_id.clone(),
+            org_id: task.org_id.clone(),
+            task_type: task.task_type.clone(),
+            params: task.params.clone(),
+            retry_count: task.retry_count,
+        },
+    );
+    let message_id = transport
+        .publish(
+            &tasks::queue(task.task_type.as_str()),
+            payload.to_bytes()?,
+            Some(PublishOpts {
+                trace_id: Some(task.job_id.to_string()),
+                idempotency_key: Some(key),
+                ..Default::default()
+            }),
+        )
+        .await?;
+    Ok(message_id)
+}
+
+/// The task fields every terminal event carries (`TaskCompleted`,
+/// `TaskFailed`).
+#[derive(serde::Deserialize)]
+struct TerminalTask {
+    task_id: TaskId,
+    task_type: TaskType,
+}
+
+struct TerminalHandler {
+    driver: Arc<JobDriver>,
+}
+
+#[async_trait]
+impl TypedMessageHandler<TerminalTask> for TerminalHandler {
+    async fn handle(
+        &self,
+        payload: DomainPayload<TerminalTask>,
+        _envelope: &Envelope,
+    ) -> HandlerOutcome {
+        let event = payload.data;
+        match self
+            .driver
+            .on_terminal(&event.task_type, &event.task_id)
+            .await
+        {
+            Ok(()) => HandlerOutcome::Ack,
+            Err(e) => {
+                // Redeliver so the job still advances once the error clears.
+                tracing::warn!(task_id = %event.task_id, "driver: {e}");
+                HandlerOutcome::Nak(None)
+            }
+        }
+    }
+}
+
+fn new_id(prefix: &str) -> String {
+    format!("{prefix}_{}", ulid::Ulid::new().to_string().to_lowercase())
+}
@@ -0,0 +1,23 @@
+use thiserror::Error;
+
+use gbe_jobs_domain::JobsDomainError;
+use gbe_nexus::TransportError;
+use gbe_state_store::StateStoreError;
+
+#[derive(Debug, Error)]
+pub enum JobsError {
+    #[error("transport error: {0}")]
+    Transport(#[from] TransportError),
+
+    #[error("state store error: {0}")]
+    State(#[from] StateStoreError),
+
+    #[error("domain error: {0}")]
+    Domain(#[from] JobsDomainError),
+
+    #[error("malformed record {key}: {reason}")]
+    MalformedRecord { key: String, reason: String },
+
+    #[error("serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
@@ -0,0 +1,21 @@
+//! Job orchestration.
+//!
+//! The driver turns a `JobDefinition` into job and task records in the
+//! `StateStore`, queues tasks on `gbe.tasks.{type}.queue` as their
+//! dependencies complete, and reports job outcomes on `gbe.jobs.{type}.*`.
+//! The watcher requeues or fails tasks whose claim has timed out, settling
+//! them through `fail_attempt` as workers do with failed attempts, and
+//! resends the events of tasks left idle after a state change.
+//! Records only change state through `compare_and_swap`; see `record`.
+
+mod attempt;
+mod driver;
+mod error;
+pub mod record;
+mod watcher;
+
+pub use attempt::{emit_failed, fail_attempt};
+pub use driver::{DRIVER_COMPONENT, DriverConfig, JobDriver};
+pub use error::JobsError;
+pub use record::{JobRecord, TaskRecord};
+pub use watcher::{WATCHER_COMPONENT, WatchReport, Watcher, WatcherConfig};
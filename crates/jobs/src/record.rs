@@ -0,0 +1,587 @@
+//! Job and task records in the `StateStore`.
+//!
+//! Every field is stored as UTF-8 text: states as their `as_str()` name,
+//! counts and unix-millis timestamps in decimal, collections as JSON.
+//! `state` only ever changes through `compare_and_swap` (or
+//! `compare_and_set_fields`, which settles an attempt together with its
+//! fields), so concurrent drivers, workers and watchers agree on who made
+//! each transition.
+
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+
+use bytes::Bytes;
+use serde::de::DeserializeOwned;
+
+use gbe_jobs_domain::keys::{self, fields};
+use gbe_jobs_domain::{
+    JobId, JobState, OrgId, TaskId, TaskOutcome, TaskParams, TaskState, TaskType,
+};
+use gbe_state_store::{Record, StateStore};
+
+use crate::error::JobsError;
+
+/// A job record, stored at `keys::job_key(job_type, job_id)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JobRecord {
+    pub job_id: JobId,
+    pub job_type: String,
+    pub org_id: OrgId,
+    pub state: JobState,
+    pub task_count: u32,
+    pub completed_count: u32,
+    pub failed_count: u32,
+    pub created_at: u64,
+    pub updated_at: u64,
+    pub error: Option<String>,
+    pub definition_ref: Option<String>,
+}
+
+impl JobRecord {
+    #[must_use]
+    pub fn key(&self) -> String {
+        keys::job_key(&self.job_type, self.job_id.as_str())
+    }
+
+    /// Load the record of `job_id`, if it exists.
+    ///
+    /// # Errors
+    /// Returns an error if the store fails or the record is malformed.
+    pub async fn load(
+        store: &Arc<dyn StateStore>,
+        job_type: &str,
+        job_id: &JobId,
+    ) -> Result<Option<Self>, JobsError> {
+        let key = keys::job_key(job_type, job_id.as_str());
+        match store.get(&key).await? {
+            Some(record) => Ok(Some(Self::from_record(&key, &record)?)),
+            None => Ok(None),
+        }
+    }
+
+    #[must_use]
+    pub fn to_record(&self) -> Record {
+        let mut out = Fields::default();
+        out.set(fields::job::STATE, self.state.as_str());
+        out.set(fields::job::JOB_TYPE, &self.job_type);
+        out.set(fields::job::JOB_ID, self.job_id.as_str());
+        out.set(fields::job::ORG_ID, self.org_id.as_str());
+        out.set(fields::job::TASK_COUNT, self.task_count.to_string());
+        out.set(
+            fields::job::COMPLETED_COUNT,
+            self.completed_count.to_string(),
+        );
+        out.set(fields::job::FAILED_COUNT, self.failed_count.to_string());
+        out.set(fields::job::CREATED_AT, self.created_at.to_string());
+        out.set(fields::job::UPDATED_AT, self.updated_at.to_string());
+        out.set_opt(fields::job::ERROR, self.error.as_deref());
+        out.set_opt(fields::job::DEFINITION_REF, self.definition_ref.as_deref());
+        out.into_record()
+    }
+
+    /// # Errors
+    /// Returns `JobsError::MalformedRecord` if a field is missing or invalid.
+    pub fn from_record(key: &str, record: &Record) -> Result<Self, JobsError> {
+        let r = FieldReader { key, record };
+        Ok(Self {
+            job_id: r.parse_with(fields::job::JOB_ID, JobId::new)?,
+            job_type: r.text(fields::job::JOB_TYPE)?.to_string(),
+            org_id: r.parse_with(fields::job::ORG_ID, OrgId::new)?,
+            state: r.parse(fields::job::STATE)?,
+            task_count: r.parse(fields::job::TASK_COUNT)?,
+            completed_count: r.parse(fields::job::COMPLETED_COUNT)?,
+            failed_count: r.parse(fields::job::FAILED_COUNT)?,
+            created_at: r.parse(fields::job::CREATED_AT)?,
+            updated_at: r.parse(fields::job::UPDATED_AT)?,
+            error: r.opt_text(fields::job::ERROR)?.map(String::from),
+            definition_ref: r.opt_text(fields::job::DEFINITION_REF)?.map(String::from),
+        })
+    }
+}
+
+/// A task record, stored at `keys::task_key(task_type, task_id)` and indexed
+/// under `keys::job_task_index_key(job_id, task_name)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TaskRecord {
+    pub task_id: TaskId,
+    pub task_type: TaskType,
+    pub task_name: String,
+    pub job_id: JobId,
+    pub job_type: String,
+    pub org_id: OrgId,
+    pub state: TaskState,
+    /// Names of the tasks this one waits for.
+    pub depends_on: Vec<String>,
+    /// Static params from the definition; refs in `input_from` are merged
+    /// in when the task is queued.
+    pub params: TaskParams,
+    pub input_from: HashMap<String, String>,
+    pub retry_count: u32,
+    pub max_retries: u32,
+    pub timeout_secs: u64,
+    /// Deadline (unix millis) of the current claim, set by the worker.
+    pub timeout_at: Option<u64>,
+    pub worker: Option<String>,
+    pub error: Option<String>,
+    /// What the worker reported, once the task is terminal.
+    pub outcome: Option<TaskOutcome>,
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+impl TaskRecord {
+    #[must_use]
+    pub fn key(&self) -> String {
+        keys::task_key(self.task_type.as_str(), self.task_id.as_str())
+    }
+
+    /// Load the record of `task_id`, if it exists.
+    ///
+    /// # Errors
+    /// Returns an error if the store fails or the record is malformed.
+    pub async fn load(
+        store: &Arc<dyn StateStore>,
+        task_type: &TaskType,
+        task_id: &TaskId,
+    ) -> Result<Option<Self>, JobsError> {
+        let key = keys::task_key(task_type.as_str(), task_id.as_str());
+        match store.get(&key).await? {
+            Some(record) => Ok(Some(Self::from_record(&key, &record)?)),
+            None => Ok(None),
+        }
+    }
+
+    /// Load every task of `job_id` through the job's task index.
+    ///
+    /// # Errors
+    /// Returns an error if the store fails, or an indexed task is missing or
+    /// malformed.
+    pub async fn load_for_job(
+        store: &Arc<dyn StateStore>,
+        job_id: &JobId,
+    ) -> Result<Vec<Self>, JobsError> {
+        let mut tasks = Vec::new();
+        for (key, index) in store
+            .scan(&keys::job_tasks_prefix(job_id.as_str()), None)
+            .await?
+        {
+            let r = FieldReader {
+                key: &key,
+                record: &index,
+            };
+            let task_type = r.parse_with(fields::task::TASK_TYPE, TaskType::new)?;
+            let task_id = r.parse_with(fields::task::TASK_ID, TaskId::new)?;
+            let task = Self::load(store, &task_type, &task_id)
+                .await?
+                .ok_or_else(|| JobsError::MalformedRecord {
+                    key: key.clone(),
+                    reason: format!("indexed task {task_id} does not exist"),
+                })?;
+            tasks.push(task);
+        }
+        Ok(tasks)
+    }
+
+    /// The job task index entry pointing at this task.
+    #[must_use]
+    pub fn index_record(&self) -> Record {
+        let mut out = Fields::default();
+        out.set(fields::task::TASK_ID, self.task_id.as_str());
+        out.set(fields::task::TASK_TYPE, self.task_type.as_str());
+        out.into_record()
+    }
+
+    /// # Errors
+    /// Returns an error if `params`, `input_from` or `outcome` cannot be
+    /// serialized.
+    pub fn to_record(&self) -> Result<Record, JobsError> {
+        let mut out = Fields::default();
+        out.set(fields::task::STATE, self.state.as_str());
+        out.set(fields::task::TASK_TYPE, self.task_type.as_str());
+        out.set(fields::task::TASK_ID, self.task_id.as_str());
+        out.set(fields::task::TASK_NAME, &self.task_name);
+        out.set(fields::task::JOB_ID, self.job_id.as_str());
+        out.set(fields::task::JOB_TYPE, &self.job_type);
+        out.set(fields::task::ORG_ID, self.org_id.as_str());
+        out.set(
+            fields::task::DEPENDS_ON,
+            serde_json::to_string(&self.depends_on)?,
+        );
+        out.set(fields::task::PARAMS, serde_json::to_string(&self.params)?);
+        out.set(
+            fields::task::INPUT_FROM,
+            serde_json::to_string(&self.input_from)?,
+        );
+        out.set(fields::task::RETRY_COUNT, self.retry_count.to_string());
+        out.set(fields::task::MAX_RETRIES, self.max_retries.to_string());
+        out.set(fields::task::TIMEOUT_SECS, self.timeout_secs.to_string());
+        out.set_opt(
+            fields::task::TIMEOUT_AT,
+            self.timeout_at.map(|t| t.to_string()).as_deref(),
+        );
+        out.set_opt(fields::task::WORKER, self.worker.as_deref());
+        out.set_opt(fields::task::ERROR, self.error.as_deref());
+        if let Some(outcome) = &self.outcome {
+            out.set(fields::task::OUTCOME, serde_json::to_string(outcome)?);
+        }
+        out.set(fields::task::CREATED_AT, self.created_at.to_string());
+        out.set(fields::task::UPDATED_AT, self.updated_at.to_string());
+        Ok(out.into_record())
+    }
+
+    /// # Errors
+    /// Returns `JobsError::MalformedRecord` if a field is missing or invalid.
+    pub fn from_record(key: &str, record: &Record) -> Result<Self, JobsError> {
+        let r = FieldReader { key, record };
+        Ok(Self {
+            task_id: r.parse_with(fields::task::TASK_ID, TaskId::new)?,
+            task_type: r.parse_with(fields::task::TASK_TYPE, TaskType::new)?,
+            task_name: r.text(fields::task::TASK_NAME)?.to_string(),
+            job_id: r.parse_with(fields::task::JOB_ID, JobId::new)?,
+            job_type: r.text(fields::task::JOB_TYPE)?.to_string(),
+            org_id: r.parse_with(fields::task::ORG_ID, OrgId::new)?,
+            state: r.parse(fields::task::STATE)?,
+            depends_on: r.json(fields::task::DEPENDS_ON)?,
+            params: r.json(fields::task::PARAMS)?,
+            input_from: r.json(fields::task::INPUT_FROM)?,
+            retry_count: r.parse(fields::task::RETRY_COUNT)?,
+            max_retries: r.parse(fields::task::MAX_RETRIES)?,
+            timeout_secs: r.parse(fields::task::TIMEOUT_SECS)?,
+            timeout_at: r
+                .opt_text(fields::task::TIMEOUT_AT)?
+                .map(|t| r.parse_text(fields::task::TIMEOUT_AT, t))
+                .transpose()?,
+            worker: r.opt_text(fields::task::WORKER)?.map(String::from),
+            error: r.opt_text(fields::task::ERROR)?.map(String::from),
+            outcome: r
+                .opt_text(fields::task::OUTCOME)?
+                .map(|_| r.json(fields::task::OUTCOME))
+                .transpose()?,
+            created_at: r.parse(fields::task::CREATED_AT)?,
+            updated_at: r.parse(fields::task::UPDATED_AT)?,
+        })
+    }
+}
+
+/// Move the job at `key` from `from` to `to` with `compare_and_swap`.
+/// Returns `false` if the job was no longer in `from`, i.e. someone else
+/// made a transition first.
+///
+/// # Errors
+/// Returns an error if the state machine forbids the transition or the
+/// store fails.
+pub async fn transition_job(
+    store: &Arc<dyn StateStore>,
+    key: &str,
+    from: JobState,
+    to: JobState,
+    now_ms: u64,
+) -> Result<bool, JobsError> {
+    from.transition_to(to)?;
+    transition(store, key, from.as_str(), to.as_str(), now_ms).await
+}
+
+/// Move the task at `key` from `from` to `to` with `compare_and_swap`.
+/// Returns `false` if the task was no longer in `from`.
+///
+/// # Errors
+/// Returns an error if the state machine forbids the transition or the
+/// store fails.
+pub async fn transition_task(
+    store: &Arc<dyn StateStore>,
+    key: &str,
+    from: TaskState,
+    to: TaskState,
+    now_ms: u64,
+) -> Result<bool, JobsError> {
+    from.transition_to(to)?;
+    transition(store, key, from.as_str(), to.as_str(), now_ms).await
+}
+
+/// Claim `task`, which was read `pending`, for `worker`: move it to
+/// `claimed` and write `worker` and the claim's `timeout_at` in one
+/// `compare_and_set_fields`, so a claimed task always has a deadline the
+/// watcher can expire. Returns `false` if another worker claimed it first.
+///
+/// # Errors
+/// Returns an error if the state machine forbids the transition or the
+/// store fails.
+pub async fn claim_attempt(
+    store: &Arc<dyn StateStore>,
+    task: &TaskRecord,
+    worker: &str,
+    timeout_at: u64,
+    now_ms: u64,
+) -> Result<bool, JobsError> {
+    task.state.transition_to(TaskState::Claimed)?;
+    let expected = HashMap::from([
+        (
+            fields::task::STATE.to_string(),
+            Bytes::from_static(task.state.as_str().as_bytes()),
+        ),
+        (
+            fields::task::RETRY_COUNT.to_string(),
+            Bytes::from(task.retry_count.to_string()),
+        ),
+    ]);
+    let updates = HashMap::from([
+        (
+            fields::task::STATE.to_string(),
+            Bytes::from_static(TaskState::Claimed.as_str().as_bytes()),
+        ),
+        (
+            fields::task::WORKER.to_string(),
+            Bytes::from(worker.to_string()),
+        ),
+        (
+            fields::task::TIMEOUT_AT.to_string(),
+            Bytes::from(timeout_at.to_string()),
+        ),
+        (
+            fields::task::UPDATED_AT.to_string(),
+            Bytes::from(now_ms.to_string()),
+        ),
+    ]);
+    Ok(store
+        .compare_and_set_fields(&task.key(), expected, updates, &[])
+        .await?)
+}
+
+/// Settle the attempt at `task`: move it from `task.state` to `to`, write
+/// `updates` and `updated_at`, and drop the claim's `timeout_at`, all in one
+/// `compare_and_set_fields`.
+///
+/// The swap expects the attempt's `retry_count` as well as its state. Every
+/// requeue bumps it, so a stale `task` never matches a task that has been
+/// requeued and claimed again since. Returns `false`, writing nothing, if
+/// the attempt was already settled.
+///
+/// # Errors
+/// Returns an error if the state machine forbids the transition or the
+/// store fails.
+pub async fn settle_attempt(
+    store: &Arc<dyn StateStore>,
+    task: &TaskRecord,
+    to: TaskState,
+    now_ms: u64,
+    mut updates: HashMap<String, Bytes>,
+) -> Result<bool, JobsError> {
+    task.state.transition_to(to)?;
+    let expected = HashMap::from([
+        (
+            fields::task::STATE.to_string(),
+            Bytes::from_static(task.state.as_str().as_bytes()),
+        ),
+        (
+            fields::task::RETRY_COUNT.to_string(),
+            Bytes::from(task.retry_count.to_string()),
+        ),
+    ]);
+    updates.insert(
+        fields::task::STATE.to_string(),
+        Bytes::from_static(to.as_str().as_bytes()),
+    );
+    updates.insert(
+        fields::task::UPDATED_AT.to_string(),
+        Bytes::from(now_ms.to_string()),
+    );
+    Ok(store
+        .compare_and_set_fields(&task.key(), expected, updates, &[fields::task::TIMEOUT_AT])
+        .await?)
+}
+
+async fn transition(
+    store: &Arc<dyn StateStore>,
+    key: &str,
+    from: &'static str,
+    to: &'static str,
+    now_ms: u64,
+) -> Result<bool, JobsError> {
+    // Job and task records share the `state` and `updated_at` field names.
+    let swapped = store
+        .compare_and_swap(
+            key,
+            fields::task::STATE,
+            Bytes::from_static(from.as_bytes()),
+            Bytes::from_static(to.as_bytes()),
+        )
+        .await?;
+    if swapped {
+        store
+            .set_field(
+                key,
+                fields::task::UPDATED_AT,
+                Bytes::from(now_ms.to_string()),
+            )
+            .await?;
+    }
+    Ok(swapped)
+}
+
+#[derive(Default)]
+struct Fields(HashMap<String, Bytes>);
+
+impl Fields {
+    fn set(&mut self, field: &str, value: impl Into<String>) {
+        self.0
+            .insert(field.to_string(), Bytes::from(value.into().into_bytes()));
+    }
+
+    fn set_opt(&mut self, field: &str, value: Option<&str>) {
+        if let Some(value) = value {
+            self.set(field, value);
+        }
+    }
+
+    fn into_record(self) -> Record {
+        Record {
+            fields: self.0,
+            ttl: None,
+        }
+    }
+}
+
+struct FieldReader<'a> {
+    key: &'a str,
+    record: &'a Record,
+}
+
+impl<'a> FieldReader<'a> {
+    fn malformed(&self, reason: String) -> JobsError {
+        JobsError::MalformedRecord {
+            key: self.key.to_string(),
+            reason,
+        }
+    }
+
+    fn opt_text(&self, field: &str) -> Result<Option<&'a str>, JobsError> {
+        self.record
+            .fields
+            .get(field)
+            .map(|value| {
+                std::str::from_utf8(value)
+                    .map_err(|_| self.malformed(format!("field {field} is not UTF-8")))
+            })
+            .transpose()
+    }
+
+    fn text(&self, field: &str) -> Result<&'a str, JobsError> {
+        self.opt_text(field)?
+            .ok_or_else(|| self.malformed(format!("missing field {field}")))
+    }
+
+    fn parse_text<T>(&self, field: &str, text: &str) -> Result<T, JobsError>
+    where
+        T: FromStr,
+        T::Err: std::fmt::Display,
+    {
+        text.parse()
+            .map_err(|e| self.malformed(format!("field {field}: {e}")))
+    }
+
+    fn parse<T>(&self, field: &str) -> Result<T, JobsError>
+    where
+        T: FromStr,
+        T::Err: std::fmt::Display,
+    {
+        self.parse_text(field, self.text(field)?)
+    }
+
+    fn parse_with<T, E: std::fmt::Display>(
+        &self,
+        field: &str,
+        parse: impl FnOnce(&str) -> Result<T, E>,
+    ) -> Result<T, JobsError> {
+        parse(self.text(field)?).map_err(|e| self.malformed(format!("field {field}: {e}")))
+    }
+
+    fn json<T: DeserializeOwned>(&self, field: &str) -> Result<T, JobsError> {
+        serde_json::from_str(self.text(field)?)
+            .map_err(|e| self.malformed(format!("field {field}: {e}")))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn task() -> TaskRecord {
+        let mut params = TaskParams::default();
+        params
+            .entries
+            .insert("source".to_string(), "billing-api".to_string());
+        TaskRecord {
+            task_id: TaskId::new("task_fetch-1").unwrap(),
+            task_type: TaskType::new("data-fetch").unwrap(),
+            task_name: "fetch".to_string(),
+            job_id: JobId::new("job_daily-001").unwrap(),
+            job_type: "daily-report".to_string(),
+            org_id: OrgId::new("org_acme").unwrap(),
+            state: TaskState::Blocked,
+            depends_on: vec!["auth".to_string()],
+            params,
+            input_from: HashMap::from([("token".to_string(), "auth.data.token".to_string())]),
+            retry_count: 1,
+            max_retries: 3,
+            timeout_secs: 300,
+            timeout_at: None,
+            worker: None,
+            error: None,
+            outcome: Some(TaskOutcome::Completed {
+                output: vec![],
+                result_ref: None,
+                data: Some(serde_json::json!({"rows": 2})),
+            }),
+            created_at: 1_707_934_567_000,
+            updated_at: 1_707_934_568_000,
+        }
+    }
+
+    #[test]
+    fn test_task_record_round_trip() {
+        let original = task();
+        let record = original.to_record().unwrap();
+        assert_eq!(record.fields[fields::task::STATE], "blocked");
+        assert_eq!(record.fields[fields::task::DEPENDS_ON], r#"["auth"]"#);
+        assert!(!record.fields.contains_key(fields::task::TIMEOUT_AT));
+
+        let back = TaskRecord::from_record(&original.key(), &record).unwrap();
+        assert_eq!(back, original);
+    }
+
+    #[test]
+    fn test_job_record_round_trip() {
+        let original = JobRecord {
+            job_id: JobId::new("job_daily-001").unwrap(),
+            job_type: "daily-report".to_string(),
+            org_id: OrgId::new("org_acme").unwrap(),
+            state: JobState::Running,
+            task_count: 3,
+            completed_count: 1,
+            failed_count: 0,
+            created_at: 1_707_934_567_000,
+            updated_at: 1_707_934_568_000,
+            error: None,
+            definition_ref: Some("defs/daily-report.yaml".to_string()),
+        };
+        let back = JobRecord::from_record(&original.key(), &original.to_record()).unwrap();
+        assert_eq!(back, original);
+    }
+
+    #[test]
+    fn test_malformed_record_names_key_and_field() {
+        let original = task();
+        let mut record = original.to_record().unwrap();
+        record
+            .fields
+            .insert(fields::task::STATE.to_string(), Bytes::from("done"));
+        record.fields.remove(fields::task::ORG_ID);
+
+        let err = TaskRecord::from_record("gbe.state.tasks.x.task_1", &record).unwrap_err();
+        assert!(
+            matches!(err, JobsError::MalformedRecord { ref key, .. } if key == "gbe.state.tasks.x.task_1")
+        );
+        assert!(err.to_string().contains("org_id"));
+    }
+}
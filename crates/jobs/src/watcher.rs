@@ -0,0 +1,281 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+use bytes::Bytes;
+use tokio_util::sync::CancellationToken;
+
+use gbe_jobs_domain::keys::{self, fields};
+use gbe_jobs_domain::{JobState, TaskId, TaskState};
+use gbe_nexus::{EventEmitter, Transport, now_millis};
+use gbe_state_store::{ScanFilter, ScanOp, StateStore};
+
+use crate::attempt::{emit_failed, fail_attempt};
+use crate::driver::publish_queued;
+use crate::error::JobsError;
+use crate::record::{JobRecord, TaskRecord};
+
+/// Component name used in lifecycle events.
+pub const WATCHER_COMPONENT: &str = "watcher";
+
+/// Configuration for a `Watcher`.
+pub struct WatcherConfig {
+    /// Time between checks.
+    pub interval: Duration,
+    /// Instance ID reported in emitted events.
+    pub instance_id: String,
+    /// How long a task may sit `pending`, or `failed` under a running job,
+    /// before its event is sent again. Idle tasks are looked for once per
+    /// `resend_after`, so an event may take up to twice that to go out.
+    pub resend_after: Duration,
+}
+
+impl Default for WatcherConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(30),
+            instance_id: format!("wtc-{}", ulid::Ulid::new().to_string().to_lowercase()),
+            resend_after: Duration::from_secs(300),
+        }
+    }
+}
+
+/// Outcome of one `Watcher::check`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct WatchReport {
+    /// Timed-out tasks moved back to `pending` and queued again.
+    pub requeued: Vec<TaskId>,
+    /// Timed-out tasks failed for good, their retries exhausted.
+    pub failed: Vec<TaskId>,
+    /// Idle tasks whose `TaskQueued` or `TaskFailed` was sent again.
+    pub resent: Vec<TaskId>,
+}
+
+/// Recovers tasks whose worker went away: finds `claimed` or `running`
+/// tasks past their `timeout_at` and either moves them back to `pending`
+/// and republishes `TaskQueued` with `retry_count + 1`, or fails them once
+/// `max_retries` is exhausted. Either way a `TaskFailed` goes to the task's
+/// terminal stream, where the driver picks up the terminal failures.
+///
+/// Both moves go through `settle_attempt`, which expects the state and
+/// `retry_count` that were read: if the worker settles the attempt first,
+/// or another watcher recovers it, or it was released and claimed again
+/// since, the move is dropped and the record is left as they wrote it.
+///
+/// It also covers an event lost after a state change: a task left `pending`
+/// for `resend_after` has its `TaskQueued` published again, and a task
+/// `failed` that long while its job still runs has its `TaskFailed` sent
+/// again. Both carry the original idempotency key. This scans pending
+/// tasks and running jobs, so it runs once per `resend_after` rather than
+/// every `interval`.
+pub struct Watcher {
+    store: Arc<dyn StateStore>,
+    emitter: EventEmitter,
+    config: WatcherConfig,
+    /// When (unix millis) the next `check` looks for idle tasks.
+    resend_due: AtomicU64,
+}
+
+impl Watcher {
+    pub fn new(
+        transport: Arc<dyn Transport>,
+        store: Arc<dyn StateStore>,
+        config: WatcherConfig,
+    ) -> Self {
+        let emitter = EventEmitter::new(transport, WATCHER_COMPONENT, config.instance_id.clone());
+        Self {
+            store,
+            emitter,
+            config,
+            resend_due: AtomicU64::new(0),
+        }
+    }
+
+    /// Recover every task whose claim has expired and, at most once per
+    /// `resend_after`, resend the events of idle tasks.
+    ///
+    /// Settled and requeued tasks have no `timeout_at`, so the expiry scan
+    /// only matches claims that ran out. A failure on one task is logged
+    /// and does not stop the others.
+    ///
+    /// # Errors
+    /// Returns an error if the records cannot be scanned.
+    pub async fn check(&self) -> Result<WatchReport, JobsError> {
+        let now = now_millis();
+        let expired = self
+            .store
+            .scan(
+                &keys::tasks_prefix(),
+                Some(ScanFilter {
+                    field: fields::task::TIMEOUT_AT.to_string(),
+                    op: ScanOp::Lt,
+                    value: Bytes::from(now.to_string()),
+                    max_results: None,
+                }),
+            )
+            .await?;
+
+        let mut report = WatchReport::default();
+        for (key, record) in expired {
+            let task = match TaskRecord::from_record(&key, &record) {
+                Ok(task) => task,
+                Err(e) => {
+                    tracing::warn!(key = %key, "skipping task record: {e}");
+                    continue;
+                }
+            };
+            if matches!(task.state, TaskState::Claimed | TaskState::Running) {
+                self.recover(&task, &mut report).await;
+            }
+        }
+
+        if now >= self.resend_due.load(Ordering::Relaxed) {
+            self.resend_due
+                .store(now.saturating_add(self.resend_after()), Ordering::Relaxed);
+            self.resend_idle(now, &mut report).await?;
+        }
+        Ok(report)
+    }
+
+    /// Check every `interval` until `token` is cancelled.
+    pub async fn run(&self, token: CancellationToken) {
+        let mut ticker = tokio::time::interval(self.config.interval);
+        loop {
+            tokio::select! {
+                () = token.cancelled() => break,
+                _ = ticker.tick() => {
+                    if let Err(e) = self.check().await {
+                        tracing::warn!("watcher check failed: {e}");
+                    }
+                }
+            }
+        }
+    }
+
+    /// Resend `TaskQueued` for idle `pending` tasks, and `TaskFailed` for
+    /// idle `failed` tasks of jobs still running.
+    async fn resend_idle(&self, now: u64, report: &mut WatchReport) -> Result<(), JobsError> {
+        for (key, record) in self
+            .store
+            .scan(
+                &keys::tasks_prefix(),
+                Some(state_is(TaskState::Pending.as_str())),
+            )
+            .await?
+        {
+            let task = match TaskRecord::from_record(&key, &record) {
+                Ok(task) => task,
+                Err(e) => {
+                    tracing::warn!(key = %key, "skipping task record: {e}");
+                    continue;
+                }
+            };
+            if !self.is_idle(&task, now) {
+                continue;
+            }
+            match self.resend_queued(&task, now).await {
+                Ok(true) => report.resent.push(task.task_id),
+                Ok(false) => {}
+                Err(e) => {
+                    tracing::warn!(task_id = %task.task_id, "resending TaskQueued failed: {e}")
+                }
+            }
+        }
+
+        // A lost `TaskFailed` leaves its job running, so only running jobs
+        // are looked at, not every failed task ever recorded.
+        for (key, record) in self
+            .store
+            .scan(
+                &keys::jobs_prefix(),
+                Some(state_is(JobState::Running.as_str())),
+            )
+            .await?
+        {
+            let job = match JobRecord::from_record(&key, &record) {
+                Ok(job) => job,
+                Err(e) => {
+                    tracing::warn!(key = %key, "skipping job record: {e}");
+                    continue;
+                }
+            };
+            let tasks = match TaskRecord::load_for_job(&self.store, &job.job_id).await {
+                Ok(tasks) => tasks,
+                Err(e) => {
+                    tracing::warn!(job_id = %job.job_id, "loading tasks failed: {e}");
+                    continue;
+                }
+            };
+            for task in tasks {
+                if task.state != TaskState::Failed || !self.is_idle(&task, now) {
+                    continue;
+                }
+                let error = task.error.as_deref().unwrap_or_default();
+                match emit_failed(&self.emitter, &task, error, task.updated_at).await {
+                    Ok(()) => {
+                        tracing::info!(task_id = %task.task_id, job_id = %job.job_id, "resent TaskFailed for running job");
+                        report.resent.push(task.task_id);
+                    }
+                    Err(e) => {
+                        tracing::warn!(task_id = %task.task_id, "resending TaskFailed failed: {e}")
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[allow(clippy::cast_possible_truncation)] // configured durations are far below u64::MAX ms
+    fn resend_after(&self) -> u64 {
+        self.config.resend_after.as_millis() as u64
+    }
+
+    fn is_idle(&self, task: &TaskRecord, now: u64) -> bool {
+        task.updated_at.saturating_add(self.resend_after()) < now
+    }
+
+    /// Publish `TaskQueued` again for a task left `pending`. Bumping
+    /// `updated_at` first keeps another watcher from doing the same, and
+    /// restarts the wait before the next resend. Returns `false` if the
+    /// task changed since it was read.
+    async fn resend_queued(&self, task: &TaskRecord, now: u64) -> Result<bool, JobsError> {
+        if !self
+            .store
+            .compare_and_swap(
+                &task.key(),
+                fields::task::UPDATED_AT,
+                Bytes::from(task.updated_at.to_string()),
+                Bytes::from(now.to_string()),
+            )
+            .await?
+        {
+            return Ok(false);
+        }
+        publish_queued(self.emitter.transport().as_ref(), task).await?;
+        tracing::info!(task_id = %task.task_id, retry_count = task.retry_count, "republished idle pending task");
+        Ok(true)
+    }
+
+    /// Requeue or fail a timed-out task, unless it changed state since it
+    /// was read.
+    async fn recover(&self, task: &TaskRecord, report: &mut WatchReport) {
+        let error = format!("timed out {} after {}s", task.state, task.timeout_secs);
+        match fail_attempt(&self.store, &self.emitter, task, &error, None).await {
+            Ok(Some(TaskState::Pending)) => report.requeued.push(task.task_id.clone()),
+            Ok(Some(_)) => report.failed.push(task.task_id.clone()),
+            Ok(None) => {}
+            Err(e) => tracing::warn!(task_id = %task.task_id, "task recovery failed: {e}"),
+        }
+    }
+}
+
+/// Scan filter for records in `state`; job and task records share the
+/// field name.
+fn state_is(state: &str) -> ScanFilter {
+    ScanFilter {
+        field: fields::task::STATE.to_string(),
+        op: ScanOp::Eq,
+        value: Bytes::from(state.to_string()),
+        max_results: None,
+    }
+}
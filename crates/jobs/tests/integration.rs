@@ -0,0 +1,722 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use gbe_jobs::{
+    DriverConfig, JobDriver, JobRecord, TaskRecord, Watcher, WatcherConfig, fail_attempt,
+};
+use gbe_jobs_domain::keys::{self, fields};
+use gbe_jobs_domain::payloads::{
+    JobCompleted, JobCreated, JobFailed, TaskCompleted, TaskFailed, TaskQueued,
+};
+use gbe_jobs_domain::subjects::{jobs, tasks};
+use gbe_jobs_domain::{
+    InputStrategy, JobDefinition, JobId, JobState, OrgId, TaskDefinition, TaskOutcome, TaskParams,
+    TaskState, TaskType,
+};
+use gbe_nexus::{
+    DomainPayload, Envelope, EventEmitter, Message, MessageHandler, StartPosition, SubscribeOpts,
+    Subscription, Transport, TransportError, now_millis,
+};
+use gbe_nexus_memory::{MemoryTransport, MemoryTransportConfig};
+use gbe_state_store::StateStore;
+use gbe_state_store_memory::MemoryStateStore;
+
+struct Harness {
+    transport: Arc<dyn Transport>,
+    store: Arc<dyn StateStore>,
+    driver: Arc<JobDriver>,
+}
+
+fn harness() -> Harness {
+    let transport: Arc<dyn Transport> =
+        Arc::new(MemoryTransport::new(MemoryTransportConfig::default()));
+    let store: Arc<dyn StateStore> = Arc::new(MemoryStateStore::new());
+    let driver = Arc::new(JobDriver::new(
+        transport.clone(),
+        store.clone(),
+        DriverConfig::default(),
+    ));
+    Harness {
+        transport,
+        store,
+        driver,
+    }
+}
+
+fn task(name: &str, task_type: &str, depends_on: &[&str]) -> TaskDefinition {
+    TaskDefinition {
+        name: name.to_string(),
+        task_type: TaskType::new(task_type).unwrap(),
+        depends_on: depends_on.iter().map(ToString::to_string).collect(),
+        params: TaskParams::default(),
+        input_from: HashMap::new(),
+        timeout_secs: None,
+        max_retries: None,
+    }
+}
+
+/// fetch -> { transform, audit } -> send
+fn diamond() -> JobDefinition {
+    JobDefinition {
+        v: 1,
+        name: "Daily Report".to_string(),
+        job_type: "daily-report".to_string(),
+        tasks: vec![
+            task("fetch", "data-fetch", &[]),
+            task("transform", "data-transform", &["fetch"]),
+            task("audit", "data-audit", &["fetch"]),
+            task("send", "email-send", &["transform", "audit"]),
+        ],
+    }
+}
+
+fn org() -> OrgId {
+    OrgId::new("org_acme").unwrap()
+}
+
+struct ChannelHandler {
+    tx: mpsc::UnboundedSender<Envelope>,
+}
+
+#[async_trait]
+impl MessageHandler for ChannelHandler {
+    async fn handle(&self, msg: &dyn Message) -> Result<(), TransportError> {
+        let _ = self.tx.send(msg.envelope().clone());
+        msg.ack().await
+    }
+}
+
+async fn watch(
+    transport: &Arc<dyn Transport>,
+    subject: &str,
+) -> (Box<dyn Subscription>, mpsc::UnboundedReceiver<Envelope>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let sub = transport
+        .subscribe(
+            subject,
+            "test-observer",
+            Box::new(ChannelHandler { tx }),
+            Some(SubscribeOpts {
+                start_from: StartPosition::Earliest,
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+    (sub, rx)
+}
+
+async fn recv<T: serde::de::DeserializeOwned>(rx: &mut mpsc::UnboundedReceiver<Envelope>) -> T {
+    let env = tokio::time::timeout(Duration::from_secs(2), rx.recv())
+        .await
+        .expect("timed out waiting for event")
+        .unwrap();
+    DomainPayload::<T>::from_bytes(&env.payload).unwrap().data
+}
+
+async fn assert_quiet(rx: &mut mpsc::UnboundedReceiver<Envelope>) {
+    let extra = tokio::time::timeout(Duration::from_millis(100), rx.recv()).await;
+    assert!(extra.is_err(), "unexpected event: {extra:?}");
+}
+
+async fn load_task(store: &Arc<dyn StateStore>, queued: &TaskQueued) -> TaskRecord {
+    TaskRecord::load(store, &queued.task_type, &queued.task_id)
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+async fn load_job(store: &Arc<dyn StateStore>, job_id: &JobId) -> JobRecord {
+    JobRecord::load(store, "daily-report", job_id)
+        .await
+        .unwrap()
+        .unwrap()
+}
+
+async fn cas(store: &Arc<dyn StateStore>, key: &str, from: TaskState, to: TaskState) {
+    assert!(
+        store
+            .compare_and_swap(
+                key,
+                fields::task::STATE,
+                Bytes::from(from.as_str()),
+                Bytes::from(to.as_str()),
+            )
+            .await
+            .unwrap()
+    );
+}
+
+fn completed(data: Option<serde_json::Value>) -> TaskOutcome {
+    TaskOutcome::Completed {
+        output: vec![],
+        result_ref: None,
+        data,
+    }
+}
+
+fn failed(error: &str) -> TaskOutcome {
+    TaskOutcome::Failed {
+        exit_code: 1,
+        error: error.to_string(),
+    }
+}
+
+/// Play the worker: claim and run `queued`, then record and report `outcome`.
+async fn run_task(h: &Harness, queued: &TaskQueued, outcome: TaskOutcome) {
+    let key = keys::task_key(queued.task_type.as_str(), queued.task_id.as_str());
+    cas(&h.store, &key, TaskState::Pending, TaskState::Claimed).await;
+    cas(&h.store, &key, TaskState::Claimed, TaskState::Running).await;
+
+    let mut updates = HashMap::from([(
+        fields::task::OUTCOME.to_string(),
+        Bytes::from(serde_json::to_string(&outcome).unwrap()),
+    )]);
+    let payload = match outcome {
+        TaskOutcome::Failed { error, .. } => {
+            cas(&h.store, &key, TaskState::Running, TaskState::Failed).await;
+            updates.insert(fields::task::ERROR.to_string(), Bytes::from(error.clone()));
+            DomainPayload::new(
+                1,
+                "failed",
+                TaskFailed {
+                    task_id: queued.task_id.clone(),
+                    job_id: queued.job_id.clone(),
+                    task_type: queued.task_type.clone(),
+                    failed_at: 0,
+                    error,
+                    retry_count: 0,
+                    max_retries: 0,
+                },
+            )
+            .to_bytes()
+        }
+        TaskOutcome::Completed { .. } => {
+            cas(&h.store, &key, TaskState::Running, TaskState::Completed).await;
+            DomainPayload::new(
+                1,
+                "completed",
+                TaskCompleted {
+                    task_id: queued.task_id.clone(),
+                    job_id: queued.job_id.clone(),
+                    task_type: queued.task_type.clone(),
+                    completed_at: 0,
+                    result_ref: None,
+                },
+            )
+            .to_bytes()
+        }
+    };
+    h.store.set_fields(&key, updates).await.unwrap();
+    h.transport
+        .publish(
+            &tasks::terminal(queued.task_type.as_str()),
+            payload.unwrap(),
+            None,
+        )
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_create_job_writes_records_and_queues_roots() {
+    let h = harness();
+    let (_created, mut created_rx) = watch(&h.transport, &jobs::created("daily-report")).await;
+    let (_queue, mut queue_rx) = watch(&h.transport, &tasks::queue("*")).await;
+
+    let job_id = h
+        .driver
+        .create_job(&diamond(), org(), Some("defs/daily.yaml".to_string()))
+        .await
+        .unwrap();
+
+    let job = load_job(&h.store, &job_id).await;
+    assert_eq!(job.state, JobState::Running);
+    assert_eq!(job.task_count, 4);
+    assert_eq!(job.definition_ref.as_deref(), Some("defs/daily.yaml"));
+
+    let tasks = TaskRecord::load_for_job(&h.store, &job_id).await.unwrap();
+    let states: HashMap<&str, TaskState> = tasks
+        .iter()
+        .map(|t| (t.task_name.as_str(), t.state))
+        .collect();
+    assert_eq!(states["fetch"], TaskState::Pending);
+    assert_eq!(states["transform"], TaskState::Blocked);
+    assert_eq!(states["send"], TaskState::Blocked);
+    assert!(
+        tasks
+            .iter()
+            .all(|t| t.max_retries == 3 && t.org_id == org())
+    );
+
+    let created: JobCreated = recv(&mut created_rx).await;
+    assert_eq!(created.job_id, job_id);
+    assert_eq!(created.task_ids.len(), 4);
+
+    let queued: TaskQueued = recv(&mut queue_rx).await;
+    assert_eq!(queued.job_id, job_id);
+    assert_eq!(queued.task_type.as_str(), "data-fetch");
+    assert_eq!(queued.retry_count, 0);
+    assert_quiet(&mut queue_rx).await;
+}
+
+#[tokio::test]
+async fn test_create_job_rejects_invalid_definition() {
+    let h = harness();
+    let mut definition = diamond();
+    definition.tasks[0].depends_on = vec!["send".to_string()];
+
+    assert!(h.driver.create_job(&definition, org(), None).await.is_err());
+    assert!(h.store.scan("gbe.state.", None).await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_job_runs_to_completion() {
+    let h = harness();
+    let _driver = h.driver.start().await.unwrap();
+    let (_completed, mut completed_rx) =
+        watch(&h.transport, &jobs::completed("daily-report")).await;
+    let (_queue, mut queue_rx) = watch(&h.transport, &tasks::queue("*")).await;
+
+    let job_id = h.driver.create_job(&diamond(), org(), None).await.unwrap();
+
+    let fetch: TaskQueued = recv(&mut queue_rx).await;
+    run_task(&h, &fetch, completed(None)).await;
+
+    // Both dependants of fetch are released together
+    let first: TaskQueued = recv(&mut queue_rx).await;
+    let second: TaskQueued = recv(&mut queue_rx).await;
+    let mut released = [first.task_type.to_string(), second.task_type.to_string()];
+    released.sort();
+    assert_eq!(released, ["data-audit", "data-transform"]);
+
+    run_task(&h, &first, completed(None)).await;
+    assert_quiet(&mut queue_rx).await;
+    assert_eq!(
+        load_job(&h.store, &job_id).await.completed_count,
+        2,
+        "send waits for both of its dependencies"
+    );
+
+    run_task(&h, &second, completed(None)).await;
+    let send: TaskQueued = recv(&mut queue_rx).await;
+    assert_eq!(send.task_type.as_str(), "email-send");
+    run_task(&h, &send, completed(None)).await;
+
+    let completed: JobCompleted = recv(&mut completed_rx).await;
+    assert_eq!(completed.job_id, job_id);
+    let job = load_job(&h.store, &job_id).await;
+    assert_eq!(job.state, JobState::Completed);
+    assert_eq!(job.completed_count, 4);
+}
+
+#[tokio::test]
+async fn test_failed_task_fails_job_and_cancels_the_rest() {
+    let h = harness();
+    let _driver = h.driver.start().await.unwrap();
+    let (_failed, mut failed_rx) = watch(&h.transport, &jobs::failed("daily-report")).await;
+    let (_queue, mut queue_rx) = watch(&h.transport, &tasks::queue("*")).await;
+
+    let job_id = h.driver.create_job(&diamond(), org(), None).await.unwrap();
+    let fetch: TaskQueued = recv(&mut queue_rx).await;
+    run_task(&h, &fetch, failed("upstream 503")).await;
+
+    let job_failed: JobFailed = recv(&mut failed_rx).await;
+    assert_eq!(job_failed.job_id, job_id);
+    assert_eq!(job_failed.failed_task_id, fetch.task_id);
+    assert_eq!(job_failed.error, "upstream 503");
+
+    let job = load_job(&h.store, &job_id).await;
+    assert_eq!(job.state, JobState::Failed);
+    assert_eq!(job.failed_count, 1);
+    assert_eq!(
+        job.error.as_deref(),
+        Some("task fetch failed: upstream 503")
+    );
+
+    for task in TaskRecord::load_for_job(&h.store, &job_id).await.unwrap() {
+        let expected = if task.task_name == "fetch" {
+            TaskState::Failed
+        } else {
+            TaskState::Cancelled
+        };
+        assert_eq!(task.state, expected, "{}", task.task_name);
+    }
+    assert_quiet(&mut queue_rx).await;
+}
+
+#[tokio::test]
+async fn test_repeated_terminal_event_queues_dependants_once() {
+    let h = harness();
+    let (_queue, mut queue_rx) = watch(&h.transport, &tasks::queue("data-transform")).await;
+    let definition = JobDefinition {
+        tasks: diamond().tasks.into_iter().take(2).collect(),
+        ..diamond()
+    };
+
+    let job_id = h.driver.create_job(&definition, org(), None).await.unwrap();
+    let fetch = TaskRecord::load_for_job(&h.store, &job_id)
+        .await
+        .unwrap()
+        .into_iter()
+        .find(|t| t.task_name == "fetch")
+        .unwrap();
+    let key = fetch.key();
+    cas(&h.store, &key, TaskState::Pending, TaskState::Claimed).await;
+    cas(&h.store, &key, TaskState::Claimed, TaskState::Running).await;
+    cas(&h.store, &key, TaskState::Running, TaskState::Completed).await;
+
+    for _ in 0..2 {
+        h.driver
+            .on_terminal(&fetch.task_type, &fetch.task_id)
+            .await
+            .unwrap();
+    }
+
+    let transform: TaskQueued = recv(&mut queue_rx).await;
+    assert_eq!(
+        load_task(&h.store, &transform).await.state,
+        TaskState::Pending
+    );
+    assert_quiet(&mut queue_rx).await;
+}
+
+// --- input_from ---
+
+/// fetch -> send, with send's `url` taken from fetch's outcome data.
+fn with_input(reference: &str) -> JobDefinition {
+    let mut send = task("send", "email-send", &["fetch"]);
+    send.params
+        .entries
+        .insert("to".to_string(), "ops@example.com".to_string());
+    send.input_from
+        .insert("url".to_string(), reference.to_string());
+    JobDefinition {
+        tasks: vec![task("fetch", "data-fetch", &[]), send],
+        ..diamond()
+    }
+}
+
+#[tokio::test]
+async fn test_dependant_is_queued_with_resolved_inputs() {
+    let h = harness();
+    let _driver = h.driver.start().await.unwrap();
+    let (_queue, mut queue_rx) = watch(&h.transport, &tasks::queue("*")).await;
+
+    h.driver
+        .create_job(&with_input("fetch.data.report.url"), org(), None)
+        .await
+        .unwrap();
+    let fetch: TaskQueued = recv(&mut queue_rx).await;
+    let data = serde_json::json!({"report": {"url": "https://example.com/r/1"}});
+    run_task(&h, &fetch, completed(Some(data))).await;
+
+    let send: TaskQueued = recv(&mut queue_rx).await;
+    assert_eq!(send.params.entries["url"], "https://example.com/r/1");
+    assert_eq!(send.params.entries["to"], "ops@example.com");
+    assert_eq!(
+        load_task(&h.store, &send).await.params,
+        send.params,
+        "resolved params are kept for retries"
+    );
+}
+
+#[tokio::test]
+async fn test_unresolvable_input_fails_job() {
+    let h = harness();
+    let _driver = h.driver.start().await.unwrap();
+    let (_failed, mut failed_rx) = watch(&h.transport, &jobs::failed("daily-report")).await;
+    let (_queue, mut queue_rx) = watch(&h.transport, &tasks::queue("*")).await;
+
+    let job_id = h
+        .driver
+        .create_job(&with_input("fetch.data.report.url"), org(), None)
+        .await
+        .unwrap();
+    let fetch: TaskQueued = recv(&mut queue_rx).await;
+    run_task(&h, &fetch, completed(Some(serde_json::json!({"rows": 3})))).await;
+
+    let job_failed: JobFailed = recv(&mut failed_rx).await;
+    assert_eq!(
+        job_failed.error,
+        "input 'url' from 'fetch.data.report.url': path not found: no 'report' in 'fetch.data' (an object)"
+    );
+    assert_quiet(&mut queue_rx).await;
+
+    let send = TaskRecord::load_for_job(&h.store, &job_id)
+        .await
+        .unwrap()
+        .into_iter()
+        .find(|t| t.task_name == "send")
+        .unwrap();
+    assert_eq!(send.state, TaskState::Cancelled);
+    assert_eq!(send.error.as_deref(), Some(job_failed.error.as_str()));
+}
+
+#[tokio::test]
+async fn test_input_strategy_is_configurable() {
+    let transport: Arc<dyn Transport> =
+        Arc::new(MemoryTransport::new(MemoryTransportConfig::default()));
+    let store: Arc<dyn StateStore> = Arc::new(MemoryStateStore::new());
+    let driver = Arc::new(JobDriver::new(
+        transport.clone(),
+        store.clone(),
+        DriverConfig {
+            input_strategy: InputStrategy::Json,
+            ..Default::default()
+        },
+    ));
+    let h = Harness {
+        transport,
+        store,
+        driver,
+    };
+    let _driver = h.driver.start().await.unwrap();
+    let (_queue, mut queue_rx) = watch(&h.transport, &tasks::queue("*")).await;
+
+    h.driver
+        .create_job(&with_input("fetch.data.recipients"), org(), None)
+        .await
+        .unwrap();
+    let fetch: TaskQueued = recv(&mut queue_rx).await;
+    let data = serde_json::json!({"recipients": ["a@example.com", "b@example.com"]});
+    run_task(&h, &fetch, completed(Some(data))).await;
+
+    let send: TaskQueued = recv(&mut queue_rx).await;
+    assert_eq!(
+        send.params.entries["url"],
+        r#"["a@example.com","b@example.com"]"#
+    );
+}
+
+// --- Watcher ---
+
+/// Claim `queued` as a worker would, with a claim deadline of `timeout_at`.
+async fn claim(h: &Harness, queued: &TaskQueued, running: bool, timeout_at: u64) -> String {
+    let key = keys::task_key(queued.task_type.as_str(), queued.task_id.as_str());
+    cas(&h.store, &key, TaskState::Pending, TaskState::Claimed).await;
+    if running {
+        cas(&h.store, &key, TaskState::Claimed, TaskState::Running).await;
+    }
+    h.store
+        .set_field(
+            &key,
+            fields::task::TIMEOUT_AT,
+            Bytes::from(timeout_at.to_string()),
+        )
+        .await
+        .unwrap();
+    key
+}
+
+fn create_watcher(h: &Harness) -> Watcher {
+    Watcher::new(
+        h.transport.clone(),
+        h.store.clone(),
+        WatcherConfig::default(),
+    )
+}
+
+#[tokio::test]
+async fn test_watcher_requeues_timed_out_task() {
+    let h = harness();
+    let (_queue, mut queue_rx) = watch(&h.transport, &tasks::queue("data-fetch")).await;
+    let (_terminal, mut terminal_rx) = watch(&h.transport, &tasks::terminal("data-fetch")).await;
+
+    let job_id = h.driver.create_job(&diamond(), org(), None).await.unwrap();
+    let fetch: TaskQueued = recv(&mut queue_rx).await;
+    claim(&h, &fetch, true, now_millis() - 1000).await;
+
+    let report = create_watcher(&h).check().await.unwrap();
+    assert_eq!(report.requeued, vec![fetch.task_id.clone()]);
+    assert!(report.failed.is_empty());
+
+    let task = load_task(&h.store, &fetch).await;
+    assert_eq!(task.state, TaskState::Pending);
+    assert_eq!(task.retry_count, 1);
+    assert_eq!(task.error.as_deref(), Some("timed out running after 300s"));
+    assert_eq!(
+        task.timeout_at, None,
+        "a requeued task has no claim to expire"
+    );
+
+    let requeued: TaskQueued = recv(&mut queue_rx).await;
+    assert_eq!(requeued.task_id, fetch.task_id);
+    assert_eq!(requeued.retry_count, 1);
+
+    let task_failed: TaskFailed = recv(&mut terminal_rx).await;
+    assert_eq!(task_failed.task_id, fetch.task_id);
+    assert_eq!((task_failed.retry_count, task_failed.max_retries), (0, 3));
+    assert_eq!(load_job(&h.store, &job_id).await.state, JobState::Running);
+}
+
+#[tokio::test]
+async fn test_fail_attempt_leaves_settled_task_alone() {
+    let h = harness();
+    let (_queue, mut queue_rx) = watch(&h.transport, &tasks::queue("data-fetch")).await;
+    h.driver.create_job(&diamond(), org(), None).await.unwrap();
+    let fetch: TaskQueued = recv(&mut queue_rx).await;
+    let key = claim(&h, &fetch, true, now_millis() - 1000).await;
+    let stale = load_task(&h.store, &fetch).await;
+
+    // The worker completes just before the timeout is handled.
+    cas(&h.store, &key, TaskState::Running, TaskState::Completed).await;
+    let emitter = EventEmitter::new(h.transport.clone(), "test", "test-1");
+    let moved = fail_attempt(&h.store, &emitter, &stale, "timed out", None)
+        .await
+        .unwrap();
+    assert_eq!(moved, None);
+
+    let task = load_task(&h.store, &fetch).await;
+    assert_eq!(task.state, TaskState::Completed);
+    assert_eq!(task.retry_count, 0);
+    assert_eq!(task.error, None);
+    assert_quiet(&mut queue_rx).await;
+}
+
+#[tokio::test]
+async fn test_fail_attempt_ignores_earlier_attempt() {
+    let h = harness();
+    let (_queue, mut queue_rx) = watch(&h.transport, &tasks::queue("data-fetch")).await;
+    h.driver.create_job(&diamond(), org(), None).await.unwrap();
+    let fetch: TaskQueued = recv(&mut queue_rx).await;
+    claim(&h, &fetch, true, now_millis() - 1000).await;
+    let stale = load_task(&h.store, &fetch).await;
+
+    // Released by its worker, then claimed again: running once more, but
+    // as the next attempt.
+    let emitter = EventEmitter::new(h.transport.clone(), "test", "test-1");
+    let released = fail_attempt(&h.store, &emitter, &stale, "shutting down", None)
+        .await
+        .unwrap();
+    assert_eq!(released, Some(TaskState::Pending));
+    let retry: TaskQueued = recv(&mut queue_rx).await;
+    claim(&h, &retry, true, now_millis() + 60_000).await;
+
+    let moved = fail_attempt(&h.store, &emitter, &stale, "timed out", None)
+        .await
+        .unwrap();
+    assert_eq!(moved, None);
+    let task = load_task(&h.store, &fetch).await;
+    assert_eq!(task.state, TaskState::Running);
+    assert_eq!(task.retry_count, 1);
+    assert_eq!(task.error.as_deref(), Some("shutting down"));
+    assert_quiet(&mut queue_rx).await;
+}
+
+#[tokio::test]
+async fn test_watcher_fails_exhausted_task_and_driver_fails_job() {
+    let h = harness();
+    let _driver = h.driver.start().await.unwrap();
+    let (_failed, mut failed_rx) = watch(&h.transport, &jobs::failed("daily-report")).await;
+    let (_queue, mut queue_rx) = watch(&h.transport, &tasks::queue("data-fetch")).await;
+    let mut definition = diamond();
+    definition.tasks[0].max_retries = Some(0);
+
+    let job_id = h.driver.create_job(&definition, org(), None).await.unwrap();
+    let fetch: TaskQueued = recv(&mut queue_rx).await;
+    claim(&h, &fetch, false, now_millis() - 1000).await;
+
+    let report = create_watcher(&h).check().await.unwrap();
+    assert_eq!(report.failed, vec![fetch.task_id.clone()]);
+    assert_eq!(load_task(&h.store, &fetch).await.state, TaskState::Failed);
+
+    let job_failed: JobFailed = recv(&mut failed_rx).await;
+    assert_eq!(job_failed.job_id, job_id);
+    assert_eq!(job_failed.error, "timed out claimed after 300s");
+    assert_quiet(&mut queue_rx).await;
+}
+
+#[tokio::test]
+async fn test_watcher_leaves_live_and_settled_tasks_alone() {
+    let h = harness();
+    let (_queue, mut queue_rx) = watch(&h.transport, &tasks::queue("*")).await;
+    let definition = JobDefinition {
+        tasks: vec![
+            task("live", "data-fetch", &[]),
+            task("done", "data-audit", &[]),
+        ],
+        ..diamond()
+    };
+    h.driver.create_job(&definition, org(), None).await.unwrap();
+    let first: TaskQueued = recv(&mut queue_rx).await;
+    let second: TaskQueued = recv(&mut queue_rx).await;
+    let (live, done) = if first.task_type.as_str() == "data-fetch" {
+        (first, second)
+    } else {
+        (second, first)
+    };
+
+    claim(&h, &live, true, now_millis() + 60_000).await;
+    let done_key = claim(&h, &done, true, now_millis() - 1000).await;
+    cas(
+        &h.store,
+        &done_key,
+        TaskState::Running,
+        TaskState::Completed,
+    )
+    .await;
+
+    let report = create_watcher(&h).check().await.unwrap();
+    assert_eq!(report, gbe_jobs::WatchReport::default());
+    assert_eq!(load_task(&h.store, &live).await.state, TaskState::Running);
+    assert_eq!(load_task(&h.store, &done).await.state, TaskState::Completed);
+    assert_quiet(&mut queue_rx).await;
+}
+
+#[tokio::test]
+async fn test_watcher_resends_events_of_idle_tasks() {
+    let h = harness();
+    let _driver = h.driver.start().await.unwrap();
+    let (_failed, mut failed_rx) = watch(&h.transport, &jobs::failed("daily-report")).await;
+    let (_queue, mut queue_rx) = watch(&h.transport, &tasks::queue("data-fetch")).await;
+    let definition = JobDefinition {
+        tasks: vec![
+            task("idle", "data-fetch", &[]),
+            task("lost", "data-fetch", &[]),
+        ],
+        ..diamond()
+    };
+    let job_id = h.driver.create_job(&definition, org(), None).await.unwrap();
+    let first: TaskQueued = recv(&mut queue_rx).await;
+    let second: TaskQueued = recv(&mut queue_rx).await;
+    let idle_key = keys::task_key(first.task_type.as_str(), first.task_id.as_str());
+    let long_ago = Bytes::from((now_millis() - 600_000).to_string());
+    h.store
+        .set_field(&idle_key, fields::task::UPDATED_AT, long_ago.clone())
+        .await
+        .unwrap();
+
+    // Failed for good, as if its TaskFailed had never been sent.
+    let lost_key = claim(&h, &second, true, now_millis() - 1000).await;
+    h.store
+        .set_field(&lost_key, fields::task::MAX_RETRIES, Bytes::from("0"))
+        .await
+        .unwrap();
+    cas(&h.store, &lost_key, TaskState::Running, TaskState::Failed).await;
+    h.store
+        .set_field(&lost_key, fields::task::UPDATED_AT, long_ago)
+        .await
+        .unwrap();
+
+    let watcher = create_watcher(&h);
+    let report = watcher.check().await.unwrap();
+    let mut resent = report.resent.clone();
+    resent.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+    let mut expected = vec![first.task_id.clone(), second.task_id.clone()];
+    expected.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+    assert_eq!(resent, expected);
+    assert!(load_task(&h.store, &first).await.updated_at > now_millis() - 60_000);
+
+    let job_failed: JobFailed = recv(&mut failed_rx).await;
+    assert_eq!(job_failed.job_id, job_id);
+    assert_eq!(job_failed.failed_task_id, second.task_id);
+    assert_eq!(
+        watcher.check().await.unwrap(),
+        gbe_jobs::WatchReport::default()
+    );
+}
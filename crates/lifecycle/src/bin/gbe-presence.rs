@@ -0,0 +1,64 @@
+//! `gbe-presence` binary: tracks component instances from lifecycle events
+//! on Redis until SIGINT or SIGTERM, publishing its own lifecycle events.
+//!
+//! Environment:
+//! - `REDIS_URL` (default `redis://127.0.0.1:6379`), for both the transport
+//!   and the state store
+//! - `HEARTBEAT_INTERVAL_SECS` (default 30), the interval components
+//!   heartbeat at
+//! - `MISSED_HEARTBEATS` (default 3), before an instance is marked stale
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use gbe_lifecycle::{
+    Lifecycle, LifecycleConfig, PRESENCE_COMPONENT, PresenceConfig, PresenceRegistry,
+};
+use gbe_nexus::EventEmitter;
+use gbe_nexus_redis::{RedisTransport, RedisTransportConfig};
+use gbe_state_store::StateStoreConfig;
+use gbe_state_store_redis::RedisStateStore;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tracing_subscriber::fmt::init();
+
+    let mut transport_config = RedisTransportConfig::default();
+    if let Ok(url) = std::env::var("REDIS_URL") {
+        transport_config.url = url;
+    }
+    let mut config = PresenceConfig::default();
+    if let Ok(secs) = std::env::var("HEARTBEAT_INTERVAL_SECS") {
+        config.heartbeat_interval = Duration::from_secs(secs.parse()?);
+    }
+    if let Ok(missed) = std::env::var("MISSED_HEARTBEATS") {
+        config.missed_heartbeats = missed.parse()?;
+    }
+
+    let store = RedisStateStore::connect(StateStoreConfig {
+        url: transport_config.url.clone(),
+    })
+    .await?;
+    let transport = Arc::new(RedisTransport::connect(transport_config).await?);
+    let emitter = EventEmitter::new(
+        transport.clone(),
+        PRESENCE_COMPONENT,
+        config.instance_id.clone(),
+    );
+    let lifecycle = Arc::new(Lifecycle::new(
+        emitter,
+        LifecycleConfig {
+            heartbeat_interval: config.heartbeat_interval,
+            version: Some(env!("CARGO_PKG_VERSION").to_string()),
+        },
+    ));
+    let registry = Arc::new(PresenceRegistry::new(transport, Arc::new(store), config));
+    let subscription = registry.start().await?;
+
+    lifecycle.stop_on_signals();
+    tracing::info!("presence registry started");
+    tokio::join!(lifecycle.run(), registry.run(lifecycle.token()));
+    subscription.unsubscribe().await?;
+    tracing::info!("presence registry stopped");
+    Ok(())
+}
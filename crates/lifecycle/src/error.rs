@@ -0,0 +1,16 @@
+use thiserror::Error;
+
+use gbe_nexus::TransportError;
+use gbe_state_store::StateStoreError;
+
+#[derive(Debug, Error)]
+pub enum LifecycleError {
+    #[error("transport error: {0}")]
+    Transport(#[from] TransportError),
+
+    #[error("state store error: {0}")]
+    State(#[from] StateStoreError),
+
+    #[error("malformed record {key}: {reason}")]
+    MalformedRecord { key: String, reason: String },
+}
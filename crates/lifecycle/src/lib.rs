@@ -0,0 +1,20 @@
+//! Component lifecycle events.
+//!
+//! `Lifecycle` publishes the `gbe.events.lifecycle.{component}.*` events
+//! defined in `gbe-jobs-domain` for a component's `EventEmitter`: `started`
+//! on boot, `heartbeat` with uptime on an interval, `degraded` when a
+//! `HealthProbe` fails, and `stopped` with a reason on shutdown.
+//!
+//! `PresenceRegistry` consumes those events and keeps the status of every
+//! component instance in the `StateStore`, marking instances stale when
+//! their heartbeats stop.
+
+mod error;
+mod lifecycle;
+mod presence;
+mod signal;
+
+pub use error::LifecycleError;
+pub use lifecycle::{HealthProbe, Lifecycle, LifecycleConfig, emit_degraded};
+pub use presence::{PRESENCE_COMPONENT, Presence, PresenceConfig, PresenceRegistry};
+pub use signal::shutdown_signal;
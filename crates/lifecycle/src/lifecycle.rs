@@ -0,0 +1,245 @@
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use async_trait::async_trait;
+use tokio_util::sync::CancellationToken;
+
+use gbe_jobs_domain::subjects::lifecycle;
+use gbe_jobs_domain::{ComponentDegraded, ComponentStarted, ComponentStopped, Heartbeat};
+use gbe_nexus::{EventEmitter, TransportError, dedup_id, now_millis};
+
+use crate::signal::shutdown_signal;
+
+/// Configuration for a `Lifecycle`.
+pub struct LifecycleConfig {
+    /// Time between heartbeats; health probes run on the same tick.
+    pub heartbeat_interval: Duration,
+    /// Version reported in `started`.
+    pub version: Option<String>,
+}
+
+impl Default for LifecycleConfig {
+    fn default() -> Self {
+        Self {
+            heartbeat_interval: Duration::from_secs(30),
+            version: None,
+        }
+    }
+}
+
+/// A health check run on every heartbeat.
+#[async_trait]
+pub trait HealthProbe: Send + Sync {
+    /// `Err` carries the reason the component is degraded.
+    async fn check(&self) -> Result<(), String>;
+}
+
+/// Publishes a component's lifecycle events on
+/// `gbe.events.lifecycle.{component}.*`, for the component and instance of
+/// its `EventEmitter`.
+///
+/// `run` publishes `started`, then a `heartbeat` with uptime every
+/// `heartbeat_interval`, followed by a `degraded` for each probe that
+/// fails, and finally `stopped` with the reason given to `stop`. Services
+/// share the shutdown through `token`.
+pub struct Lifecycle {
+    emitter: EventEmitter,
+    config: LifecycleConfig,
+    probes: Vec<Arc<dyn HealthProbe>>,
+    booted: Instant,
+    token: CancellationToken,
+    stop_reason: Mutex<Option<String>>,
+}
+
+impl Lifecycle {
+    pub fn new(emitter: EventEmitter, config: LifecycleConfig) -> Self {
+        Self {
+            emitter,
+            config,
+            probes: Vec::new(),
+            booted: Instant::now(),
+            token: CancellationToken::new(),
+            stop_reason: Mutex::new(None),
+        }
+    }
+
+    /// Check `probe` on every heartbeat.
+    #[must_use]
+    pub fn with_probe(mut self, probe: Arc<dyn HealthProbe>) -> Self {
+        self.probes.push(probe);
+        self
+    }
+
+    /// Token cancelled by `stop`, for the services of this component.
+    #[must_use]
+    pub fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    /// Begin shutdown; `run` reports `reason` in `stopped`. Only the first
+    /// reason is kept.
+    pub fn stop(&self, reason: impl Into<String>) {
+        let mut stop_reason = self.stop_reason.lock().unwrap();
+        if stop_reason.is_none() {
+            *stop_reason = Some(reason.into());
+        }
+        self.token.cancel();
+    }
+
+    /// Call `stop` with the signal's name on SIGINT or SIGTERM.
+    pub fn stop_on_signals(self: &Arc<Self>) {
+        let lifecycle = self.clone();
+        tokio::spawn(async move {
+            tokio::select! {
+                () = lifecycle.token.cancelled() => {}
+                signal = shutdown_signal() => lifecycle.stop(signal),
+            }
+        });
+    }
+
+    /// Publish `started`, heartbeats and probe results until `stop`, then
+    /// `stopped`. Publish failures are logged, never fatal.
+    pub async fn run(&self) {
+        if let Err(e) = self.started().await {
+            tracing::warn!("failed to emit started event: {e}");
+        }
+
+        let mut ticker = tokio::time::interval(self.config.heartbeat_interval);
+        // The first tick is immediate and `started` already said we're up.
+        ticker.tick().await;
+        loop {
+            tokio::select! {
+                () = self.token.cancelled() => break,
+                _ = ticker.tick() => self.beat().await,
+            }
+        }
+
+        let reason = self
+            .stop_reason
+            .lock()
+            .unwrap()
+            .clone()
+            .unwrap_or_else(|| "stopped".to_string());
+        if let Err(e) = self.stopped(reason).await {
+            tracing::warn!("failed to emit stopped event: {e}");
+        }
+    }
+
+    /// Publish `started`.
+    ///
+    /// # Errors
+    /// Returns an error if the event cannot be published.
+    pub async fn started(&self) -> Result<String, TransportError> {
+        let event = ComponentStarted {
+            component: self.emitter.component().to_string(),
+            instance_id: self.emitter.instance_id().to_string(),
+            started_at: now_millis(),
+            version: self.config.version.clone(),
+        };
+        self.emit(
+            &lifecycle::started(self.emitter.component()),
+            "started",
+            event,
+        )
+        .await
+    }
+
+    /// Publish a `heartbeat` with the uptime since this `Lifecycle` was
+    /// created.
+    ///
+    /// # Errors
+    /// Returns an error if the event cannot be published.
+    pub async fn heartbeat(&self) -> Result<String, TransportError> {
+        let event = Heartbeat {
+            component: self.emitter.component().to_string(),
+            instance_id: self.emitter.instance_id().to_string(),
+            timestamp: now_millis(),
+            uptime_secs: self.booted.elapsed().as_secs(),
+        };
+        self.emit(
+            &lifecycle::heartbeat(self.emitter.component()),
+            "heartbeat",
+            event,
+        )
+        .await
+    }
+
+    /// Publish `degraded` with `reason`, e.g. from a health check outside
+    /// the registered probes.
+    ///
+    /// # Errors
+    /// Returns an error if the event cannot be published.
+    pub async fn degraded(&self, reason: impl Into<String>) -> Result<String, TransportError> {
+        emit_degraded(&self.emitter, reason).await
+    }
+
+    /// Publish `stopped` with `reason`.
+    ///
+    /// # Errors
+    /// Returns an error if the event cannot be published.
+    pub async fn stopped(&self, reason: impl Into<String>) -> Result<String, TransportError> {
+        let event = ComponentStopped {
+            component: self.emitter.component().to_string(),
+            instance_id: self.emitter.instance_id().to_string(),
+            stopped_at: now_millis(),
+            reason: reason.into(),
+        };
+        self.emit(
+            &lifecycle::stopped(self.emitter.component()),
+            "stopped",
+            event,
+        )
+        .await
+    }
+
+    async fn beat(&self) {
+        if let Err(e) = self.heartbeat().await {
+            tracing::warn!("failed to emit heartbeat: {e}");
+        }
+        for probe in &self.probes {
+            if let Err(reason) = probe.check().await {
+                tracing::warn!("health probe failed: {reason}");
+                if let Err(e) = self.degraded(reason).await {
+                    tracing::warn!("failed to emit degraded event: {e}");
+                }
+            }
+        }
+    }
+
+    async fn emit<T: serde::Serialize>(
+        &self,
+        subject: &str,
+        event: &str,
+        data: T,
+    ) -> Result<String, TransportError> {
+        let id = dedup_id(self.emitter.component(), self.emitter.instance_id(), event);
+        self.emitter.emit(subject, 1, id, data).await
+    }
+}
+
+/// Publish `degraded` with `reason` for the component of `emitter`, for
+/// services that report degradation without running a `Lifecycle`.
+///
+/// # Errors
+/// Returns an error if the event cannot be published.
+pub async fn emit_degraded(
+    emitter: &EventEmitter,
+    reason: impl Into<String>,
+) -> Result<String, TransportError> {
+    let component = emitter.component();
+    let instance_id = emitter.instance_id();
+    let event = ComponentDegraded {
+        component: component.to_string(),
+        instance_id: instance_id.to_string(),
+        degraded_at: now_millis(),
+        reason: reason.into(),
+    };
+    emitter
+        .emit(
+            &lifecycle::degraded(component),
+            1,
+            dedup_id(component, instance_id, "degraded"),
+            event,
+        )
+        .await
+}
@@ -0,0 +1,549 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use serde_json::Value;
+use tokio_util::sync::CancellationToken;
+
+use gbe_jobs_domain::keys::{self, fields};
+use gbe_jobs_domain::subjects::{lifecycle, presence};
+use gbe_jobs_domain::{
+    ComponentDegraded, ComponentStarted, ComponentStopped, Heartbeat, PresenceChanged,
+    PresenceStatus,
+};
+use gbe_nexus::{
+    DomainPayload, Envelope, EventEmitter, HandlerOutcome, StartPosition, SubscribeOpts,
+    Subscription, Transport, TypedHandler, TypedMessageHandler, now_millis,
+};
+use gbe_state_store::{Record, ScanFilter, ScanOp, StateStore};
+
+use crate::error::LifecycleError;
+
+/// Component name used in lifecycle events.
+pub const PRESENCE_COMPONENT: &str = "presence";
+
+/// Configuration for a `PresenceRegistry`.
+pub struct PresenceConfig {
+    /// Consumer group reading the lifecycle streams.
+    pub group: String,
+    /// Instance ID reported in emitted events.
+    pub instance_id: String,
+    /// Heartbeat interval the components run with; also the time between
+    /// stale checks in `run`.
+    pub heartbeat_interval: Duration,
+    /// Heartbeats an instance may miss before it is marked stale.
+    pub missed_heartbeats: u32,
+    /// TTL of a presence record, refreshed by every event from the
+    /// instance. Keep it well above `heartbeat_interval * missed_heartbeats`
+    /// so instances go stale before they disappear.
+    pub ttl: Duration,
+}
+
+impl Default for PresenceConfig {
+    fn default() -> Self {
+        Self {
+            group: PRESENCE_COMPONENT.to_string(),
+            instance_id: format!("prs-{}", ulid::Ulid::new().to_string().to_lowercase()),
+            heartbeat_interval: Duration::from_secs(30),
+            missed_heartbeats: 3,
+            ttl: Duration::from_secs(3600),
+        }
+    }
+}
+
+/// One component instance as last seen by the registry.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Presence {
+    pub component: String,
+    pub instance_id: String,
+    pub status: PresenceStatus,
+    /// Time (unix millis) of the event that set `status`.
+    pub status_at: u64,
+    /// Time (unix millis) of the latest event from the instance.
+    pub last_seen: u64,
+    pub started_at: Option<u64>,
+    pub version: Option<String>,
+    pub uptime_secs: Option<u64>,
+    /// Reason given with the last `degraded` or `stopped`.
+    pub reason: Option<String>,
+}
+
+impl Presence {
+    /// Parse a presence record read from `key`.
+    ///
+    /// # Errors
+    /// Returns `LifecycleError::MalformedRecord` naming the key and the
+    /// missing or unparsable field.
+    pub fn from_record(key: &str, record: &Record) -> Result<Self, LifecycleError> {
+        let text = |field: &str| -> Result<Option<&str>, LifecycleError> {
+            record
+                .fields
+                .get(field)
+                .map(|value| {
+                    std::str::from_utf8(value).map_err(|_| LifecycleError::MalformedRecord {
+                        key: key.to_string(),
+                        reason: format!("field {field} is not UTF-8"),
+                    })
+                })
+                .transpose()
+        };
+        let required = |field: &str| -> Result<&str, LifecycleError> {
+            text(field)?.ok_or_else(|| LifecycleError::MalformedRecord {
+                key: key.to_string(),
+                reason: format!("missing field {field}"),
+            })
+        };
+        let optional = |field: &str| -> Result<Option<u64>, LifecycleError> {
+            text(field)?.map(|t| parse(key, field, t)).transpose()
+        };
+
+        Ok(Self {
+            component: required(fields::presence::COMPONENT)?.to_string(),
+            instance_id: required(fields::presence::INSTANCE_ID)?.to_string(),
+            status: parse(
+                key,
+                fields::presence::STATUS,
+                required(fields::presence::STATUS)?,
+            )?,
+            status_at: parse(
+                key,
+                fields::presence::STATUS_AT,
+                required(fields::presence::STATUS_AT)?,
+            )?,
+            last_seen: parse(
+                key,
+                fields::presence::LAST_SEEN,
+                required(fields::presence::LAST_SEEN)?,
+            )?,
+            started_at: optional(fields::presence::STARTED_AT)?,
+            version: text(fields::presence::VERSION)?.map(String::from),
+            uptime_secs: optional(fields::presence::UPTIME_SECS)?,
+            reason: text(fields::presence::REASON)?.map(String::from),
+        })
+    }
+}
+
+fn parse<T: FromStr>(key: &str, field: &str, text: &str) -> Result<T, LifecycleError>
+where
+    T::Err: std::fmt::Display,
+{
+    text.parse().map_err(|e| LifecycleError::MalformedRecord {
+        key: key.to_string(),
+        reason: format!("field {field}: {e}"),
+    })
+}
+
+/// A lifecycle event reduced to what the registry records.
+struct Observation {
+    component: String,
+    instance_id: String,
+    status: PresenceStatus,
+    at: u64,
+    started_at: Option<u64>,
+    version: Option<String>,
+    uptime_secs: Option<u64>,
+    reason: Option<String>,
+}
+
+/// Tracks which component instances are alive from the lifecycle events
+/// on `gbe.events.lifecycle.*.*`, one record per instance under
+/// `gbe.state.presence.{component}.{instance_id}` with a TTL.
+///
+/// `started`, `heartbeat`, `degraded` and `stopped` set the status to
+/// `started`, `healthy`, `degraded` and `stopped`; `check` marks alive
+/// instances `stale` once they miss `missed_heartbeats` heartbeats. Events
+/// from different subjects can arrive out of order, so an event older than
+/// the current status does not replace it, and `stopped` is final. Every
+/// status change is published as `PresenceChanged` on
+/// `gbe.events.presence.{component}.changed`.
+pub struct PresenceRegistry {
+    store: Arc<dyn StateStore>,
+    emitter: EventEmitter,
+    config: PresenceConfig,
+}
+
+impl PresenceRegistry {
+    pub fn new(
+        transport: Arc<dyn Transport>,
+        store: Arc<dyn StateStore>,
+        config: PresenceConfig,
+    ) -> Self {
+        let emitter = EventEmitter::new(transport, PRESENCE_COMPONENT, config.instance_id.clone());
+        Self {
+            store,
+            emitter,
+            config,
+        }
+    }
+
+    /// Consume the lifecycle events of every component.
+    ///
+    /// # Errors
+    /// Returns an error if the subscription fails.
+    pub async fn start(self: &Arc<Self>) -> Result<Box<dyn Subscription>, LifecycleError> {
+        let subscription = self
+            .emitter
+            .transport()
+            .subscribe(
+                &lifecycle::all_components(),
+                &self.config.group,
+                TypedHandler::boxed(LifecycleHandler {
+                    registry: self.clone(),
+                }),
+                Some(SubscribeOpts {
+                    start_from: StartPosition::Earliest,
+                    ..Default::default()
+                }),
+            )
+            .await?;
+        Ok(subscription)
+    }
+
+    /// One instance of `component`, if it has a record.
+    ///
+    /// # Errors
+    /// Returns an error if the record cannot be read or parsed.
+    pub async fn get(
+        &self,
+        component: &str,
+        instance_id: &str,
+    ) -> Result<Option<Presence>, LifecycleError> {
+        let key = keys::presence_key(component, instance_id);
+        self.store
+            .get(&key)
+            .await?
+            .map(|record| Presence::from_record(&key, &record))
+            .transpose()
+    }
+
+    /// Every instance of `component` with a record, whatever its status,
+    /// ordered by instance ID.
+    ///
+    /// # Errors
+    /// Returns an error if the records cannot be scanned or parsed.
+    pub async fn instances(&self, component: &str) -> Result<Vec<Presence>, LifecycleError> {
+        let mut instances = self
+            .store
+            .scan(&keys::presence_prefix(component), None)
+            .await?
+            .iter()
+            .map(|(key, record)| Presence::from_record(key, record))
+            .collect::<Result<Vec<_>, _>>()?;
+        instances.sort_by(|a, b| a.instance_id.cmp(&b.instance_id));
+        Ok(instances)
+    }
+
+    /// The instances of `component` that are started, healthy or degraded.
+    ///
+    /// # Errors
+    /// Returns an error if the records cannot be scanned or parsed.
+    pub async fn alive(&self, component: &str) -> Result<Vec<Presence>, LifecycleError> {
+        let mut instances = self.instances(component).await?;
+        instances.retain(|p| p.status.is_alive());
+        Ok(instances)
+    }
+
+    /// Mark every alive instance that has missed `missed_heartbeats`
+    /// heartbeats as stale. Returns the instances that went stale.
+    ///
+    /// A failure on one instance is logged and does not stop the others.
+    ///
+    /// # Errors
+    /// Returns an error if the presence records cannot be scanned.
+    pub async fn check(&self) -> Result<Vec<Presence>, LifecycleError> {
+        let now = now_millis();
+        #[allow(clippy::cast_possible_truncation)] // a few heartbeats fit in u64 millis
+        let window =
+            (self.config.heartbeat_interval * self.config.missed_heartbeats).as_millis() as u64;
+        let cutoff = now.saturating_sub(window);
+        let silent = self
+            .store
+            .scan(
+                &keys::presences_prefix(),
+                Some(ScanFilter {
+                    field: fields::presence::LAST_SEEN.to_string(),
+                    op: ScanOp::Lt,
+                    value: Bytes::from(cutoff.to_string()),
+                    max_results: None,
+                }),
+            )
+            .await?;
+
+        let mut stale = Vec::new();
+        for (key, record) in silent {
+            let presence = match Presence::from_record(&key, &record) {
+                Ok(presence) => presence,
+                Err(e) => {
+                    tracing::warn!(key = %key, "skipping presence record: {e}");
+                    continue;
+                }
+            };
+            if !presence.status.is_alive() || presence.last_seen >= cutoff {
+                continue;
+            }
+            match self.mark_stale(&key, presence, now).await {
+                Ok(Some(presence)) => stale.push(presence),
+                Ok(None) => {}
+                Err(e) => tracing::warn!(key = %key, "marking instance stale failed: {e}"),
+            }
+        }
+        Ok(stale)
+    }
+
+    /// Check every `heartbeat_interval` until `token` is cancelled.
+    pub async fn run(&self, token: CancellationToken) {
+        let mut ticker = tokio::time::interval(self.config.heartbeat_interval);
+        loop {
+            tokio::select! {
+                () = token.cancelled() => break,
+                _ = ticker.tick() => {
+                    if let Err(e) = self.check().await {
+                        tracing::warn!("presence check failed: {e}");
+                    }
+                }
+            }
+        }
+    }
+
+    /// Move `presence` to stale. Returns it updated, or `None` if its
+    /// status changed since it was read.
+    async fn mark_stale(
+        &self,
+        key: &str,
+        presence: Presence,
+        now: u64,
+    ) -> Result<Option<Presence>, LifecycleError> {
+        let swapped = self
+            .store
+            .compare_and_swap(
+                key,
+                fields::presence::STATUS,
+                Bytes::from(presence.status.as_str()),
+                Bytes::from(PresenceStatus::Stale.as_str()),
+            )
+            .await?;
+        if !swapped {
+            return Ok(None);
+        }
+        self.store
+            .set_field(
+                key,
+                fields::presence::STATUS_AT,
+                Bytes::from(now.to_string()),
+            )
+            .await?;
+        tracing::warn!(
+            component = %presence.component,
+            instance_id = %presence.instance_id,
+            last_seen = presence.last_seen,
+            "instance went stale"
+        );
+        self.changed(&presence, Some(presence.status), PresenceStatus::Stale, now)
+            .await?;
+        Ok(Some(Presence {
+            status: PresenceStatus::Stale,
+            status_at: now,
+            ..presence
+        }))
+    }
+
+    /// Record one lifecycle event.
+    async fn observe(&self, event: Observation) -> Result<(), LifecycleError> {
+        let key = keys::presence_key(&event.component, &event.instance_id);
+        let current = match self.store.get(&key).await? {
+            Some(record) => Some(Presence::from_record(&key, &record)?),
+            None => None,
+        };
+        if current
+            .as_ref()
+            .is_some_and(|c| c.status == PresenceStatus::Stopped)
+        {
+            return Ok(());
+        }
+
+        let last_seen = current
+            .as_ref()
+            .map_or(event.at, |c| c.last_seen.max(event.at));
+        let mut updates = HashMap::from([
+            (
+                fields::presence::COMPONENT.to_string(),
+                Bytes::from(event.component.clone()),
+            ),
+            (
+                fields::presence::INSTANCE_ID.to_string(),
+                Bytes::from(event.instance_id.clone()),
+            ),
+            (
+                fields::presence::LAST_SEEN.to_string(),
+                Bytes::from(last_seen.to_string()),
+            ),
+        ]);
+        let mut set = |field: &str, value: Option<String>| {
+            if let Some(value) = value {
+                updates.insert(field.to_string(), Bytes::from(value));
+            }
+        };
+        set(
+            fields::presence::STARTED_AT,
+            event.started_at.map(|t| t.to_string()),
+        );
+        set(fields::presence::VERSION, event.version.clone());
+        set(
+            fields::presence::UPTIME_SECS,
+            event.uptime_secs.map(|t| t.to_string()),
+        );
+
+        let applies = event.status == PresenceStatus::Stopped
+            || current.as_ref().is_none_or(|c| event.at >= c.status_at);
+        if applies {
+            set(
+                fields::presence::STATUS,
+                Some(event.status.as_str().to_string()),
+            );
+            set(fields::presence::STATUS_AT, Some(event.at.to_string()));
+            set(fields::presence::REASON, event.reason.clone());
+        }
+        self.store
+            .put(
+                &key,
+                Record {
+                    fields: updates,
+                    ttl: None,
+                },
+                Some(self.config.ttl),
+            )
+            .await?;
+
+        let from = current.as_ref().map(|c| c.status);
+        if applies && from != Some(event.status) {
+            let presence = Presence {
+                component: event.component,
+                instance_id: event.instance_id,
+                status: event.status,
+                status_at: event.at,
+                last_seen,
+                started_at: event.started_at,
+                version: event.version,
+                uptime_secs: event.uptime_secs,
+                reason: event.reason,
+            };
+            self.changed(&presence, from, event.status, event.at)
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn changed(
+        &self,
+        presence: &Presence,
+        from: Option<PresenceStatus>,
+        to: PresenceStatus,
+        at: u64,
+    ) -> Result<(), LifecycleError> {
+        self.emitter
+            .emit(
+                &presence::changed(&presence.component),
+                1,
+                format!("{}-{to}-{at}", presence.instance_id),
+                PresenceChanged {
+                    component: presence.component.clone(),
+                    instance_id: presence.instance_id.clone(),
+                    from,
+                    to,
+                    changed_at: at,
+                    last_seen: presence.last_seen,
+                },
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+struct LifecycleHandler {
+    registry: Arc<PresenceRegistry>,
+}
+
+impl LifecycleHandler {
+    /// Decode the data of a lifecycle event by the last token of its
+    /// subject. `None` for event kinds the registry does not track.
+    fn decode(subject: &str, data: Value) -> Result<Option<Observation>, serde_json::Error> {
+        let observation = match subject.rsplit('.').next() {
+            Some("started") => {
+                let e: ComponentStarted = serde_json::from_value(data)?;
+                Observation {
+                    component: e.component,
+                    instance_id: e.instance_id,
+                    status: PresenceStatus::Started,
+                    at: e.started_at,
+                    started_at: Some(e.started_at),
+                    version: e.version,
+                    uptime_secs: None,
+                    reason: None,
+                }
+            }
+            Some("heartbeat") => {
+                let e: Heartbeat = serde_json::from_value(data)?;
+                Observation {
+                    component: e.component,
+                    instance_id: e.instance_id,
+                    status: PresenceStatus::Healthy,
+                    at: e.timestamp,
+                    started_at: None,
+                    version: None,
+                    uptime_secs: Some(e.uptime_secs),
+                    reason: None,
+                }
+            }
+            Some("degraded") => {
+                let e: ComponentDegraded = serde_json::from_value(data)?;
+                Observation {
+                    component: e.component,
+                    instance_id: e.instance_id,
+                    status: PresenceStatus::Degraded,
+                    at: e.degraded_at,
+                    started_at: None,
+                    version: None,
+                    uptime_secs: None,
+                    reason: Some(e.reason),
+                }
+            }
+            Some("stopped") => {
+                let e: ComponentStopped = serde_json::from_value(data)?;
+                Observation {
+                    component: e.component,
+                    instance_id: e.instance_id,
+                    status: PresenceStatus::Stopped,
+                    at: e.stopped_at,
+                    started_at: None,
+                    version: None,
+                    uptime_secs: None,
+                    reason: Some(e.reason),
+                }
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(observation))
+    }
+}
+
+#[async_trait]
+impl TypedMessageHandler<Value> for LifecycleHandler {
+    async fn handle(&self, payload: DomainPayload<Value>, envelope: &Envelope) -> HandlerOutcome {
+        let observation = match Self::decode(&envelope.subject, payload.data) {
+            Ok(Some(observation)) => observation,
+            Ok(None) => return HandlerOutcome::Ack,
+            Err(e) => return HandlerOutcome::DeadLetter(format!("undecodable payload: {e}")),
+        };
+        match self.registry.observe(observation).await {
+            Ok(()) => HandlerOutcome::Ack,
+            Err(e) => {
+                // Redeliver so the presence record still catches up.
+                tracing::warn!(subject = %envelope.subject, "presence: {e}");
+                HandlerOutcome::Nak(None)
+            }
+        }
+    }
+}
@@ -0,0 +1,390 @@
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use gbe_jobs_domain::subjects::{lifecycle, presence};
+use gbe_jobs_domain::{
+    ComponentDegraded, ComponentStarted, ComponentStopped, Heartbeat, PresenceChanged,
+    PresenceStatus,
+};
+use gbe_lifecycle::{
+    HealthProbe, Lifecycle, LifecycleConfig, Presence, PresenceConfig, PresenceRegistry,
+};
+use gbe_nexus::{
+    DomainPayload, Envelope, EventEmitter, Message, MessageHandler, StartPosition, SubscribeOpts,
+    Subscription, Transport, TransportError,
+};
+use gbe_nexus_memory::{MemoryTransport, MemoryTransportConfig};
+use gbe_state_store::StateStore;
+use gbe_state_store_memory::MemoryStateStore;
+
+const COMPONENT: &str = "test-svc";
+const INSTANCE: &str = "tst-001";
+
+struct ChannelHandler {
+    tx: mpsc::UnboundedSender<Envelope>,
+}
+
+#[async_trait]
+impl MessageHandler for ChannelHandler {
+    async fn handle(&self, msg: &dyn Message) -> Result<(), TransportError> {
+        let _ = self.tx.send(msg.envelope().clone());
+        msg.ack().await
+    }
+}
+
+async fn watch(
+    transport: &Arc<dyn Transport>,
+) -> (Box<dyn Subscription>, mpsc::UnboundedReceiver<Envelope>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let sub = transport
+        .subscribe(
+            &lifecycle::all(COMPONENT),
+            "test-observer",
+            Box::new(ChannelHandler { tx }),
+            Some(SubscribeOpts {
+                start_from: StartPosition::Earliest,
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+    (sub, rx)
+}
+
+async fn recv(rx: &mut mpsc::UnboundedReceiver<Envelope>) -> Envelope {
+    tokio::time::timeout(Duration::from_secs(2), rx.recv())
+        .await
+        .expect("timed out waiting for event")
+        .unwrap()
+}
+
+fn decode<T: serde::de::DeserializeOwned>(env: &Envelope) -> T {
+    DomainPayload::<T>::from_bytes(&env.payload).unwrap().data
+}
+
+/// Fails while `broken` is set.
+struct Switch {
+    broken: AtomicBool,
+}
+
+#[async_trait]
+impl HealthProbe for Switch {
+    async fn check(&self) -> Result<(), String> {
+        if self.broken.load(Ordering::SeqCst) {
+            Err("connection pool exhausted".to_string())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+fn create_lifecycle(transport: &Arc<dyn Transport>, probe: Arc<Switch>) -> Arc<Lifecycle> {
+    let emitter = EventEmitter::new(transport.clone(), COMPONENT, INSTANCE);
+    Arc::new(
+        Lifecycle::new(
+            emitter,
+            LifecycleConfig {
+                heartbeat_interval: Duration::from_millis(50),
+                version: Some("1.2.3".to_string()),
+            },
+        )
+        .with_probe(probe),
+    )
+}
+
+#[tokio::test]
+async fn run_emits_started_heartbeats_degraded_and_stopped() {
+    let transport: Arc<dyn Transport> =
+        Arc::new(MemoryTransport::new(MemoryTransportConfig::default()));
+    let (_sub, mut rx) = watch(&transport).await;
+    let probe = Arc::new(Switch {
+        broken: AtomicBool::new(false),
+    });
+    let lifecycle = create_lifecycle(&transport, probe.clone());
+    let running = tokio::spawn({
+        let lifecycle = lifecycle.clone();
+        async move { lifecycle.run().await }
+    });
+
+    let env = recv(&mut rx).await;
+    assert_eq!(env.subject, lifecycle::started(COMPONENT));
+    let started: ComponentStarted = decode(&env);
+    assert_eq!(started.instance_id, INSTANCE);
+    assert_eq!(started.version.as_deref(), Some("1.2.3"));
+
+    let env = recv(&mut rx).await;
+    assert_eq!(env.subject, lifecycle::heartbeat(COMPONENT));
+    let beat: Heartbeat = decode(&env);
+    assert_eq!(beat.component, COMPONENT);
+
+    probe.broken.store(true, Ordering::SeqCst);
+    let degraded = loop {
+        let env = recv(&mut rx).await;
+        if env.subject == lifecycle::degraded(COMPONENT) {
+            break decode::<ComponentDegraded>(&env);
+        }
+    };
+    assert_eq!(degraded.reason, "connection pool exhausted");
+
+    let token = lifecycle.token();
+    lifecycle.stop("SIGTERM");
+    lifecycle.stop("ignored: only the first reason is kept");
+    assert!(token.is_cancelled());
+    running.await.unwrap();
+    let stopped = loop {
+        let env = recv(&mut rx).await;
+        if env.subject == lifecycle::stopped(COMPONENT) {
+            break decode::<ComponentStopped>(&env);
+        }
+    };
+    assert_eq!(stopped.reason, "SIGTERM");
+    assert_eq!(stopped.instance_id, INSTANCE);
+}
+
+#[tokio::test]
+async fn heartbeat_reports_uptime() {
+    let transport: Arc<dyn Transport> =
+        Arc::new(MemoryTransport::new(MemoryTransportConfig::default()));
+    let (_sub, mut rx) = watch(&transport).await;
+    let lifecycle = create_lifecycle(
+        &transport,
+        Arc::new(Switch {
+            broken: AtomicBool::new(false),
+        }),
+    );
+
+    tokio::time::sleep(Duration::from_millis(1100)).await;
+    lifecycle.heartbeat().await.unwrap();
+    let beat: Heartbeat = decode(&recv(&mut rx).await);
+    assert_eq!(beat.uptime_secs, 1);
+
+    lifecycle.degraded("disk nearly full").await.unwrap();
+    let degraded: ComponentDegraded = decode(&recv(&mut rx).await);
+    assert_eq!(degraded.reason, "disk nearly full");
+}
+
+// --- Presence ---
+
+struct Presences {
+    transport: Arc<dyn Transport>,
+    registry: Arc<PresenceRegistry>,
+    _sub: Box<dyn Subscription>,
+}
+
+async fn presences(missed_heartbeats: u32) -> Presences {
+    let transport: Arc<dyn Transport> =
+        Arc::new(MemoryTransport::new(MemoryTransportConfig::default()));
+    let store: Arc<dyn StateStore> = Arc::new(MemoryStateStore::new());
+    let registry = Arc::new(PresenceRegistry::new(
+        transport.clone(),
+        store,
+        PresenceConfig {
+            heartbeat_interval: Duration::from_millis(50),
+            missed_heartbeats,
+            ..Default::default()
+        },
+    ));
+    let sub = registry.start().await.unwrap();
+    Presences {
+        transport,
+        registry,
+        _sub: sub,
+    }
+}
+
+fn instance(p: &Presences, component: &str, instance_id: &str) -> Lifecycle {
+    let emitter = EventEmitter::new(p.transport.clone(), component, instance_id);
+    Lifecycle::new(emitter, LifecycleConfig::default())
+}
+
+async fn wait_for(p: &Presences, instance_id: &str, done: impl Fn(&Presence) -> bool) -> Presence {
+    for _ in 0..100 {
+        if let Some(presence) = p.registry.get(COMPONENT, instance_id).await.unwrap()
+            && done(&presence)
+        {
+            return presence;
+        }
+        tokio::time::sleep(Duration::from_millis(10)).await;
+    }
+    panic!("presence of {instance_id} never matched");
+}
+
+async fn watch_changes(
+    transport: &Arc<dyn Transport>,
+) -> (Box<dyn Subscription>, mpsc::UnboundedReceiver<Envelope>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let sub = transport
+        .subscribe(
+            &presence::changed(COMPONENT),
+            "test-observer",
+            Box::new(ChannelHandler { tx }),
+            Some(SubscribeOpts {
+                start_from: StartPosition::Earliest,
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+    (sub, rx)
+}
+
+#[tokio::test]
+async fn registry_follows_instance_through_its_lifecycle() {
+    let p = presences(3).await;
+    let (_changes, mut changes) = watch_changes(&p.transport).await;
+    let svc = instance(&p, COMPONENT, INSTANCE);
+
+    svc.started().await.unwrap();
+    let seen = wait_for(&p, INSTANCE, |s| s.status == PresenceStatus::Started).await;
+    assert!(seen.started_at.is_some());
+
+    svc.heartbeat().await.unwrap();
+    let seen = wait_for(&p, INSTANCE, |s| s.status == PresenceStatus::Healthy).await;
+    assert_eq!(seen.uptime_secs, Some(0));
+
+    svc.degraded("queue backlog").await.unwrap();
+    let seen = wait_for(&p, INSTANCE, |s| s.status == PresenceStatus::Degraded).await;
+    assert_eq!(seen.reason.as_deref(), Some("queue backlog"));
+
+    svc.stopped("SIGTERM").await.unwrap();
+    let seen = wait_for(&p, INSTANCE, |s| s.status == PresenceStatus::Stopped).await;
+    assert_eq!(seen.reason.as_deref(), Some("SIGTERM"));
+
+    // Stopped is final, even for a straggling heartbeat.
+    tokio::time::sleep(Duration::from_millis(5)).await;
+    svc.heartbeat().await.unwrap();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let seen = p.registry.get(COMPONENT, INSTANCE).await.unwrap().unwrap();
+    assert_eq!(seen.status, PresenceStatus::Stopped);
+
+    let mut transitions = Vec::new();
+    for _ in 0..4 {
+        let change: PresenceChanged = decode(&recv(&mut changes).await);
+        assert_eq!(change.instance_id, INSTANCE);
+        transitions.push((change.from, change.to));
+    }
+    assert_eq!(
+        transitions,
+        vec![
+            (None, PresenceStatus::Started),
+            (Some(PresenceStatus::Started), PresenceStatus::Healthy),
+            (Some(PresenceStatus::Healthy), PresenceStatus::Degraded),
+            (Some(PresenceStatus::Degraded), PresenceStatus::Stopped),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn registry_marks_silent_instances_stale() {
+    let p = presences(2).await;
+    let (_changes, mut changes) = watch_changes(&p.transport).await;
+    let quiet = instance(&p, COMPONENT, "tst-quiet");
+    let chatty = instance(&p, COMPONENT, "tst-chatty");
+
+    quiet.heartbeat().await.unwrap();
+    wait_for(&p, "tst-quiet", |s| s.status == PresenceStatus::Healthy).await;
+    let change: PresenceChanged = decode(&recv(&mut changes).await);
+    assert_eq!(change.to, PresenceStatus::Healthy);
+    assert!(p.registry.check().await.unwrap().is_empty());
+
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    chatty.heartbeat().await.unwrap();
+    wait_for(&p, "tst-chatty", |s| s.status == PresenceStatus::Healthy).await;
+    let _: PresenceChanged = decode(&recv(&mut changes).await);
+
+    let stale = p.registry.check().await.unwrap();
+    assert_eq!(stale.len(), 1);
+    assert_eq!(stale[0].instance_id, "tst-quiet");
+    assert_eq!(stale[0].status, PresenceStatus::Stale);
+    let change: PresenceChanged = decode(&recv(&mut changes).await);
+    assert_eq!(change.instance_id, "tst-quiet");
+    assert_eq!(change.from, Some(PresenceStatus::Healthy));
+    assert_eq!(change.to, PresenceStatus::Stale);
+    assert_eq!(change.last_seen, stale[0].last_seen);
+    assert!(
+        p.registry.check().await.unwrap().is_empty(),
+        "already stale"
+    );
+
+    let alive = p.registry.alive(COMPONENT).await.unwrap();
+    assert_eq!(alive.len(), 1);
+    assert_eq!(alive[0].instance_id, "tst-chatty");
+
+    // A heartbeat brings a stale instance back.
+    quiet.heartbeat().await.unwrap();
+    wait_for(&p, "tst-quiet", |s| s.status == PresenceStatus::Healthy).await;
+    let change: PresenceChanged = decode(&recv(&mut changes).await);
+    assert_eq!(change.from, Some(PresenceStatus::Stale));
+    assert_eq!(p.registry.alive(COMPONENT).await.unwrap().len(), 2);
+}
+
+#[tokio::test]
+async fn registry_queries_by_component() {
+    let p = presences(3).await;
+    instance(&p, COMPONENT, "tst-b").started().await.unwrap();
+    instance(&p, COMPONENT, "tst-a").started().await.unwrap();
+    instance(&p, "other-svc", "oth-a").started().await.unwrap();
+    let stopped = instance(&p, COMPONENT, "tst-c");
+    stopped.started().await.unwrap();
+    wait_for(&p, "tst-c", |s| s.status == PresenceStatus::Started).await;
+    stopped.stopped("done").await.unwrap();
+    wait_for(&p, "tst-c", |s| s.status == PresenceStatus::Stopped).await;
+    wait_for(&p, "tst-a", |_| true).await;
+    wait_for(&p, "tst-b", |_| true).await;
+
+    let ids = |instances: Vec<Presence>| -> Vec<String> {
+        instances.into_iter().map(|p| p.instance_id).collect()
+    };
+    assert_eq!(
+        ids(p.registry.instances(COMPONENT).await.unwrap()),
+        vec!["tst-a", "tst-b", "tst-c"]
+    );
+    assert_eq!(
+        ids(p.registry.alive(COMPONENT).await.unwrap()),
+        vec!["tst-a", "tst-b"]
+    );
+    assert!(p.registry.instances("absent").await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn older_event_does_not_replace_newer_status() {
+    let p = presences(3).await;
+    let emitter = EventEmitter::new(p.transport.clone(), COMPONENT, INSTANCE);
+    emitter
+        .emit(
+            &lifecycle::degraded(COMPONENT),
+            1,
+            "degraded-2000",
+            ComponentDegraded {
+                component: COMPONENT.to_string(),
+                instance_id: INSTANCE.to_string(),
+                degraded_at: 2_000,
+                reason: "slow disk".to_string(),
+            },
+        )
+        .await
+        .unwrap();
+    wait_for(&p, INSTANCE, |s| s.status == PresenceStatus::Degraded).await;
+
+    emitter
+        .emit(
+            &lifecycle::heartbeat(COMPONENT),
+            1,
+            "heartbeat-1000",
+            Heartbeat {
+                component: COMPONENT.to_string(),
+                instance_id: INSTANCE.to_string(),
+                timestamp: 1_000,
+                uptime_secs: 5,
+            },
+        )
+        .await
+        .unwrap();
+    let seen = wait_for(&p, INSTANCE, |s| s.uptime_secs == Some(5)).await;
+    assert_eq!(seen.status, PresenceStatus::Degraded);
+    assert_eq!(seen.status_at, 2_000);
+    assert_eq!(seen.last_seen, 2_000);
+}
@@ -1,22 +1,37 @@
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::Instant;
 use tokio_util::sync::CancellationToken;
 
-use gbe_nexus::{Envelope, MessageHandler, SubscribeOpts};
+use gbe_nexus::{
+    Envelope, Sink, SubscribeOpts, is_wildcard, max_deliveries_reason, subject_matches,
+};
 
 use crate::message::MemoryMessage;
-use crate::store::SharedStore;
+use crate::store::{ConsumerGroup, SharedStore, StreamData};
+
+/// One message handed to the handler.
+struct Delivery {
+    /// Stream the message was read from.
+    subject: String,
+    envelope: Envelope,
+    delivery_count: u32,
+}
 
 pub(crate) struct ConsumerParams {
     pub store: SharedStore,
+    /// Literal subject or wildcard pattern.
     pub subject: String,
     pub group: String,
-    pub handler: Box<dyn MessageHandler>,
+    pub sink: Sink<MemoryMessage>,
     pub opts: SubscribeOpts,
     pub token: CancellationToken,
     pub active: Arc<AtomicBool>,
     pub notify: Arc<tokio::sync::Notify>,
+    /// Set once the loop has stopped and drained.
+    pub done: watch::Sender<bool>,
 }
 
 pub(crate) async fn run_consumer_loop(params: ConsumerParams) {
@@ -24,13 +39,16 @@ pub(crate) async fn run_consumer_loop(params: ConsumerParams) {
         store,
         subject,
         group,
-        handler,
+        sink,
         opts,
         token,
         active,
         notify,
+        done,
     } = params;
 
+    let mut rotation = 0;
+
     loop {
         if token.is_cancelled() {
             break;
@@ -41,96 +59,187 @@ pub(crate) async fn run_consumer_loop(params: ConsumerParams) {
         // and a publish calling notify_waiters.
         let notified = notify.notified();
 
-        let batch = collect_batch(&store, &subject, &group, &opts).await;
+        let (batch, next_due) = collect_batch(&store, &subject, &group, &opts, rotation).await;
+        rotation = rotation.wrapping_add(1);
 
         if batch.is_empty() {
+            // Wake for new publishes, or when the next delayed nak comes due.
+            let poll = Instant::now() + Duration::from_millis(100);
+            let wake = next_due.map_or(poll, |due| due.min(poll));
             tokio::select! {
                 () = notified => {}
                 () = token.cancelled() => break,
-                () = tokio::time::sleep(Duration::from_millis(100)) => {}
+                () = tokio::time::sleep_until(wake) => {}
             }
             continue;
         }
 
-        for envelope in batch {
-            if token.is_cancelled() {
-                break;
-            }
-
+        // Once unsubscribed, deliver naks the rest of the batch so the
+        // group gets it back.
+        for delivery in batch {
             let msg = MemoryMessage {
-                envelope,
-                subject: subject.clone(),
+                envelope: delivery.envelope,
+                subject: delivery.subject,
                 group: group.clone(),
                 store: store.clone(),
+                delivery_count: delivery.delivery_count,
                 acked: AtomicBool::new(false),
             };
 
-            if let Err(e) = handler.handle(&msg).await {
-                tracing::warn!(error = %e, "handler error, auto-nak");
-                let _ = <MemoryMessage as gbe_nexus::Message>::nak(&msg, None).await;
-            }
+            sink.deliver(msg, &token).await;
         }
     }
 
+    sink.drain(opts.drain_timeout).await;
     active.store(false, Ordering::Release);
+    done.send_replace(true);
 }
 
+/// Collect the next batch for the group across every stream the
+/// subscription covers, plus when the next delayed message becomes due
+/// (if any are still waiting). `rotation` varies which stream goes first,
+/// so a busy stream can't starve the others.
+///
+/// Redeliveries past `max_deliveries` are dead-lettered here instead.
 async fn collect_batch(
     store: &SharedStore,
     subject: &str,
     group: &str,
     opts: &SubscribeOpts,
-) -> Vec<Envelope> {
+    rotation: usize,
+) -> (Vec<Delivery>, Option<Instant>) {
     let mut store = store.lock().await;
-    let batch_size = opts.batch_size as usize;
 
-    let Some(stream) = store.streams.get_mut(subject) else {
-        return Vec::new();
+    let mut subjects: Vec<String> = if is_wildcard(subject) {
+        let mut matched: Vec<String> = store
+            .streams
+            .keys()
+            .filter(|s| subject_matches(subject, s))
+            .cloned()
+            .collect();
+        matched.sort_unstable();
+        matched
+    } else {
+        vec![subject.to_string()]
     };
+    if !subjects.is_empty() {
+        let len = subjects.len();
+        subjects.rotate_left(rotation % len);
+    }
 
-    let Some(consumer_group) = stream.groups.get_mut(group) else {
-        return Vec::new();
-    };
+    let now = Instant::now();
+    let mut batch = Vec::new();
+    let mut exhausted = Vec::new();
+    let mut next_due: Option<Instant> = None;
 
-    // Backpressure: skip if too many pending
-    if consumer_group.pending.len() >= opts.max_inflight as usize {
-        return Vec::new();
+    for s in &subjects {
+        let Some(stream) = store.streams.get_mut(s) else {
+            continue;
+        };
+        // Streams that appeared after a wildcard subscribe are read from the start
+        if is_wildcard(subject) && !stream.groups.contains_key(group) {
+            stream
+                .groups
+                .insert(group.to_string(), ConsumerGroup::new(None));
+        }
+
+        let budget = opts.batch_size as usize - batch.len();
+        let due = collect_from(
+            stream,
+            s,
+            group,
+            opts,
+            now,
+            budget,
+            &mut batch,
+            &mut exhausted,
+        );
+        next_due = match (next_due, due) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        };
     }
 
-    let remaining_capacity = opts.max_inflight as usize - consumer_group.pending.len();
-    let take = batch_size.min(remaining_capacity);
+    for (s, envelope, attempts) in exhausted {
+        let reason = max_deliveries_reason(attempts);
+        if let Err(e) = store.dead_letter(&s, group, &envelope, &reason) {
+            tracing::warn!(message_id = %envelope.message_id, "failed to dead-letter: {e}");
+        }
+    }
 
-    let mut batch = Vec::new();
+    (batch, next_due)
+}
 
-    // Phase 1: redeliver nak'd messages first
-    while batch.len() < take {
+/// Collect up to `budget` deliveries from one stream into `batch`.
+/// Returns when the stream's next delayed message becomes due.
+#[allow(clippy::too_many_arguments)]
+fn collect_from(
+    stream: &mut StreamData,
+    subject: &str,
+    group: &str,
+    opts: &SubscribeOpts,
+    now: Instant,
+    budget: usize,
+    batch: &mut Vec<Delivery>,
+    exhausted: &mut Vec<(String, Envelope, u32)>,
+) -> Option<Instant> {
+    let consumer_group = stream.groups.get_mut(group)?;
+
+    // Phase 0: delayed naks that are due join the redeliver queue
+    let next_due = consumer_group.release_due(now);
+
+    let delivery = |envelope: &Envelope, delivery_count| Delivery {
+        subject: subject.to_string(),
+        envelope: envelope.clone(),
+        delivery_count,
+    };
+    let mut taken = 0;
+
+    // Phase 1: redeliver nak'd messages first. They are already pending,
+    // so backpressure does not hold them back.
+    while taken < budget {
         let Some(msg_id) = consumer_group.redeliver.pop_front() else {
             break;
         };
         // Find the message by ID
-        if let Some(&idx) = stream.id_index.get(&msg_id) {
-            batch.push(stream.messages[idx].clone());
+        if let Some(&seq) = stream.id_index.get(&msg_id)
+            && let Some(count) = consumer_group.pending.get_mut(&msg_id)
+        {
+            let envelope = &stream.messages[seq - stream.first_seq];
+            if opts.max_deliveries.is_some_and(|max| *count >= max) {
+                exhausted.push((subject.to_string(), envelope.clone(), *count));
+                continue;
+            }
+            *count += 1;
+            batch.push(delivery(envelope, *count));
+            taken += 1;
         }
     }
 
-    // Phase 2: deliver new messages by index
-    if batch.len() < take {
-        let start_idx = match consumer_group.cursor {
+    // Phase 2: deliver new messages by sequence, within max_inflight
+    let remaining_capacity =
+        (opts.max_inflight as usize).saturating_sub(consumer_group.pending.len());
+    let take = (budget - taken).min(remaining_capacity);
+    if take > 0 {
+        let start_seq = match consumer_group.cursor {
             Some(c) => c + 1,
             None => 0,
-        };
-
-        let end_idx = (start_idx + take - batch.len()).min(stream.messages.len());
-
-        for i in start_idx..end_idx {
-            let envelope = &stream.messages[i];
-            if !consumer_group.pending.contains(&envelope.message_id) {
-                consumer_group.pending.insert(envelope.message_id.clone());
-                consumer_group.cursor = Some(i);
-                batch.push(envelope.clone());
+        }
+        .max(stream.first_seq);
+
+        let end_seq = (start_seq + take).min(stream.first_seq + stream.messages.len());
+
+        for seq in start_seq..end_seq {
+            let envelope = &stream.messages[seq - stream.first_seq];
+            if !consumer_group.pending.contains_key(&envelope.message_id) {
+                consumer_group
+                    .pending
+                    .insert(envelope.message_id.clone(), 1);
+                consumer_group.cursor = Some(seq);
+                batch.push(delivery(envelope, 1));
             }
         }
     }
 
-    batch
+    next_due
 }
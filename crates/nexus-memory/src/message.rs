@@ -1,16 +1,19 @@
 use async_trait::async_trait;
 use bytes::Bytes;
+use std::cmp::Reverse;
 use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::time::Instant;
 
 use gbe_nexus::{Envelope, TransportError};
 
-use crate::store::{SharedStore, extract_domain};
+use crate::store::SharedStore;
 
 pub(crate) struct MemoryMessage {
     pub(crate) envelope: Envelope,
     pub(crate) subject: String,
     pub(crate) group: String,
     pub(crate) store: SharedStore,
+    pub(crate) delivery_count: u32,
     pub(crate) acked: AtomicBool,
 }
 
@@ -24,6 +27,10 @@ impl gbe_nexus::Message for MemoryMessage {
         &self.envelope.payload
     }
 
+    fn delivery_count(&self) -> u32 {
+        self.delivery_count
+    }
+
     async fn ack(&self) -> Result<(), TransportError> {
         if self.acked.swap(true, Ordering::AcqRel) {
             return Ok(());
@@ -37,7 +44,7 @@ impl gbe_nexus::Message for MemoryMessage {
         Ok(())
     }
 
-    async fn nak(&self, _delay: Option<std::time::Duration>) -> Result<(), TransportError> {
+    async fn nak(&self, delay: Option<std::time::Duration>) -> Result<(), TransportError> {
         if self.acked.swap(true, Ordering::AcqRel) {
             return Ok(());
         }
@@ -45,7 +52,15 @@ impl gbe_nexus::Message for MemoryMessage {
         if let Some(stream) = store.streams.get_mut(&self.subject)
             && let Some(group) = stream.groups.get_mut(&self.group)
         {
-            group.redeliver.push_back(self.envelope.message_id.clone());
+            // Stays pending either way; a delayed message only becomes
+            // eligible for redelivery once its due time passes.
+            match delay.filter(|d| !d.is_zero()) {
+                Some(delay) => group.delayed.push(Reverse((
+                    Instant::now() + delay,
+                    self.envelope.message_id.clone(),
+                ))),
+                None => group.redeliver.push_back(self.envelope.message_id.clone()),
+            }
         }
         Ok(())
     }
@@ -54,41 +69,7 @@ impl gbe_nexus::Message for MemoryMessage {
         if self.acked.swap(true, Ordering::AcqRel) {
             return Ok(());
         }
-
-        let domain = extract_domain(&self.subject);
-        let dl_subject = format!("gbe._deadletter.{domain}");
-
-        let dl_envelope = Envelope::new(
-            dl_subject.clone(),
-            Bytes::from(
-                serde_json::json!({
-                    "original_envelope": serde_json::to_value(&self.envelope)
-                        .map_err(TransportError::Serialization)?,
-                    "reason": reason,
-                })
-                .to_string(),
-            ),
-            self.envelope.trace_id.clone(),
-        );
-
         let mut store = self.store.lock().await;
-
-        // Insert into dead-letter stream
-        let dl_stream = store.get_or_create_stream(&dl_subject);
-        let idx = dl_stream.messages.len();
-        dl_stream
-            .id_index
-            .insert(dl_envelope.message_id.clone(), idx);
-        dl_stream.messages.push(dl_envelope);
-        dl_stream.notify.notify_waiters();
-
-        // Remove from original group's pending
-        if let Some(stream) = store.streams.get_mut(&self.subject)
-            && let Some(group) = stream.groups.get_mut(&self.group)
-        {
-            group.pending.remove(&self.envelope.message_id);
-        }
-
-        Ok(())
+        store.dead_letter(&self.subject, &self.group, &self.envelope, reason)
     }
 }
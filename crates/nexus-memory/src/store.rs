@@ -1,67 +1,334 @@
-use std::collections::{HashMap, HashSet, VecDeque};
+use std::cmp::Reverse;
+use std::collections::hash_map::Entry;
+use std::collections::{BinaryHeap, HashMap, VecDeque};
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::{Mutex, Notify};
+use tokio::time::Instant;
 
-use gbe_nexus::{Envelope, StreamConfig};
+use gbe_nexus::{
+    DeadLetter, Envelope, GroupInfo, StartPosition, StreamConfig, TransportError, now_millis,
+};
 
 pub(crate) type SharedStore = Arc<Mutex<StreamStore>>;
 
 pub(crate) struct StreamStore {
     pub streams: HashMap<String, StreamData>,
+    /// Woken on every publish to any stream, for wildcard subscriptions.
+    pub notify: Arc<Notify>,
 }
 
 pub(crate) struct StreamData {
-    /// Messages in insertion order. Index is the cursor position.
-    pub messages: Vec<Envelope>,
-    /// Quick lookup: `message_id` -> index in messages vec.
+    /// Messages in insertion order.
+    pub messages: VecDeque<Envelope>,
+    /// When each message was appended (unix millis, never decreasing), like
+    /// the time part of a Redis entry ID. Retention goes by this rather
+    /// than `Envelope::timestamp`, which a restored envelope carries over.
+    pub appended: VecDeque<u64>,
+    /// Sequence number of `messages[0]`. A message keeps its sequence
+    /// number while older ones are evicted; cursors refer to these.
+    pub first_seq: usize,
+    /// Quick lookup: `message_id` -> sequence number.
     pub id_index: HashMap<String, usize>,
+    /// Payload bytes across `messages`, for `max_bytes`.
+    pub payload_bytes: u64,
     pub groups: HashMap<String, ConsumerGroup>,
     pub config: Option<StreamConfig>,
     pub notify: Arc<Notify>,
+    /// Publish idempotency key -> (original message ID, forgotten at).
+    pub dedup: HashMap<String, (String, Instant)>,
 }
 
 pub(crate) struct ConsumerGroup {
-    /// Index of the last delivered message. None means start from beginning.
+    /// Sequence number of the last delivered message. None (or one before
+    /// `first_seq`) means start from the beginning.
     pub cursor: Option<usize>,
-    pub pending: HashSet<String>,
+    /// Pending message ID -> deliveries so far.
+    pub pending: HashMap<String, u32>,
     pub redeliver: VecDeque<String>,
+    /// Nak'd-with-delay messages, ordered by when they become due.
+    pub delayed: BinaryHeap<Reverse<(Instant, String)>>,
 }
 
 impl StreamStore {
     pub fn new() -> Self {
         Self {
             streams: HashMap::new(),
+            notify: Arc::new(Notify::new()),
         }
     }
 
+    /// Append a message to `subject`, creating the stream if needed, then
+    /// evict whatever the stream's retention limits no longer allow.
+    pub fn publish(&mut self, subject: &str, envelope: Envelope) {
+        let stream = self.get_or_create_stream(subject);
+        stream.push(envelope);
+        stream.enforce_retention();
+        self.notify.notify_waiters();
+    }
+
     pub fn get_or_create_stream(&mut self, subject: &str) -> &mut StreamData {
         self.streams
             .entry(subject.to_string())
             .or_insert_with(|| StreamData {
-                messages: Vec::new(),
+                messages: VecDeque::new(),
+                appended: VecDeque::new(),
+                first_seq: 0,
                 id_index: HashMap::new(),
+                payload_bytes: 0,
                 groups: HashMap::new(),
                 config: None,
                 notify: Arc::new(Notify::new()),
+                dedup: HashMap::new(),
             })
     }
+
+    /// Write `envelope` to its domain's dead-letter stream and drop it from
+    /// the group's pending set.
+    pub fn dead_letter(
+        &mut self,
+        subject: &str,
+        group: &str,
+        envelope: &Envelope,
+        reason: &str,
+    ) -> Result<(), TransportError> {
+        let dl_envelope = DeadLetter::envelope(envelope, reason)?;
+
+        // Insert into dead-letter stream
+        let dl_subject = dl_envelope.subject.clone();
+        self.publish(&dl_subject, dl_envelope);
+
+        // Remove from original group's pending
+        if let Some(stream) = self.streams.get_mut(subject)
+            && let Some(group) = stream.groups.get_mut(group)
+        {
+            group.pending.remove(&envelope.message_id);
+        }
+
+        Ok(())
+    }
+}
+
+impl StreamData {
+    /// Sequence number the next message will get.
+    pub fn end_seq(&self) -> usize {
+        self.first_seq + self.messages.len()
+    }
+
+    /// The message with sequence number `seq`, if still in the stream.
+    pub fn get(&self, seq: usize) -> Option<&Envelope> {
+        self.messages.get(seq.checked_sub(self.first_seq)?)
+    }
+
+    /// Cursor for a new group starting at `start`.
+    pub fn start_cursor(&self, start: &StartPosition) -> Option<usize> {
+        match start {
+            StartPosition::Latest => self.end_seq().checked_sub(1),
+            StartPosition::Earliest => None,
+            StartPosition::Timestamp(ts) => {
+                // The last message appended before this timestamp
+                let before = self.appended.partition_point(|t| t < ts);
+                (self.first_seq + before).checked_sub(1)
+            }
+            StartPosition::Id(id) => self.id_index.get(id).copied(),
+        }
+    }
+
+    /// Record `key` for a publish of `message_id`, unless it was already
+    /// seen within `window`: then return the original message ID instead.
+    pub fn check_dedup(
+        &mut self,
+        key: String,
+        message_id: String,
+        window: Duration,
+    ) -> Option<String> {
+        let now = Instant::now();
+        self.dedup.retain(|_, (_, expires_at)| *expires_at > now);
+        match self.dedup.entry(key) {
+            Entry::Occupied(seen) => Some(seen.get().0.clone()),
+            Entry::Vacant(slot) => {
+                slot.insert((message_id, now + window));
+                None
+            }
+        }
+    }
+
+    /// Evict the oldest messages until the stream is within its configured
+    /// `max_age`, `max_msgs` and `max_bytes` (payload bytes). Returns how
+    /// many were evicted.
+    ///
+    /// Age goes by append time and, like `MINID`, keeps a message appended
+    /// exactly at the cutoff.
+    #[allow(clippy::cast_possible_truncation)] // any practical max_age fits in u64 millis
+    pub fn enforce_retention(&mut self) -> usize {
+        let Some(config) = &self.config else {
+            return 0;
+        };
+
+        let mut evict = 0;
+        if !config.max_age.is_zero() {
+            let cutoff_ms = now_millis().saturating_sub(config.max_age.as_millis() as u64);
+            evict = self.appended.partition_point(|t| *t < cutoff_ms);
+        }
+        if let Some(max_msgs) = config.max_msgs {
+            let max_msgs = usize::try_from(max_msgs).unwrap_or(usize::MAX);
+            evict = evict.max(self.messages.len().saturating_sub(max_msgs));
+        }
+        if let Some(max_bytes) = config.max_bytes {
+            let mut bytes = self.payload_bytes;
+            let mut n = 0;
+            while bytes > max_bytes && n < self.messages.len() {
+                bytes -= self.messages[n].payload.len() as u64;
+                n += 1;
+            }
+            evict = evict.max(n);
+        }
+
+        self.evict_front(evict)
+    }
+
+    /// Evict up to `n` messages from the front of the stream, dropping them
+    /// from every group's pending set. Returns how many were evicted.
+    pub fn evict_front(&mut self, n: usize) -> usize {
+        let n = n.min(self.messages.len());
+        for envelope in self.messages.drain(..n) {
+            self.id_index.remove(&envelope.message_id);
+            self.payload_bytes -= envelope.payload.len() as u64;
+            for group in self.groups.values_mut() {
+                group.pending.remove(&envelope.message_id);
+            }
+        }
+        self.appended.drain(..n);
+        self.first_seq += n;
+        n
+    }
+
+    /// Append time of the oldest message any group still has to
+    /// acknowledge: its oldest pending message, or the first one past its
+    /// cursor.
+    pub fn oldest_unacked(&self) -> Option<u64> {
+        self.groups
+            .values()
+            .flat_map(|group| {
+                let next = group.cursor.map_or(0, |c| c + 1).max(self.first_seq);
+                group
+                    .pending
+                    .keys()
+                    .filter_map(|id| self.id_index.get(id).copied())
+                    .chain(std::iter::once(next))
+            })
+            .filter_map(|seq| self.appended.get(seq - self.first_seq))
+            .copied()
+            .min()
+    }
+
+    /// Where `group` stands on this stream, if it has joined it. Consumers
+    /// are not tracked by name here.
+    pub fn group_info(&self, subject: &str, group: &str) -> Option<GroupInfo> {
+        let consumer_group = self.groups.get(group)?;
+        let next = consumer_group
+            .cursor
+            .map_or(0, |c| c + 1)
+            .max(self.first_seq);
+        Some(GroupInfo {
+            subject: subject.to_string(),
+            group: group.to_string(),
+            length: self.messages.len() as u64,
+            last_delivered_id: consumer_group
+                .cursor
+                .and_then(|c| self.get(c))
+                .map(|env| env.message_id.clone()),
+            pending: consumer_group.pending.len() as u64,
+            lag: self.end_seq().saturating_sub(next) as u64,
+            consumers: Vec::new(),
+        })
+    }
+
+    /// Append a message and wake consumers.
+    pub fn push(&mut self, envelope: Envelope) {
+        let now_ms = now_millis();
+        let appended = self
+            .appended
+            .back()
+            .map_or(now_ms, |last| now_ms.max(*last));
+        self.id_index
+            .insert(envelope.message_id.clone(), self.end_seq());
+        self.payload_bytes += envelope.payload.len() as u64;
+        self.appended.push_back(appended);
+        self.messages.push_back(envelope);
+        self.notify.notify_waiters();
+    }
+
+    /// Remove every message matching `pred`, keeping the index, group
+    /// cursors and pending sets consistent. Returns how many were removed.
+    pub fn remove_where(&mut self, mut pred: impl FnMut(&Envelope) -> bool) -> usize {
+        let mut kept = VecDeque::with_capacity(self.messages.len());
+        let mut kept_appended = VecDeque::with_capacity(self.messages.len());
+        let mut removed = Vec::new();
+        // kept_through[i] = messages kept among the first i + 1
+        let mut kept_through = Vec::with_capacity(self.messages.len());
+
+        for (envelope, appended) in self.messages.drain(..).zip(self.appended.drain(..)) {
+            if pred(&envelope) {
+                self.payload_bytes -= envelope.payload.len() as u64;
+                removed.push(envelope.message_id);
+            } else {
+                kept.push_back(envelope);
+                kept_appended.push_back(appended);
+            }
+            kept_through.push(kept.len());
+        }
+        self.messages = kept;
+        self.appended = kept_appended;
+
+        if removed.is_empty() {
+            return 0;
+        }
+
+        // Later messages move up into the gaps
+        self.id_index.clear();
+        for (i, env) in self.messages.iter().enumerate() {
+            self.id_index
+                .insert(env.message_id.clone(), self.first_seq + i);
+        }
+
+        for group in self.groups.values_mut() {
+            for id in &removed {
+                group.pending.remove(id);
+            }
+            if let Some(cursor) = group.cursor
+                && let Some(idx) = cursor.checked_sub(self.first_seq)
+            {
+                group.cursor = (self.first_seq + kept_through[idx]).checked_sub(1);
+            }
+        }
+
+        removed.len()
+    }
 }
 
 impl ConsumerGroup {
     pub fn new(cursor: Option<usize>) -> Self {
         Self {
             cursor,
-            pending: HashSet::new(),
+            pending: HashMap::new(),
             redeliver: VecDeque::new(),
+            delayed: BinaryHeap::new(),
         }
     }
-}
 
-/// Extract the domain (second token) from a dot-delimited subject.
-///
-/// `gbe.tasks.email-send.queue` → `"tasks"`
-pub(crate) fn extract_domain(subject: &str) -> &str {
-    subject.split('.').nth(1).unwrap_or("unknown")
+    /// Move delayed messages that are due onto the redeliver queue.
+    /// Returns when the next still-delayed message becomes due.
+    pub fn release_due(&mut self, now: Instant) -> Option<Instant> {
+        while let Some(Reverse((due, _))) = self.delayed.peek() {
+            if *due > now {
+                return Some(*due);
+            }
+            let Reverse((_, msg_id)) = self.delayed.pop().expect("peeked entry");
+            self.redeliver.push_back(msg_id);
+        }
+        None
+    }
 }
 
 #[cfg(test)]
@@ -69,10 +336,128 @@ mod tests {
     use super::*;
 
     #[test]
-    fn test_extract_domain() {
-        assert_eq!(extract_domain("gbe.tasks.email-send.queue"), "tasks");
-        assert_eq!(extract_domain("gbe.notify.topic.alerts"), "notify");
-        assert_eq!(extract_domain("gbe._deadletter.tasks"), "_deadletter");
-        assert_eq!(extract_domain("single"), "unknown");
+    fn test_release_due_in_order() {
+        let now = Instant::now();
+        let mut group = ConsumerGroup::new(None);
+        group
+            .delayed
+            .push(Reverse((now + Duration::from_secs(5), "late".to_string())));
+        group.delayed.push(Reverse((now, "due-b".to_string())));
+        group.delayed.push(Reverse((
+            now.checked_sub(Duration::from_secs(1)).unwrap(),
+            "due-a".to_string(),
+        )));
+
+        let next = group.release_due(now);
+
+        assert_eq!(group.redeliver, ["due-a", "due-b"]);
+        assert_eq!(next, Some(now + Duration::from_secs(5)));
+        assert_eq!(group.delayed.len(), 1);
+    }
+
+    #[test]
+    fn test_remove_where_keeps_cursors_consistent() {
+        let mut store = StreamStore::new();
+        let stream = store.get_or_create_stream("gbe.test.remove");
+        let ids: Vec<String> = (0..4)
+            .map(|i| {
+                let env = Envelope::new(
+                    "gbe.test.remove".to_string(),
+                    bytes::Bytes::from(format!("m{i}")),
+                    None,
+                );
+                let id = env.message_id.clone();
+                stream.push(env);
+                id
+            })
+            .collect();
+
+        // "behind" has delivered m0..=m2 with m1 pending; "start" has only seen m0
+        let mut behind = ConsumerGroup::new(Some(2));
+        behind.pending.insert(ids[1].clone(), 1);
+        stream.groups.insert("behind".to_string(), behind);
+        stream
+            .groups
+            .insert("start".to_string(), ConsumerGroup::new(Some(0)));
+
+        let removed =
+            stream.remove_where(|env| env.message_id == ids[0] || env.message_id == ids[1]);
+
+        assert_eq!(removed, 2);
+        assert_eq!(stream.messages.len(), 2);
+        assert_eq!(stream.id_index[&ids[2]], 0);
+        assert_eq!(stream.id_index[&ids[3]], 1);
+        assert!(!stream.id_index.contains_key(&ids[0]));
+        // m2 is now index 0, so "behind" still resumes at m3
+        assert_eq!(stream.groups["behind"].cursor, Some(0));
+        assert!(stream.groups["behind"].pending.is_empty());
+        // Everything "start" had seen is gone: it resumes from the beginning
+        assert_eq!(stream.groups["start"].cursor, None);
+    }
+
+    #[tokio::test]
+    async fn test_check_dedup_window() {
+        let mut store = StreamStore::new();
+        let stream = store.get_or_create_stream("gbe.test.dedup");
+        let window = Duration::from_millis(100);
+
+        assert_eq!(stream.check_dedup("k".into(), "m1".into(), window), None);
+        assert_eq!(
+            stream.check_dedup("k".into(), "m2".into(), window),
+            Some("m1".to_string())
+        );
+        assert_eq!(
+            stream.check_dedup("other".into(), "m3".into(), window),
+            None
+        );
+
+        tokio::time::sleep(window).await;
+        assert_eq!(stream.check_dedup("k".into(), "m4".into(), window), None);
+        assert_eq!(stream.dedup.len(), 1, "expired keys are forgotten");
+    }
+
+    #[test]
+    fn test_enforce_retention_evicts_oldest() {
+        let subject = "gbe.test.retention";
+        let mut store = StreamStore::new();
+        store.get_or_create_stream(subject).config = Some(StreamConfig {
+            subject: subject.to_string(),
+            max_age: Duration::ZERO,
+            max_bytes: Some(6),
+            max_msgs: Some(3),
+        });
+
+        for i in 0..5 {
+            let env = Envelope::new(
+                subject.to_string(),
+                bytes::Bytes::from(format!("m{i}")),
+                None,
+            );
+            store.publish(subject, env);
+        }
+        let payloads = |store: &StreamStore| -> Vec<bytes::Bytes> {
+            store.streams[subject]
+                .messages
+                .iter()
+                .map(|e| e.payload.clone())
+                .collect()
+        };
+        assert_eq!(payloads(&store), ["m2", "m3", "m4"]);
+
+        // Larger payload pushes the stream over max_bytes
+        let env = Envelope::new(subject.to_string(), bytes::Bytes::from("big"), None);
+        store.publish(subject, env);
+        assert_eq!(payloads(&store), ["m4", "big"]);
+
+        // An age limit evicts what was appended before the cutoff, whatever
+        // timestamp the envelope carries
+        let stream = store.get_or_create_stream(subject);
+        stream.messages[1].timestamp = 0;
+        stream.appended[0] = 0;
+        stream.config.as_mut().unwrap().max_age = Duration::from_secs(60);
+        assert_eq!(stream.enforce_retention(), 1);
+        assert_eq!(payloads(&store), ["big"]);
+        assert_eq!(store.streams[subject].first_seq, 5);
+        assert_eq!(store.streams[subject].payload_bytes, 3);
     }
 }
@@ -1,24 +1,58 @@
 use async_trait::async_trait;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
+use tokio::sync::watch;
 use tokio_util::sync::CancellationToken;
 
 use gbe_nexus::TransportError;
 
-pub(crate) struct MemorySubscription {
+/// A running consumer loop, shared by its subscription and the transport.
+pub(crate) struct ConsumerHandle {
     pub(crate) token: CancellationToken,
     pub(crate) active: Arc<AtomicBool>,
+    /// Set by the loop once it has stopped and drained.
+    pub(crate) done: watch::Receiver<bool>,
+}
+
+impl ConsumerHandle {
+    /// Stop fetching and wait for the loop to drain.
+    pub(crate) async fn stop(&self) {
+        self.cancel();
+        self.finished().await;
+    }
+
+    /// Stop fetching without waiting.
+    pub(crate) fn cancel(&self) {
+        self.token.cancel();
+        self.active.store(false, Ordering::Release);
+    }
+
+    pub(crate) async fn finished(&self) {
+        // A dropped sender means the loop is gone too.
+        let _ = self.done.clone().wait_for(|done| *done).await;
+    }
+
+    pub(crate) fn is_finished(&self) -> bool {
+        *self.done.borrow()
+    }
+}
+
+pub(crate) struct MemorySubscription {
+    pub(crate) handle: Arc<ConsumerHandle>,
 }
 
 #[async_trait]
 impl gbe_nexus::Subscription for MemorySubscription {
     async fn unsubscribe(&self) -> Result<(), TransportError> {
-        self.token.cancel();
-        self.active.store(false, Ordering::Release);
+        self.handle.stop().await;
         Ok(())
     }
 
     fn is_active(&self) -> bool {
-        self.active.load(Ordering::Acquire)
+        self.handle.active.load(Ordering::Acquire)
+    }
+
+    async fn finished(&self) {
+        self.handle.finished().await;
     }
 }
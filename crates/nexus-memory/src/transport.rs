@@ -3,27 +3,33 @@ use bytes::Bytes;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
-use tokio::sync::Mutex;
+use tokio::sync::{Mutex, mpsc, watch};
 use tokio_util::sync::CancellationToken;
 
 use gbe_nexus::{
-    Envelope, MessageHandler, PublishOpts, StartPosition, StreamConfig, SubscribeOpts,
-    TransportError,
+    DeadLetter, Envelope, GroupInfo, MessageHandler, MessageStream, PublishOpts, Sink,
+    StartPosition, StreamConfig, SubscribeOpts, TransportError, domain_dead_letter_subject,
+    is_wildcard, now_millis, subject_matches, validate_group, validate_pattern,
 };
 
 use crate::consumer::{ConsumerParams, run_consumer_loop};
-use crate::store::{ConsumerGroup, SharedStore, StreamStore};
-use crate::subscription::MemorySubscription;
+use crate::message::MemoryMessage;
+use crate::store::{ConsumerGroup, SharedStore, StreamData, StreamStore};
+use crate::subscription::{ConsumerHandle, MemorySubscription};
 
 #[derive(Debug, Clone)]
 pub struct MemoryTransportConfig {
     pub max_payload_size: usize,
+    /// How long a publish `idempotency_key` is remembered per subject.
+    /// A repeat within the window returns the original message ID.
+    pub dedup_window: Duration,
 }
 
 impl Default for MemoryTransportConfig {
     fn default() -> Self {
         Self {
             max_payload_size: 1_048_576, // 1MB
+            dedup_window: Duration::from_secs(120),
         }
     }
 }
@@ -32,6 +38,8 @@ pub struct MemoryTransport {
     store: SharedStore,
     config: MemoryTransportConfig,
     closed: AtomicBool,
+    /// Consumer loops to drain on `close`.
+    consumers: std::sync::Mutex<Vec<Arc<ConsumerHandle>>>,
 }
 
 impl MemoryTransport {
@@ -41,6 +49,7 @@ impl MemoryTransport {
             store: Arc::new(Mutex::new(StreamStore::new())),
             config,
             closed: AtomicBool::new(false),
+            consumers: std::sync::Mutex::new(Vec::new()),
         }
     }
 
@@ -50,16 +59,8 @@ impl MemoryTransport {
         }
         Ok(())
     }
-}
 
-#[async_trait]
-impl gbe_nexus::Transport for MemoryTransport {
-    async fn publish(
-        &self,
-        subject: &str,
-        payload: Bytes,
-        opts: Option<PublishOpts>,
-    ) -> Result<String, TransportError> {
+    fn check_publishable(&self, subject: &str, payload: &Bytes) -> Result<(), TransportError> {
         self.check_closed()?;
 
         if payload.len() > self.config.max_payload_size {
@@ -69,80 +70,160 @@ impl gbe_nexus::Transport for MemoryTransport {
             });
         }
 
-        let trace_id = opts.and_then(|o| o.trace_id);
-        let envelope = Envelope::new(subject.to_string(), payload, trace_id);
-        let message_id = envelope.message_id.clone();
-
-        let mut store = self.store.lock().await;
-        let stream = store.get_or_create_stream(subject);
-        let idx = stream.messages.len();
-        stream.id_index.insert(message_id.clone(), idx);
-        stream.messages.push(envelope);
-        stream.notify.notify_waiters();
-
-        Ok(message_id)
+        if is_wildcard(subject) {
+            return Err(TransportError::Publish(format!(
+                "cannot publish to wildcard subject {subject}"
+            )));
+        }
+        Ok(())
     }
 
-    async fn subscribe(
+    /// Join `group` to the streams of `subject` and spawn its consumer loop.
+    async fn start_consumer(
         &self,
         subject: &str,
         group: &str,
-        handler: Box<dyn MessageHandler>,
-        opts: Option<SubscribeOpts>,
-    ) -> Result<Box<dyn gbe_nexus::Subscription>, TransportError> {
+        sink: Sink<MemoryMessage>,
+        opts: SubscribeOpts,
+    ) -> Result<MemorySubscription, TransportError> {
         self.check_closed()?;
 
-        let opts = opts.unwrap_or_default();
         let token = CancellationToken::new();
         let active = Arc::new(AtomicBool::new(true));
 
+        validate_pattern(subject)?;
+        validate_group(group)?;
+        let wildcard = is_wildcard(subject);
+        if wildcard && matches!(opts.start_from, StartPosition::Id(_)) {
+            return Err(TransportError::Subscribe(
+                "StartPosition::Id requires a literal subject".to_string(),
+            ));
+        }
+
         let notify = {
             let mut store = self.store.lock().await;
-            let stream = store.get_or_create_stream(subject);
-
-            // Set cursor based on StartPosition
-            let cursor = match &opts.start_from {
-                StartPosition::Latest => {
-                    if stream.messages.is_empty() {
-                        None
-                    } else {
-                        Some(stream.messages.len() - 1)
-                    }
-                }
-                StartPosition::Earliest => None,
-                StartPosition::Timestamp(ts) => {
-                    // Find the last message index before this timestamp
-                    stream
-                        .messages
-                        .iter()
-                        .enumerate()
-                        .rev()
-                        .find(|(_, env)| env.timestamp < *ts)
-                        .map(|(i, _)| i)
-                }
-                StartPosition::Id(id) => stream.id_index.get(id).copied(),
-            };
 
-            stream
-                .groups
-                .entry(group.to_string())
-                .or_insert_with(|| ConsumerGroup::new(cursor));
+            // Join streams that exist now at start_from. A wildcard
+            // subscription joins streams created later from their start.
+            let subjects: Vec<String> = if wildcard {
+                store
+                    .streams
+                    .keys()
+                    .filter(|s| subject_matches(subject, s))
+                    .cloned()
+                    .collect()
+            } else {
+                vec![subject.to_string()]
+            };
+            for s in subjects {
+                let stream = store.get_or_create_stream(&s);
+                let cursor = stream.start_cursor(&opts.start_from);
+                stream
+                    .groups
+                    .entry(group.to_string())
+                    .or_insert_with(|| ConsumerGroup::new(cursor));
+            }
 
-            stream.notify.clone()
+            if wildcard {
+                store.notify.clone()
+            } else {
+                store.get_or_create_stream(subject).notify.clone()
+            }
         };
 
+        let (done, done_rx) = watch::channel(false);
         tokio::spawn(run_consumer_loop(ConsumerParams {
             store: self.store.clone(),
             subject: subject.to_string(),
             group: group.to_string(),
-            handler,
+            sink,
             opts,
             token: token.clone(),
             active: active.clone(),
             notify,
+            done,
         }));
 
-        Ok(Box::new(MemorySubscription { token, active }))
+        let handle = Arc::new(ConsumerHandle {
+            token,
+            active,
+            done: done_rx,
+        });
+        let mut consumers = self.consumers.lock().unwrap();
+        consumers.retain(|c| !c.is_finished());
+        consumers.push(handle.clone());
+        Ok(MemorySubscription { handle })
+    }
+
+    async fn append(
+        &self,
+        envelope: Envelope,
+        idempotency_key: Option<String>,
+    ) -> Result<String, TransportError> {
+        let message_id = envelope.message_id.clone();
+        let subject = envelope.subject.clone();
+
+        let mut store = self.store.lock().await;
+        if let Some(key) = idempotency_key {
+            let stream = store.get_or_create_stream(&subject);
+            if let Some(original) =
+                stream.check_dedup(key, message_id.clone(), self.config.dedup_window)
+            {
+                return Ok(original);
+            }
+        }
+        store.publish(&subject, envelope);
+
+        Ok(message_id)
+    }
+}
+
+#[async_trait]
+impl gbe_nexus::Transport for MemoryTransport {
+    async fn publish(
+        &self,
+        subject: &str,
+        payload: Bytes,
+        opts: Option<PublishOpts>,
+    ) -> Result<String, TransportError> {
+        self.check_publishable(subject, &payload)?;
+
+        let opts = opts.unwrap_or_default();
+        let mut envelope = Envelope::new(subject.to_string(), payload, opts.trace_id);
+        envelope.ordering_key = opts.ordering_key;
+        self.append(envelope, opts.idempotency_key).await
+    }
+
+    async fn publish_envelope(&self, envelope: Envelope) -> Result<String, TransportError> {
+        self.check_publishable(&envelope.subject, &envelope.payload)?;
+        self.append(envelope, None).await
+    }
+
+    async fn subscribe(
+        &self,
+        subject: &str,
+        group: &str,
+        handler: Box<dyn MessageHandler>,
+        opts: Option<SubscribeOpts>,
+    ) -> Result<Box<dyn gbe_nexus::Subscription>, TransportError> {
+        let opts = opts.unwrap_or_default();
+        let sink = Sink::handler(handler, &opts);
+        let subscription = self.start_consumer(subject, group, sink, opts).await?;
+        Ok(Box::new(subscription))
+    }
+
+    async fn pull(
+        &self,
+        subject: &str,
+        group: &str,
+        opts: Option<SubscribeOpts>,
+    ) -> Result<MessageStream, TransportError> {
+        let opts = opts.unwrap_or_default();
+        let (tx, rx) = mpsc::channel(opts.batch_size.max(1) as usize);
+        let subscription = self
+            .start_consumer(subject, group, Sink::channel(tx), opts)
+            .await?;
+        Ok(MessageStream::new(rx, Box::new(subscription)))
     }
 
     async fn ensure_stream(&self, config: StreamConfig) -> Result<(), TransportError> {
@@ -151,69 +232,212 @@ impl gbe_nexus::Transport for MemoryTransport {
         let mut store = self.store.lock().await;
         let stream = store.get_or_create_stream(&config.subject);
         stream.config = Some(config);
+        stream.enforce_retention();
         Ok(())
     }
 
-    #[allow(clippy::cast_possible_truncation)] // millis since epoch fits in u64 until year 584556
-    async fn trim_stream(&self, subject: &str, max_age: Duration) -> Result<u64, TransportError> {
+    async fn stream_config(&self, subject: &str) -> Result<Option<StreamConfig>, TransportError> {
+        self.check_closed()?;
+
+        let store = self.store.lock().await;
+        Ok(store.streams.get(subject).and_then(|s| s.config.clone()))
+    }
+
+    async fn stream_configs(&self) -> Result<Vec<StreamConfig>, TransportError> {
         self.check_closed()?;
 
-        let now_ms = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as u64;
-        let cutoff_ms = now_ms.saturating_sub(max_age.as_millis() as u64);
+        let store = self.store.lock().await;
+        let mut configs: Vec<StreamConfig> = store
+            .streams
+            .values()
+            .filter_map(|s| s.config.clone())
+            .collect();
+        configs.sort_by(|a, b| a.subject.cmp(&b.subject));
+        Ok(configs)
+    }
+
+    #[allow(clippy::cast_possible_truncation)] // any practical max_age fits in u64 millis
+    async fn trim_stream(&self, subject: &str, max_age: Duration) -> Result<u64, TransportError> {
+        let cutoff_ms = now_millis().saturating_sub(max_age.as_millis() as u64);
+        self.trim_stream_before(subject, cutoff_ms).await
+    }
+
+    async fn trim_stream_before(
+        &self,
+        subject: &str,
+        before_ms: u64,
+    ) -> Result<u64, TransportError> {
+        self.check_closed()?;
 
         let mut store = self.store.lock().await;
         let Some(stream) = store.streams.get_mut(subject) else {
             return Ok(0);
         };
 
-        // Count messages at the front that are expired
-        let expired_count = stream
-            .messages
-            .iter()
-            .take_while(|env| env.timestamp <= cutoff_ms)
-            .count();
+        // Expire from the front only, by append time, like an ID-based trim
+        let expired = stream.appended.partition_point(|t| *t < before_ms);
+        Ok(stream.evict_front(expired) as u64)
+    }
 
-        if expired_count == 0 {
-            return Ok(0);
+    async fn oldest_unacked(&self, subject: &str) -> Result<Option<u64>, TransportError> {
+        self.check_closed()?;
+
+        let store = self.store.lock().await;
+        Ok(store
+            .streams
+            .get(subject)
+            .and_then(StreamData::oldest_unacked))
+    }
+
+    async fn group_info(
+        &self,
+        subject: &str,
+        group: &str,
+    ) -> Result<Option<GroupInfo>, TransportError> {
+        self.check_closed()?;
+
+        let store = self.store.lock().await;
+        Ok(store
+            .streams
+            .get(subject)
+            .and_then(|stream| stream.group_info(subject, group)))
+    }
+
+    async fn list_groups(&self, subject: &str) -> Result<Vec<String>, TransportError> {
+        self.check_closed()?;
+
+        let store = self.store.lock().await;
+        let mut groups: Vec<String> = store
+            .streams
+            .get(subject)
+            .map(|stream| stream.groups.keys().cloned().collect())
+            .unwrap_or_default();
+        groups.sort_unstable();
+        Ok(groups)
+    }
+
+    async fn delete_group(&self, subject: &str, group: &str) -> Result<bool, TransportError> {
+        self.check_closed()?;
+
+        let mut store = self.store.lock().await;
+        Ok(store
+            .streams
+            .get_mut(subject)
+            .is_some_and(|stream| stream.groups.remove(group).is_some()))
+    }
+
+    async fn set_group_position(
+        &self,
+        subject: &str,
+        group: &str,
+        position: StartPosition,
+    ) -> Result<(), TransportError> {
+        self.check_closed()?;
+
+        let mut store = self.store.lock().await;
+        let Some(stream) = store
+            .streams
+            .get_mut(subject)
+            .filter(|stream| stream.groups.contains_key(group))
+        else {
+            return Err(TransportError::Stream(format!(
+                "no group {group} on {subject}"
+            )));
+        };
+        let cursor = stream.start_cursor(&position);
+        if let Some(consumer_group) = stream.groups.get_mut(group) {
+            consumer_group.cursor = cursor;
         }
+        stream.notify.notify_waiters();
+        Ok(())
+    }
+
+    async fn remove_idle_consumers(
+        &self,
+        _subject: &str,
+        _group: &str,
+        _min_idle: Duration,
+    ) -> Result<Vec<String>, TransportError> {
+        self.check_closed()?;
+        Ok(Vec::new())
+    }
 
-        // Remove expired IDs from index and group pending sets
-        let expired_ids: Vec<String> = stream.messages[..expired_count]
+    async fn list_dead_letters(
+        &self,
+        domain: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<DeadLetter>, TransportError> {
+        self.check_closed()?;
+
+        let store = self.store.lock().await;
+        let Some(stream) = store.streams.get(&domain_dead_letter_subject(domain)) else {
+            return Ok(Vec::new());
+        };
+        Ok(stream
+            .messages
             .iter()
-            .map(|env| env.message_id.clone())
-            .collect();
+            .filter_map(parse_dead_letter)
+            .take(limit.unwrap_or(usize::MAX))
+            .collect())
+    }
 
-        for id in &expired_ids {
-            stream.id_index.remove(id);
-            for group in stream.groups.values_mut() {
-                group.pending.remove(id);
-            }
-        }
+    async fn replay_dead_letters(
+        &self,
+        domain: &str,
+        ids: Option<&[String]>,
+    ) -> Result<u64, TransportError> {
+        self.check_closed()?;
 
-        // Remove the expired messages from the front
-        stream.messages.drain(..expired_count);
+        let selected: Vec<DeadLetter> = {
+            let store = self.store.lock().await;
+            let Some(stream) = store.streams.get(&domain_dead_letter_subject(domain)) else {
+                return Ok(0);
+            };
+            stream
+                .messages
+                .iter()
+                .filter(|env| is_selected(ids, &env.message_id))
+                .filter_map(parse_dead_letter)
+                .collect()
+        };
 
-        // Rebuild id_index since indices shifted
-        stream.id_index.clear();
-        for (i, env) in stream.messages.iter().enumerate() {
-            stream.id_index.insert(env.message_id.clone(), i);
-        }
+        // One at a time, so a failed publish leaves the rest dead-lettered
+        let mut replayed = 0;
+        for dl in selected {
+            let opts = PublishOpts {
+                trace_id: dl.original.trace_id.clone(),
+                ordering_key: dl.original.ordering_key.clone(),
+                ..Default::default()
+            };
+            self.publish(
+                dl.original_subject(),
+                dl.original.payload.clone(),
+                Some(opts),
+            )
+            .await?;
 
-        // Adjust group cursors
-        for group in stream.groups.values_mut() {
-            if let Some(cursor) = &mut group.cursor {
-                if *cursor < expired_count {
-                    group.cursor = None;
-                } else {
-                    *cursor -= expired_count;
-                }
+            let mut store = self.store.lock().await;
+            if let Some(stream) = store.streams.get_mut(&domain_dead_letter_subject(domain)) {
+                stream.remove_where(|env| env.message_id == dl.id);
             }
+            replayed += 1;
         }
+        Ok(replayed)
+    }
+
+    async fn purge_dead_letters(
+        &self,
+        domain: &str,
+        ids: Option<&[String]>,
+    ) -> Result<u64, TransportError> {
+        self.check_closed()?;
 
-        Ok(expired_count as u64)
+        let mut store = self.store.lock().await;
+        let Some(stream) = store.streams.get_mut(&domain_dead_letter_subject(domain)) else {
+            return Ok(0);
+        };
+        let removed = stream.remove_where(|env| is_selected(ids, &env.message_id));
+        Ok(removed as u64)
     }
 
     async fn ping(&self) -> Result<bool, TransportError> {
@@ -222,6 +446,27 @@ impl gbe_nexus::Transport for MemoryTransport {
 
     async fn close(&self) -> Result<(), TransportError> {
         self.closed.store(true, Ordering::Release);
+        let consumers = std::mem::take(&mut *self.consumers.lock().unwrap());
+        // Cancel them all first so they drain side by side.
+        for consumer in &consumers {
+            consumer.cancel();
+        }
+        for consumer in &consumers {
+            consumer.finished().await;
+        }
         Ok(())
     }
 }
+
+/// Entries that aren't dead letters are skipped rather than failing the listing.
+fn parse_dead_letter(envelope: &Envelope) -> Option<DeadLetter> {
+    DeadLetter::from_envelope(envelope)
+        .inspect_err(|e| {
+            tracing::warn!(message_id = %envelope.message_id, "skipping malformed dead letter: {e}");
+        })
+        .ok()
+}
+
+fn is_selected(ids: Option<&[String]>, id: &str) -> bool {
+    ids.is_none_or(|ids| ids.iter().any(|selected| selected == id))
+}
@@ -0,0 +1,15 @@
+//! Shared transport conformance suite against the in-memory backend.
+
+use std::sync::Arc;
+
+use gbe_nexus::Transport;
+use gbe_nexus_memory::{MemoryTransport, MemoryTransportConfig};
+
+#[allow(clippy::unused_async)] // factory signature is fixed by the suite
+async fn transport() -> Option<Arc<dyn Transport>> {
+    Some(Arc::new(MemoryTransport::new(
+        MemoryTransportConfig::default(),
+    )))
+}
+
+gbe_nexus_testkit::transport_conformance_tests!(transport);
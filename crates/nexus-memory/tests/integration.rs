@@ -112,6 +112,7 @@ async fn test_publish_returns_message_id() {
 async fn test_payload_too_large() {
     let transport = MemoryTransport::new(MemoryTransportConfig {
         max_payload_size: 100,
+        ..Default::default()
     });
 
     let subject = test_subject("large");
@@ -132,6 +133,39 @@ async fn test_payload_too_large() {
     );
 }
 
+#[tokio::test]
+async fn test_idempotency_key_expires_after_window() {
+    let transport = MemoryTransport::new(MemoryTransportConfig {
+        dedup_window: Duration::from_millis(100),
+        ..Default::default()
+    });
+    let subject = test_subject("dedup-window");
+    let opts = || {
+        Some(PublishOpts {
+            idempotency_key: Some("order-42".to_string()),
+            ..Default::default()
+        })
+    };
+
+    let first = transport
+        .publish(&subject, Bytes::from("a"), opts())
+        .await
+        .unwrap();
+    let repeat = transport
+        .publish(&subject, Bytes::from("a"), opts())
+        .await
+        .unwrap();
+    assert_eq!(first, repeat);
+
+    tokio::time::sleep(Duration::from_millis(150)).await;
+
+    let after_window = transport
+        .publish(&subject, Bytes::from("a"), opts())
+        .await
+        .unwrap();
+    assert_ne!(first, after_window);
+}
+
 #[tokio::test]
 async fn test_publish_subscribe_roundtrip() {
     let transport = create_transport();
@@ -262,6 +296,89 @@ async fn test_dead_letter_routing() {
     dl_sub.unsubscribe().await.unwrap();
 }
 
+#[tokio::test]
+#[allow(clippy::items_after_statements)]
+async fn test_max_deliveries_dead_letters_poison_message() {
+    let transport = create_transport();
+    let subject = test_subject("poison");
+
+    // Always fails, recording the attempt number it was handed
+    struct FailingHandler {
+        counts: Arc<tokio::sync::Mutex<Vec<u32>>>,
+    }
+
+    #[async_trait]
+    impl MessageHandler for FailingHandler {
+        async fn handle(&self, msg: &dyn Message) -> Result<(), TransportError> {
+            self.counts.lock().await.push(msg.delivery_count());
+            Err(TransportError::Other("test: always fails".to_string()))
+        }
+    }
+
+    let counts = Arc::new(tokio::sync::Mutex::new(Vec::new()));
+
+    let sub = transport
+        .subscribe(
+            &subject,
+            "poison-group",
+            Box::new(FailingHandler {
+                counts: counts.clone(),
+            }),
+            Some(SubscribeOpts {
+                start_from: StartPosition::Earliest,
+                max_deliveries: Some(3),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    transport
+        .publish(&subject, Bytes::from("poison"), None)
+        .await
+        .unwrap();
+
+    let dl_payloads: Arc<tokio::sync::Mutex<Vec<Bytes>>> =
+        Arc::new(tokio::sync::Mutex::new(vec![]));
+    let dl_notify = Arc::new(Notify::new());
+
+    let dl_sub = transport
+        .subscribe(
+            "gbe._deadletter.test",
+            "dl-check",
+            Box::new(CollectingHandler {
+                payloads: dl_payloads.clone(),
+                notify: dl_notify.clone(),
+            }),
+            Some(SubscribeOpts {
+                start_from: StartPosition::Earliest,
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+
+    tokio::time::timeout(Duration::from_secs(2), dl_notify.notified())
+        .await
+        .expect("timed out waiting for dead letter message");
+
+    assert_eq!(*counts.lock().await, [1, 2, 3]);
+
+    let received = dl_payloads.lock().await;
+    assert_eq!(received.len(), 1);
+    let payload: serde_json::Value = serde_json::from_slice(&received[0]).unwrap();
+    assert_eq!(
+        payload["reason"],
+        "max deliveries exceeded after 3 attempts"
+    );
+    assert_eq!(payload["original_envelope"]["subject"], subject);
+
+    sub.unsubscribe().await.unwrap();
+    dl_sub.unsubscribe().await.unwrap();
+}
+
 #[tokio::test]
 async fn test_close_prevents_operations() {
     let transport = create_transport();
@@ -389,6 +506,79 @@ async fn test_nak_triggers_redelivery() {
     sub.unsubscribe().await.unwrap();
 }
 
+#[tokio::test]
+#[allow(clippy::items_after_statements)]
+async fn test_nak_with_delay_holds_back_redelivery() {
+    let transport = create_transport();
+    let subject = test_subject("nak-delay");
+
+    // Naks the first delivery with a delay, records when each delivery lands
+    struct DelayedNakHandler {
+        deliveries: Arc<tokio::sync::Mutex<Vec<tokio::time::Instant>>>,
+        notify: Arc<Notify>,
+    }
+
+    #[async_trait]
+    impl MessageHandler for DelayedNakHandler {
+        async fn handle(&self, msg: &dyn Message) -> Result<(), TransportError> {
+            let mut deliveries = self.deliveries.lock().await;
+            deliveries.push(tokio::time::Instant::now());
+            if deliveries.len() == 1 {
+                msg.nak(Some(Duration::from_millis(300))).await?;
+            } else {
+                msg.ack().await?;
+            }
+            self.notify.notify_one();
+            Ok(())
+        }
+    }
+
+    let deliveries = Arc::new(tokio::sync::Mutex::new(Vec::new()));
+    let notify = Arc::new(Notify::new());
+
+    // max_inflight 1: the delayed message still occupies the only slot,
+    // and must come back without waiting on capacity.
+    let sub = transport
+        .subscribe(
+            &subject,
+            "delay-group",
+            Box::new(DelayedNakHandler {
+                deliveries: deliveries.clone(),
+                notify: notify.clone(),
+            }),
+            Some(SubscribeOpts {
+                start_from: StartPosition::Earliest,
+                max_inflight: 1,
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+
+    tokio::time::sleep(Duration::from_millis(50)).await;
+
+    transport
+        .publish(&subject, Bytes::from("later"), None)
+        .await
+        .unwrap();
+
+    for _ in 0..2 {
+        tokio::time::timeout(Duration::from_secs(2), notify.notified())
+            .await
+            .expect("timed out waiting for delivery");
+    }
+
+    let deliveries = deliveries.lock().await;
+    assert_eq!(deliveries.len(), 2);
+    let waited = deliveries[1] - deliveries[0];
+    assert!(
+        waited >= Duration::from_millis(300),
+        "redelivered after {waited:?}, before the 300ms delay"
+    );
+
+    sub.unsubscribe().await.unwrap();
+}
+
 #[tokio::test]
 async fn test_trim_stream() {
     let transport = create_transport();
@@ -400,7 +590,9 @@ async fn test_trim_stream() {
         .await
         .unwrap();
 
-    // Trim with zero max_age should remove everything
+    // Trim with zero max_age should remove everything appended before now;
+    // an entry appended in the same millisecond is kept
+    tokio::time::sleep(Duration::from_millis(5)).await;
     let trimmed = transport
         .trim_stream(&subject, Duration::from_secs(0))
         .await
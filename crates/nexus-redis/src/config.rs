@@ -1,9 +1,14 @@
+use std::time::Duration;
+
 /// Configuration for the Redis transport backend.
 pub struct RedisTransportConfig {
     /// Redis connection URL (e.g. `redis://localhost:6379`).
     pub url: String,
     /// Maximum payload size in bytes. Publishes exceeding this are rejected.
     pub max_payload_size: usize,
+    /// How long a publish `idempotency_key` is remembered per subject.
+    /// A repeat within the window returns the original message ID.
+    pub dedup_window: Duration,
 }
 
 impl Default for RedisTransportConfig {
@@ -11,6 +16,7 @@ impl Default for RedisTransportConfig {
         Self {
             url: "redis://127.0.0.1:6379".to_string(),
             max_payload_size: 1_048_576, // 1MB
+            dedup_window: Duration::from_secs(120),
         }
     }
 }
@@ -2,36 +2,69 @@ use redis::streams::StreamReadReply;
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::{Duration, Instant};
+use tokio::sync::watch;
 use tokio_util::sync::CancellationToken;
 
-use gbe_nexus::{Envelope, MessageHandler, SubscribeOpts, TransportError};
+use gbe_nexus::{
+    Envelope, Sink, SubscribeOpts, TransportError, is_wildcard, max_deliveries_reason, now_millis,
+    subject_matches,
+};
 
 use crate::error::map_redis_err;
 use crate::message::RedisMessage;
+use crate::subject::{delayed_key, key_to_subject, scan_glob, subject_to_key};
 
 pub(crate) struct ConsumerParams {
     pub conn: redis::aio::ConnectionManager,
-    pub stream_key: String,
+    /// Literal subject or wildcard pattern.
+    pub subject: String,
     pub group: String,
     pub consumer_id: String,
-    pub handler: Box<dyn MessageHandler>,
+    pub sink: Sink<RedisMessage>,
     pub opts: SubscribeOpts,
     pub token: CancellationToken,
     pub active: Arc<AtomicBool>,
+    /// Set once the loop has stopped and drained.
+    pub done: watch::Sender<bool>,
 }
 
+/// How often a wildcard subscription looks for newly created matching streams.
+const DISCOVERY_INTERVAL: Duration = Duration::from_secs(1);
+
 pub(crate) async fn run_consumer_loop(mut p: ConsumerParams) {
-    let start_id = match &p.opts.start_from {
-        gbe_nexus::StartPosition::Latest => "$".to_string(),
-        gbe_nexus::StartPosition::Earliest => "0".to_string(),
-        gbe_nexus::StartPosition::Id(id) => id.clone(),
-        gbe_nexus::StartPosition::Timestamp(ts) => format!("{ts}-0"),
-    };
+    consume(&mut p).await;
+    p.sink.drain(p.opts.drain_timeout).await;
+    p.active.store(false, Ordering::Release);
+    p.done.send_replace(true);
+    tracing::debug!(subject = %p.subject, group = %p.group, "consumer loop exited");
+}
 
-    if let Err(e) = create_group(&mut p.conn, &p.stream_key, &p.group, &start_id).await {
-        tracing::error!(stream = %p.stream_key, group = %p.group, "failed to create consumer group: {e}");
-        p.active.store(false, Ordering::Release);
-        return;
+async fn consume(p: &mut ConsumerParams) {
+    let wildcard = is_wildcard(&p.subject);
+
+    // Join the streams that exist now at start_from
+    let initial = if wildcard {
+        match discover_streams(&mut p.conn, &p.subject).await {
+            Ok(keys) => keys,
+            Err(e) => {
+                tracing::error!(subject = %p.subject, "failed to discover streams: {e}");
+                return;
+            }
+        }
+    } else {
+        vec![subject_to_key(&p.subject)]
+    };
+    let mut streams = Vec::with_capacity(initial.len());
+    for stream_key in initial {
+        let joined = match start_id(p, &stream_key).await {
+            Ok(start_id) => create_group(&mut p.conn, &stream_key, &p.group, &start_id).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = joined {
+            tracing::error!(stream = %stream_key, group = %p.group, "failed to join stream: {e}");
+            return;
+        }
+        streams.push(stream_key);
     }
 
     // Safety: ack_timeout is a Duration, millis fit in u64 for any practical timeout
@@ -39,47 +72,79 @@ pub(crate) async fn run_consumer_loop(mut p: ConsumerParams) {
     let ack_timeout_ms = p.opts.ack_timeout.as_millis() as u64;
     let mut last_reclaim = Instant::now();
     let reclaim_interval = p.opts.ack_timeout / 2;
+    let mut last_discovery = Instant::now();
 
     loop {
         if p.token.is_cancelled() {
             break;
         }
 
-        // Backpressure: check pending count
-        if let Ok(pending) = get_pending_count(&mut p.conn, &p.stream_key, &p.group).await
-            && pending >= p.opts.max_inflight as usize
-        {
-            tokio::time::sleep(Duration::from_millis(100)).await;
-            continue;
+        // Phase 0: Wildcards pick up streams created since the last look,
+        // reading them from the start
+        if wildcard && last_discovery.elapsed() >= DISCOVERY_INTERVAL {
+            join_new_streams(p, &mut streams).await;
+            last_discovery = Instant::now();
+        }
+
+        // Phase 1: Deliver delayed naks that are due. They are already
+        // pending, so backpressure does not hold them back.
+        let mut next_due: Option<u64> = None;
+        for stream_key in &streams {
+            if let Some(due) = process_delayed(p, stream_key).await {
+                next_due = Some(next_due.map_or(due, |d| d.min(due)));
+            }
         }
 
-        // Phase 1: Reclaim timed-out messages periodically
+        // Phase 2: Reclaim timed-out messages periodically
         if last_reclaim.elapsed() >= reclaim_interval {
-            process_reclaimed(
-                &mut p.conn,
-                &p.stream_key,
-                &p.group,
-                &p.consumer_id,
-                ack_timeout_ms,
-                p.opts.batch_size,
-                &*p.handler,
-            )
-            .await;
+            for stream_key in &streams {
+                process_reclaimed(p, stream_key, ack_timeout_ms).await;
+            }
             last_reclaim = Instant::now();
         }
 
-        // Phase 2: Read new messages
+        // Backpressure: check pending counts and never read past max_inflight
+        let mut readable = Vec::with_capacity(streams.len());
+        let mut count = p.opts.batch_size;
+        for stream_key in &streams {
+            let pending = get_pending_count(&mut p.conn, stream_key, &p.group)
+                .await
+                .unwrap_or(0);
+            if pending < p.opts.max_inflight as usize {
+                #[allow(clippy::cast_possible_truncation)] // bounded by max_inflight (u32)
+                let room = (p.opts.max_inflight as usize - pending) as u32;
+                count = count.min(room);
+                readable.push(stream_key.as_str());
+            }
+        }
+        if readable.is_empty() {
+            tokio::time::sleep(Duration::from_millis(100)).await;
+            continue;
+        }
+
+        // Don't block past the next delayed nak's due time, or the next discovery
+        let mut block_ms =
+            next_due.map_or(2000, |due| due.saturating_sub(now_millis()).clamp(1, 2000));
+        if wildcard {
+            #[allow(clippy::cast_possible_truncation)] // at most DISCOVERY_INTERVAL
+            let until_discovery = DISCOVERY_INTERVAL
+                .saturating_sub(last_discovery.elapsed())
+                .as_millis() as u64;
+            block_ms = block_ms.min(until_discovery.max(1));
+        }
+
+        // Phase 3: Read new messages
         let result: Result<StreamReadReply, _> = redis::cmd("XREADGROUP")
             .arg("GROUP")
             .arg(&p.group)
             .arg(&p.consumer_id)
             .arg("COUNT")
-            .arg(p.opts.batch_size)
+            .arg(count)
             .arg("BLOCK")
-            .arg(2000_u64)
+            .arg(block_ms)
             .arg("STREAMS")
-            .arg(&p.stream_key)
-            .arg(">")
+            .arg(&readable)
+            .arg(vec![">"; readable.len()])
             .query_async(&mut p.conn)
             .await;
 
@@ -87,15 +152,11 @@ pub(crate) async fn run_consumer_loop(mut p: ConsumerParams) {
             Ok(reply) => {
                 for key in &reply.keys {
                     for entry in &key.ids {
-                        process_entry(
-                            &p.conn,
-                            &p.stream_key,
-                            &p.group,
-                            &entry.id,
-                            entry,
-                            &*p.handler,
-                        )
-                        .await;
+                        // Unsubscribed while blocked: leave the rest pending for reclaim.
+                        if p.token.is_cancelled() {
+                            break;
+                        }
+                        process_entry(p, &key.key, &entry.id, entry).await;
                     }
                 }
             }
@@ -103,15 +164,100 @@ pub(crate) async fn run_consumer_loop(mut p: ConsumerParams) {
                 // XREADGROUP returns nil (not an error) on timeout with no messages.
                 // Actual errors get a backoff.
                 if !is_timeout_nil(&e) {
-                    tracing::warn!(stream = %p.stream_key, "XREADGROUP error: {e}");
+                    tracing::warn!(subject = %p.subject, "XREADGROUP error: {e}");
+                    // A matched stream was deleted: rejoin whatever still exists
+                    if wildcard && e.to_string().contains("NOGROUP") {
+                        streams.clear();
+                        join_new_streams(p, &mut streams).await;
+                        last_discovery = Instant::now();
+                    }
                     tokio::time::sleep(Duration::from_secs(1)).await;
                 }
             }
         }
     }
+}
 
-    p.active.store(false, Ordering::Release);
-    tracing::debug!(stream = %p.stream_key, group = %p.group, "consumer loop exited");
+/// Entry ID a new group on `stream_key` starts from, per `start_from`.
+async fn start_id(p: &mut ConsumerParams, stream_key: &str) -> Result<String, TransportError> {
+    position_id(&mut p.conn, stream_key, &p.opts.start_from).await
+}
+
+/// Last-delivered entry ID that makes a group on `stream_key` read from
+/// `position` onwards.
+pub(crate) async fn position_id(
+    conn: &mut redis::aio::ConnectionManager,
+    stream_key: &str,
+    position: &gbe_nexus::StartPosition,
+) -> Result<String, TransportError> {
+    Ok(match position {
+        gbe_nexus::StartPosition::Latest => "$".to_string(),
+        gbe_nexus::StartPosition::Earliest => "0".to_string(),
+        gbe_nexus::StartPosition::Id(id) => resolve_entry_id(conn, stream_key, id).await?,
+        gbe_nexus::StartPosition::Timestamp(ts) => format!("{ts}-0"),
+    })
+}
+
+/// Join matching streams not yet in `streams`, from their first entry.
+async fn join_new_streams(p: &mut ConsumerParams, streams: &mut Vec<String>) {
+    let keys = match discover_streams(&mut p.conn, &p.subject).await {
+        Ok(keys) => keys,
+        Err(e) => {
+            tracing::warn!(subject = %p.subject, "failed to discover streams: {e}");
+            return;
+        }
+    };
+    for stream_key in keys {
+        if streams.contains(&stream_key) {
+            continue;
+        }
+        match create_group(&mut p.conn, &stream_key, &p.group, "0").await {
+            Ok(()) => {
+                tracing::debug!(stream = %stream_key, subject = %p.subject, "joined stream");
+                streams.push(stream_key);
+            }
+            Err(e) => {
+                tracing::warn!(stream = %stream_key, group = %p.group, "failed to join stream: {e}");
+            }
+        }
+    }
+}
+
+/// Stream keys whose subject matches `pattern`. SCAN narrows by the
+/// pattern's literal prefix; the exact match is checked per key.
+pub(crate) async fn discover_streams(
+    conn: &mut redis::aio::ConnectionManager,
+    pattern: &str,
+) -> Result<Vec<String>, TransportError> {
+    let glob = scan_glob(pattern);
+    let mut keys = Vec::new();
+    let mut cursor: u64 = 0;
+    loop {
+        let (next, batch): (u64, Vec<String>) = redis::cmd("SCAN")
+            .arg(cursor)
+            .arg("MATCH")
+            .arg(&glob)
+            .arg("COUNT")
+            .arg(1000)
+            .arg("TYPE")
+            .arg("stream")
+            .query_async(conn)
+            .await
+            .map_err(map_redis_err)?;
+        keys.extend(
+            batch
+                .into_iter()
+                .filter(|key| subject_matches(pattern, &key_to_subject(key))),
+        );
+        if next == 0 {
+            break;
+        }
+        cursor = next;
+    }
+    // SCAN may return a key more than once
+    keys.sort_unstable();
+    keys.dedup();
+    Ok(keys)
 }
 
 async fn create_group(
@@ -136,6 +282,70 @@ async fn create_group(
     }
 }
 
+/// How far the Redis server clock may run behind the publisher's when
+/// looking up an entry by the time in its message ID.
+const CLOCK_SKEW_MS: u64 = 60_000;
+
+/// Map a `StartPosition::Id` to a stream entry ID.
+///
+/// Callers only ever see envelope message IDs (ULIDs), so look up the entry
+/// carrying that envelope. It was appended after the ULID was minted, so
+/// the scan starts at the ULID's time, less `CLOCK_SKEW_MS`, rather than at
+/// the head of the stream. Native entry IDs (`{ms}-{seq}`) pass through.
+async fn resolve_entry_id(
+    conn: &mut redis::aio::ConnectionManager,
+    stream_key: &str,
+    id: &str,
+) -> Result<String, TransportError> {
+    if is_entry_id(id) {
+        return Ok(id.to_string());
+    }
+
+    let mut start = ulid::Ulid::from_string(id).map_or_else(
+        |_| "-".to_string(),
+        |ulid| format!("{}-0", ulid.timestamp_ms().saturating_sub(CLOCK_SKEW_MS)),
+    );
+    loop {
+        let reply: redis::streams::StreamRangeReply = redis::cmd("XRANGE")
+            .arg(stream_key)
+            .arg(&start)
+            .arg("+")
+            .arg("COUNT")
+            .arg(500)
+            .query_async(conn)
+            .await
+            .map_err(map_redis_err)?;
+
+        let Some(last) = reply.ids.last() else {
+            return Err(TransportError::Subscribe(format!(
+                "message {id} not found in {stream_key}"
+            )));
+        };
+
+        for entry in &reply.ids {
+            let envelope_json: Option<String> = entry.get("envelope");
+            if let Some(json) = envelope_json
+                && let Ok(envelope) = serde_json::from_str::<Envelope>(&json)
+                && envelope.message_id == id
+            {
+                return Ok(entry.id.clone());
+            }
+        }
+
+        start = format!("({}", last.id);
+    }
+}
+
+/// Whether `id` is a native Redis stream entry ID (`{ms}-{seq}`).
+fn is_entry_id(id: &str) -> bool {
+    id.split_once('-').is_some_and(|(ms, seq)| {
+        !ms.is_empty()
+            && !seq.is_empty()
+            && ms.bytes().all(|b| b.is_ascii_digit())
+            && seq.bytes().all(|b| b.is_ascii_digit())
+    })
+}
+
 async fn get_pending_count(
     conn: &mut redis::aio::ConnectionManager,
     stream_key: &str,
@@ -157,66 +367,216 @@ async fn get_pending_count(
     Ok(0)
 }
 
-async fn process_reclaimed(
-    conn: &mut redis::aio::ConnectionManager,
-    stream_key: &str,
-    group: &str,
-    consumer_id: &str,
-    min_idle_ms: u64,
-    count: u32,
-    handler: &dyn MessageHandler,
-) {
+/// Claim back and deliver delayed naks whose due time has passed.
+///
+/// Returns the due time (unix ms) of the next still-delayed message, if any.
+async fn process_delayed(p: &mut ConsumerParams, stream_key: &str) -> Option<u64> {
+    let key = delayed_key(stream_key, &p.group);
+
+    let due: Vec<String> = match redis::cmd("ZRANGEBYSCORE")
+        .arg(&key)
+        .arg("-inf")
+        .arg(now_millis())
+        .arg("LIMIT")
+        .arg(0)
+        .arg(p.opts.batch_size)
+        .query_async(&mut p.conn)
+        .await
+    {
+        Ok(ids) => ids,
+        Err(e) => {
+            tracing::warn!(key = %key, "failed to read delayed messages: {e}");
+            return None;
+        }
+    };
+
+    for entry_id in &due {
+        // Whichever consumer removes the entry from the delay set owns its
+        // redelivery. If we crash before handling it, it stays in the PEL
+        // and is reclaimed after ack_timeout like any other message.
+        let removed: i64 = redis::cmd("ZREM")
+            .arg(&key)
+            .arg(entry_id)
+            .query_async(&mut p.conn)
+            .await
+            .unwrap_or(0);
+        if removed == 0 {
+            continue;
+        }
+
+        let result: Result<redis::Value, _> = redis::cmd("XCLAIM")
+            .arg(stream_key)
+            .arg(&p.group)
+            .arg(&p.consumer_id)
+            .arg(0)
+            .arg(entry_id)
+            .query_async(&mut p.conn)
+            .await;
+        match result {
+            Ok(redis::Value::Array(entries)) => handle_claimed(p, stream_key, &entries).await,
+            Ok(_) => {}
+            Err(e) => {
+                tracing::warn!(entry_id = %entry_id, "failed to claim delayed message: {e}");
+            }
+        }
+    }
+
+    let next: Vec<(String, f64)> = redis::cmd("ZRANGE")
+        .arg(&key)
+        .arg(0)
+        .arg(0)
+        .arg("WITHSCORES")
+        .query_async(&mut p.conn)
+        .await
+        .unwrap_or_default();
+    // Scores are the unix-ms due times written by nak
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    next.first().map(|(_, due)| *due as u64)
+}
+
+async fn process_reclaimed(p: &mut ConsumerParams, stream_key: &str, min_idle_ms: u64) {
+    // JUSTID: take ownership without bumping the delivery counter, since
+    // some of these may be delayed naks that we leave alone.
     let result: Result<redis::Value, _> = redis::cmd("XAUTOCLAIM")
         .arg(stream_key)
-        .arg(group)
-        .arg(consumer_id)
+        .arg(&p.group)
+        .arg(&p.consumer_id)
         .arg(min_idle_ms)
         .arg("0-0")
         .arg("COUNT")
-        .arg(count)
-        .query_async(conn)
+        .arg(p.opts.batch_size)
+        .arg("JUSTID")
+        .query_async(&mut p.conn)
         .await;
 
-    // XAUTOCLAIM returns: [next-start-id, [[id, [field, value, ...]], ...], deleted-ids]
-    if let Ok(redis::Value::Array(parts)) = &result
-        && parts.len() >= 2
-        && let redis::Value::Array(entries) = &parts[1]
+    // XAUTOCLAIM JUSTID returns: [next-start-id, [id, ...], deleted-ids]
+    let ids: Vec<String> = match &result {
+        Ok(redis::Value::Array(parts)) if parts.len() >= 2 => match &parts[1] {
+            redis::Value::Array(ids) => ids
+                .iter()
+                .filter_map(|id| match id {
+                    redis::Value::BulkString(b) => Some(String::from_utf8_lossy(b).to_string()),
+                    _ => None,
+                })
+                .collect(),
+            _ => return,
+        },
+        _ => return,
+    };
+    if ids.is_empty() {
+        return;
+    }
+
+    // Delayed naks sit idle in the PEL on purpose; process_delayed owns them.
+    let scores: Vec<Option<f64>> = match redis::cmd("ZMSCORE")
+        .arg(delayed_key(stream_key, &p.group))
+        .arg(&ids)
+        .query_async(&mut p.conn)
+        .await
     {
-        for entry_val in entries {
-            if let Some((entry_id, fields)) = parse_stream_entry(entry_val)
-                && let Some(envelope_json) = fields.get("envelope")
-            {
-                match serde_json::from_str::<Envelope>(envelope_json) {
-                    Ok(envelope) => {
-                        let msg = RedisMessage {
-                            envelope,
-                            stream_key: stream_key.to_string(),
-                            group: group.to_string(),
-                            entry_id: entry_id.clone(),
-                            conn: conn.clone(),
-                            acked: AtomicBool::new(false),
-                        };
-                        let _ = handler.handle(&msg).await;
-                    }
-                    Err(e) => {
-                        tracing::warn!(
-                            entry_id = %entry_id,
-                            "failed to deserialize reclaimed envelope: {e}"
-                        );
-                    }
-                }
+        Ok(scores) => scores,
+        Err(e) => {
+            tracing::warn!(stream = %stream_key, "failed to check delayed messages: {e}");
+            return;
+        }
+    };
+    let ready: Vec<&String> = ids
+        .iter()
+        .zip(scores)
+        .filter_map(|(id, score)| score.is_none().then_some(id))
+        .collect();
+    if ready.is_empty() {
+        return;
+    }
+
+    let result: Result<redis::Value, _> = redis::cmd("XCLAIM")
+        .arg(stream_key)
+        .arg(&p.group)
+        .arg(&p.consumer_id)
+        .arg(0)
+        .arg(&ready)
+        .query_async(&mut p.conn)
+        .await;
+    match result {
+        Ok(redis::Value::Array(entries)) => handle_claimed(p, stream_key, &entries).await,
+        Ok(_) => {}
+        Err(e) => tracing::warn!(stream = %stream_key, "failed to claim messages: {e}"),
+    }
+}
+
+/// Deliver raw claimed entries (`[[id, [field, value, ...]], ...]`) to the handler,
+/// dead-lettering any that have used up `max_deliveries`.
+async fn handle_claimed(p: &mut ConsumerParams, stream_key: &str, entries: &[redis::Value]) {
+    for entry_val in entries {
+        let Some((entry_id, fields)) = parse_stream_entry(entry_val) else {
+            continue;
+        };
+        let Some(envelope_json) = fields.get("envelope") else {
+            continue;
+        };
+        let envelope = match serde_json::from_str::<Envelope>(envelope_json) {
+            Ok(envelope) => envelope,
+            Err(e) => {
+                tracing::warn!(
+                    entry_id = %entry_id,
+                    "failed to deserialize reclaimed envelope: {e}"
+                );
+                continue;
             }
+        };
+
+        // XCLAIM already counted this delivery
+        let delivery_count = get_delivery_count(p, stream_key, &entry_id).await;
+        let msg = RedisMessage {
+            envelope,
+            stream_key: stream_key.to_string(),
+            group: p.group.clone(),
+            entry_id: entry_id.clone(),
+            delivery_count,
+            conn: p.conn.clone(),
+            acked: AtomicBool::new(false),
+        };
+
+        if let Some(max) = p.opts.max_deliveries
+            && delivery_count > max
+        {
+            let reason = max_deliveries_reason(delivery_count - 1);
+            if let Err(e) = gbe_nexus::Message::dead_letter(&msg, &reason).await {
+                tracing::warn!(entry_id = %entry_id, "failed to dead-letter: {e}");
+            }
+            continue;
         }
+
+        p.sink.deliver(msg, &p.token).await;
     }
 }
 
+/// Times-delivered counter for a pending entry, from the extended XPENDING form.
+async fn get_delivery_count(p: &mut ConsumerParams, stream_key: &str, entry_id: &str) -> u32 {
+    let result: Result<redis::Value, _> = redis::cmd("XPENDING")
+        .arg(stream_key)
+        .arg(&p.group)
+        .arg(entry_id)
+        .arg(entry_id)
+        .arg(1)
+        .query_async(&mut p.conn)
+        .await;
+
+    // [[id, consumer, idle-ms, times-delivered]]
+    if let Ok(redis::Value::Array(rows)) = &result
+        && let Some(redis::Value::Array(row)) = rows.first()
+        && let Some(redis::Value::Int(count)) = row.get(3)
+    {
+        return u32::try_from(*count).unwrap_or(u32::MAX);
+    }
+    1
+}
+
 async fn process_entry(
-    conn: &redis::aio::ConnectionManager,
+    p: &ConsumerParams,
     stream_key: &str,
-    group: &str,
     entry_id: &str,
     entry: &redis::streams::StreamId,
-    handler: &dyn MessageHandler,
 ) {
     let envelope_json: Option<String> = entry.get("envelope");
     let Some(json) = envelope_json else {
@@ -229,17 +589,13 @@ async fn process_entry(
             let msg = RedisMessage {
                 envelope,
                 stream_key: stream_key.to_string(),
-                group: group.to_string(),
+                group: p.group.clone(),
                 entry_id: entry_id.to_string(),
-                conn: conn.clone(),
+                delivery_count: 1, // first read via `>`
+                conn: p.conn.clone(),
                 acked: AtomicBool::new(false),
             };
-            if let Err(e) = handler.handle(&msg).await {
-                tracing::debug!(
-                    entry_id = %entry_id,
-                    "handler returned error (claim-based nak): {e}"
-                );
-            }
+            p.sink.deliver(msg, &p.token).await;
         }
         Err(e) => {
             tracing::warn!(entry_id = %entry_id, "failed to deserialize envelope: {e}");
@@ -247,7 +603,7 @@ async fn process_entry(
     }
 }
 
-/// Parse a raw Redis Value into (`entry_id`, `field_map`) for XAUTOCLAIM results.
+/// Parse a raw Redis Value into (`entry_id`, `field_map`) for XCLAIM results.
 fn parse_stream_entry(
     val: &redis::Value,
 ) -> Option<(String, std::collections::HashMap<String, String>)> {
@@ -280,3 +636,18 @@ fn parse_stream_entry(
 fn is_timeout_nil(e: &redis::RedisError) -> bool {
     matches!(e.kind(), redis::ErrorKind::TypeError)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_entry_id() {
+        assert!(is_entry_id("1707934567000-0"));
+        assert!(is_entry_id("0-1"));
+        assert!(!is_entry_id("01HQ3K5V8X9Y2Z4A6B7C8D9E0F"));
+        assert!(!is_entry_id("1707934567000"));
+        assert!(!is_entry_id("-0"));
+        assert!(!is_entry_id("abc-0"));
+    }
+}
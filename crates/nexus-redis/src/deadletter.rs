@@ -0,0 +1,156 @@
+use redis::streams::{StreamId, StreamRangeReply};
+
+use gbe_nexus::{DeadLetter, Envelope, TransportError, domain_dead_letter_subject};
+
+use crate::error::map_redis_err;
+use crate::subject::subject_to_key;
+
+/// Redis key of the dead-letter stream for `domain`.
+pub(crate) fn dead_letter_key(domain: &str) -> String {
+    subject_to_key(&domain_dead_letter_subject(domain))
+}
+
+/// Read a dead-letter stream, oldest first, as (`entry_id`, parsed dead letter).
+/// Malformed entries parse to `None` and don't count towards `limit`.
+pub(crate) async fn read_entries(
+    conn: &mut redis::aio::ConnectionManager,
+    key: &str,
+    limit: Option<usize>,
+) -> Result<Vec<(String, Option<DeadLetter>)>, TransportError> {
+    let mut out = Vec::new();
+    let mut parsed = 0;
+    let mut start = "-".to_string();
+    loop {
+        let reply: StreamRangeReply = redis::cmd("XRANGE")
+            .arg(key)
+            .arg(&start)
+            .arg("+")
+            .arg("COUNT")
+            .arg(500)
+            .query_async(conn)
+            .await
+            .map_err(map_redis_err)?;
+
+        let Some(last) = reply.ids.last() else {
+            return Ok(out);
+        };
+        start = format!("({}", last.id);
+
+        for entry in &reply.ids {
+            if limit.is_some_and(|limit| parsed >= limit) {
+                return Ok(out);
+            }
+            let dl = parse_entry(entry);
+            parsed += usize::from(dl.is_some());
+            out.push((entry.id.clone(), dl));
+        }
+    }
+}
+
+/// Parse one dead-letter stream entry.
+///
+/// Entries written before the shared format carry the original envelope
+/// plus a separate `reason` field; those are still readable. They have no
+/// dead-letter envelope, so their `id` is the stream entry ID, never the
+/// original message's.
+fn parse_entry(entry: &StreamId) -> Option<DeadLetter> {
+    let json: String = entry.get("envelope")?;
+    let envelope = match serde_json::from_str::<Envelope>(&json) {
+        Ok(envelope) => envelope,
+        Err(e) => {
+            tracing::warn!(entry_id = %entry.id, "skipping malformed dead letter: {e}");
+            return None;
+        }
+    };
+
+    if let Some(reason) = entry.get::<String>("reason") {
+        return Some(DeadLetter {
+            id: entry.id.clone(),
+            dead_lettered_at: entry_millis(&entry.id),
+            reason,
+            original: envelope,
+        });
+    }
+
+    DeadLetter::from_envelope(&envelope)
+        .inspect_err(|e| {
+            tracing::warn!(entry_id = %entry.id, "skipping malformed dead letter: {e}");
+        })
+        .ok()
+}
+
+/// Millisecond part of a stream entry ID (`{ms}-{seq}`).
+pub(crate) fn entry_millis(entry_id: &str) -> u64 {
+    entry_id
+        .split_once('-')
+        .and_then(|(ms, _)| ms.parse().ok())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use std::collections::HashMap;
+
+    fn entry(id: &str, fields: &[(&str, &str)]) -> StreamId {
+        StreamId {
+            id: id.to_string(),
+            map: fields
+                .iter()
+                .map(|(k, v)| {
+                    (
+                        (*k).to_string(),
+                        redis::Value::BulkString(v.as_bytes().to_vec()),
+                    )
+                })
+                .collect::<HashMap<_, _>>(),
+        }
+    }
+
+    fn original() -> Envelope {
+        Envelope::new(
+            "gbe.tasks.email-send.queue".to_string(),
+            Bytes::from("hello"),
+            None,
+        )
+    }
+
+    #[test]
+    fn test_parse_shared_format() {
+        let stored = DeadLetter::envelope(&original(), "boom").unwrap();
+        let json = serde_json::to_string(&stored).unwrap();
+
+        let dl = parse_entry(&entry("1707934567000-0", &[("envelope", &json)])).unwrap();
+        assert_eq!(dl.id, stored.message_id);
+        assert_eq!(dl.reason, "boom");
+        assert_eq!(dl.original_subject(), "gbe.tasks.email-send.queue");
+    }
+
+    #[test]
+    fn test_parse_legacy_format() {
+        let original = original();
+        let json = serde_json::to_string(&original).unwrap();
+
+        let dl = parse_entry(&entry(
+            "1707934567000-0",
+            &[("envelope", &json), ("reason", "boom")],
+        ))
+        .unwrap();
+        assert_eq!(dl.id, "1707934567000-0");
+        assert_eq!(dl.dead_lettered_at, 1_707_934_567_000);
+        assert_eq!(dl.reason, "boom");
+        assert_eq!(dl.original.payload, original.payload);
+    }
+
+    #[test]
+    fn test_parse_malformed() {
+        assert!(parse_entry(&entry("1-0", &[("other", "x")])).is_none());
+        assert!(parse_entry(&entry("1-0", &[("envelope", "not json")])).is_none());
+    }
+
+    #[test]
+    fn test_dead_letter_key() {
+        assert_eq!(dead_letter_key("tasks"), "gbe:_deadletter:tasks");
+    }
+}
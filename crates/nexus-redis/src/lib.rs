@@ -1,7 +1,9 @@
 mod config;
 mod consumer;
+mod deadletter;
 mod error;
 mod message;
+mod registry;
 mod subject;
 mod subscription;
 mod transport;
@@ -2,16 +2,17 @@ use async_trait::async_trait;
 use bytes::Bytes;
 use std::sync::atomic::{AtomicBool, Ordering};
 
-use gbe_nexus::{Envelope, TransportError};
+use gbe_nexus::{DeadLetter, Envelope, TransportError, now_millis};
 
 use crate::error::map_redis_err;
-use crate::subject::extract_domain;
+use crate::subject::{delayed_key, subject_to_key};
 
 pub(crate) struct RedisMessage {
     pub(crate) envelope: Envelope,
     pub(crate) stream_key: String,
     pub(crate) group: String,
     pub(crate) entry_id: String,
+    pub(crate) delivery_count: u32,
     pub(crate) conn: redis::aio::ConnectionManager,
     pub(crate) acked: AtomicBool,
 }
@@ -26,6 +27,10 @@ impl gbe_nexus::Message for RedisMessage {
         &self.envelope.payload
     }
 
+    fn delivery_count(&self) -> u32 {
+        self.delivery_count
+    }
+
     async fn ack(&self) -> Result<(), TransportError> {
         if self.acked.swap(true, Ordering::AcqRel) {
             return Ok(()); // already acked
@@ -41,10 +46,24 @@ impl gbe_nexus::Message for RedisMessage {
         Ok(())
     }
 
-    async fn nak(&self, _delay: Option<std::time::Duration>) -> Result<(), TransportError> {
-        // Claim-based nak: do nothing. Message stays in PEL and will be
-        // reclaimed after ack_timeout via XAUTOCLAIM in the consumer loop.
-        self.acked.store(true, Ordering::Release);
+    async fn nak(&self, delay: Option<std::time::Duration>) -> Result<(), TransportError> {
+        if self.acked.swap(true, Ordering::AcqRel) {
+            return Ok(());
+        }
+        // The message stays in the PEL, parked in the group's delay set.
+        // Consumer loops skip it when reclaiming and claim it back once the
+        // due time passes, so it survives restarts. Without a delay it is
+        // due now, rather than waiting out ack_timeout.
+        #[allow(clippy::cast_possible_truncation)] // millis fit in u64 for any practical delay
+        let due_ms = now_millis() + delay.map_or(0, |d| d.as_millis() as u64);
+        let mut conn = self.conn.clone();
+        redis::cmd("ZADD")
+            .arg(delayed_key(&self.stream_key, &self.group))
+            .arg(due_ms)
+            .arg(&self.entry_id)
+            .query_async::<i64>(&mut conn)
+            .await
+            .map_err(map_redis_err)?;
         Ok(())
     }
 
@@ -54,11 +73,10 @@ impl gbe_nexus::Message for RedisMessage {
         }
         let mut conn = self.conn.clone();
 
-        let domain = extract_domain(&self.stream_key);
-        let dl_key = format!("gbe:_deadletter:{domain}");
-
+        let dl_envelope = DeadLetter::envelope(&self.envelope, reason)?;
+        let dl_key = subject_to_key(&dl_envelope.subject);
         let envelope_json =
-            serde_json::to_string(&self.envelope).map_err(TransportError::Serialization)?;
+            serde_json::to_string(&dl_envelope).map_err(TransportError::Serialization)?;
 
         // Write to dead letter stream
         redis::cmd("XADD")
@@ -66,8 +84,6 @@ impl gbe_nexus::Message for RedisMessage {
             .arg("*")
             .arg("envelope")
             .arg(&envelope_json)
-            .arg("reason")
-            .arg(reason)
             .query_async::<String>(&mut conn)
             .await
             .map_err(map_redis_err)?;
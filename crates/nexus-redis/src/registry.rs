@@ -0,0 +1,184 @@
+//! Stream config registry.
+//!
+//! `ensure_stream` records each stream's retention limits in one hash,
+//! `gbe:_streams` (field = subject, value = JSON), so every process
+//! publishing to a stream enforces the same limits:
+//!
+//! ```json
+//! { "max_age_ms": 86400000, "max_bytes": null, "max_msgs": 100000 }
+//! ```
+
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+use tokio::time::Instant;
+
+use gbe_nexus::{StreamConfig, TransportError};
+
+use crate::error::map_redis_err;
+
+/// Hash holding every registered stream config.
+pub(crate) const REGISTRY_KEY: &str = "gbe:_streams";
+
+/// How long a publisher trusts its cached copy of a stream's config.
+const CACHE_TTL: Duration = Duration::from_secs(30);
+
+#[derive(Serialize, Deserialize)]
+struct StoredConfig {
+    max_age_ms: u64,
+    max_bytes: Option<u64>,
+    max_msgs: Option<u64>,
+}
+
+#[allow(clippy::cast_possible_truncation)] // any practical max_age fits in u64 millis
+pub(crate) fn encode(config: &StreamConfig) -> Result<String, TransportError> {
+    Ok(serde_json::to_string(&StoredConfig {
+        max_age_ms: config.max_age.as_millis() as u64,
+        max_bytes: config.max_bytes,
+        max_msgs: config.max_msgs,
+    })?)
+}
+
+pub(crate) fn decode(subject: &str, json: &str) -> Option<StreamConfig> {
+    match serde_json::from_str::<StoredConfig>(json) {
+        Ok(stored) => Some(StreamConfig {
+            subject: subject.to_string(),
+            max_age: Duration::from_millis(stored.max_age_ms),
+            max_bytes: stored.max_bytes,
+            max_msgs: stored.max_msgs,
+        }),
+        Err(e) => {
+            tracing::warn!(subject = %subject, "ignoring malformed stream config: {e}");
+            None
+        }
+    }
+}
+
+/// Read one stream's config from the registry.
+pub(crate) async fn fetch(
+    conn: &mut redis::aio::ConnectionManager,
+    subject: &str,
+) -> Result<Option<StreamConfig>, TransportError> {
+    let json: Option<String> = redis::cmd("HGET")
+        .arg(REGISTRY_KEY)
+        .arg(subject)
+        .query_async(conn)
+        .await
+        .map_err(map_redis_err)?;
+    Ok(json.and_then(|json| decode(subject, &json)))
+}
+
+/// Read every stream config from the registry, sorted by subject.
+pub(crate) async fn fetch_all(
+    conn: &mut redis::aio::ConnectionManager,
+) -> Result<Vec<StreamConfig>, TransportError> {
+    let all: HashMap<String, String> = redis::cmd("HGETALL")
+        .arg(REGISTRY_KEY)
+        .query_async(conn)
+        .await
+        .map_err(map_redis_err)?;
+    let mut configs: Vec<StreamConfig> = all
+        .iter()
+        .filter_map(|(subject, json)| decode(subject, json))
+        .collect();
+    configs.sort_by(|a, b| a.subject.cmp(&b.subject));
+    Ok(configs)
+}
+
+/// Per-transport cache of registry lookups, including misses, so publishing
+/// does not cost an extra round trip. Entries refresh after `CACHE_TTL`.
+#[derive(Default)]
+pub(crate) struct ConfigCache {
+    entries: Mutex<HashMap<String, (Option<StreamConfig>, Instant)>>,
+}
+
+impl ConfigCache {
+    pub(crate) async fn get(
+        &self,
+        conn: &mut redis::aio::ConnectionManager,
+        subject: &str,
+    ) -> Result<Option<StreamConfig>, TransportError> {
+        if let Some((config, fetched)) = self.entries.lock().unwrap().get(subject)
+            && fetched.elapsed() < CACHE_TTL
+        {
+            return Ok(config.clone());
+        }
+        let config = fetch(conn, subject).await?;
+        self.store(subject.to_string(), config.clone());
+        Ok(config)
+    }
+
+    /// Cache a config this transport just registered.
+    pub(crate) fn insert(&self, config: StreamConfig) {
+        self.store(config.subject.clone(), Some(config));
+    }
+
+    fn store(&self, subject: String, config: Option<StreamConfig>) {
+        self.entries
+            .lock()
+            .unwrap()
+            .insert(subject, (config, Instant::now()));
+    }
+}
+
+/// XADD/XTRIM arguments enforcing `config` at `now_ms`:
+/// (`max_msgs`, `MINID` for `max_age`, `max_bytes`), with `0` / `""` for none.
+pub(crate) fn trim_args(config: Option<&StreamConfig>, now_ms: u64) -> (u64, String, u64) {
+    let Some(config) = config else {
+        return (0, String::new(), 0);
+    };
+    let min_id = if config.max_age.is_zero() {
+        String::new()
+    } else {
+        #[allow(clippy::cast_possible_truncation)] // any practical max_age fits in u64 millis
+        let max_age_ms = config.max_age.as_millis() as u64;
+        format!("{}-0", now_ms.saturating_sub(max_age_ms))
+    };
+    (
+        config.max_msgs.unwrap_or(0),
+        min_id,
+        config.max_bytes.unwrap_or(0),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(max_age: Duration, max_bytes: Option<u64>, max_msgs: Option<u64>) -> StreamConfig {
+        StreamConfig {
+            subject: "gbe.tasks.email-send.queue".to_string(),
+            max_age,
+            max_bytes,
+            max_msgs,
+        }
+    }
+
+    #[test]
+    fn test_encode_decode_round_trip() {
+        let original = config(Duration::from_secs(3600), None, Some(1000));
+        let json = encode(&original).unwrap();
+        assert_eq!(
+            json,
+            r#"{"max_age_ms":3600000,"max_bytes":null,"max_msgs":1000}"#
+        );
+        assert_eq!(decode(&original.subject, &json), Some(original));
+        assert_eq!(decode("gbe.x", "not json"), None);
+    }
+
+    #[test]
+    fn test_trim_args() {
+        assert_eq!(trim_args(None, 5000), (0, String::new(), 0));
+
+        let unlimited_age = config(Duration::ZERO, Some(1024), Some(10));
+        assert_eq!(
+            trim_args(Some(&unlimited_age), 5000),
+            (10, String::new(), 1024)
+        );
+
+        let aged = config(Duration::from_secs(2), None, None);
+        assert_eq!(trim_args(Some(&aged), 5000), (0, "3000-0".to_string(), 0));
+    }
+}
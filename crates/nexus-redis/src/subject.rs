@@ -5,11 +5,47 @@ pub(crate) fn subject_to_key(subject: &str) -> String {
     subject.replace('.', ":")
 }
 
-/// Extract the domain (second token) from a colon-delimited Redis stream key.
+/// Convert a Redis stream key back to its dot-delimited subject.
 ///
-/// `gbe:tasks:email-send:queue` → `"tasks"`
-pub(crate) fn extract_domain(stream_key: &str) -> &str {
-    stream_key.split(':').nth(1).unwrap_or("unknown")
+/// `gbe:tasks:email-send:queue` → `gbe.tasks.email-send.queue`
+pub(crate) fn key_to_subject(stream_key: &str) -> String {
+    stream_key.replace(':', ".")
+}
+
+/// SCAN MATCH glob covering every stream key a subject pattern can match:
+/// the pattern's literal prefix, glob-escaped, followed by `*`.
+///
+/// `gbe.events.lifecycle.*.*` → `gbe:events:lifecycle:*`
+pub(crate) fn scan_glob(pattern: &str) -> String {
+    let mut glob = String::new();
+    for token in pattern.split('.') {
+        if token == "*" || token == ">" {
+            break;
+        }
+        for c in token.chars() {
+            if matches!(c, '*' | '?' | '[' | ']' | '\\') {
+                glob.push('\\');
+            }
+            glob.push(c);
+        }
+        glob.push(':');
+    }
+    glob.push('*');
+    glob
+}
+
+/// Sorted set holding a group's delayed naks: entry ID scored by due time (ms).
+///
+/// `gbe:tasks:email-send:queue` + `workers` → `gbe:tasks:email-send:queue:_delayed:workers`
+pub(crate) fn delayed_key(stream_key: &str, group: &str) -> String {
+    format!("{stream_key}:_delayed:{group}")
+}
+
+/// String key remembering a publish idempotency key for a stream.
+///
+/// `gbe:tasks:email-send:queue` + `order-42` → `gbe:tasks:email-send:queue:_dedup:order-42`
+pub(crate) fn dedup_key(stream_key: &str, idempotency_key: &str) -> String {
+    format!("{stream_key}:_dedup:{idempotency_key}")
 }
 
 #[cfg(test)]
@@ -33,10 +69,37 @@ mod tests {
     }
 
     #[test]
-    fn test_extract_domain() {
-        assert_eq!(extract_domain("gbe:tasks:email-send:queue"), "tasks");
-        assert_eq!(extract_domain("gbe:notify:topic:alerts"), "notify");
-        assert_eq!(extract_domain("gbe:_deadletter:tasks"), "_deadletter");
-        assert_eq!(extract_domain("single"), "unknown");
+    fn test_delayed_key() {
+        assert_eq!(
+            delayed_key("gbe:tasks:email-send:queue", "workers"),
+            "gbe:tasks:email-send:queue:_delayed:workers"
+        );
+    }
+
+    #[test]
+    fn test_key_to_subject() {
+        assert_eq!(
+            key_to_subject("gbe:tasks:email-send:queue"),
+            "gbe.tasks.email-send.queue"
+        );
+    }
+
+    #[test]
+    fn test_scan_glob() {
+        assert_eq!(
+            scan_glob("gbe.events.lifecycle.*.*"),
+            "gbe:events:lifecycle:*"
+        );
+        assert_eq!(scan_glob("gbe.jobs.>"), "gbe:jobs:*");
+        assert_eq!(scan_glob("*.jobs"), "*");
+        assert_eq!(scan_glob("gbe.a[1].*"), "gbe:a\\[1\\]:*");
+    }
+
+    #[test]
+    fn test_dedup_key() {
+        assert_eq!(
+            dedup_key("gbe:tasks:email-send:queue", "order-42"),
+            "gbe:tasks:email-send:queue:_dedup:order-42"
+        );
     }
 }
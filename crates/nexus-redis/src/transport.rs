@@ -1,24 +1,93 @@
 use async_trait::async_trait;
 use bytes::Bytes;
+use redis::streams::{
+    StreamInfoConsumersReply, StreamInfoGroupsReply, StreamPendingReply, StreamRangeReply,
+};
 use std::sync::Arc;
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
+use tokio::sync::{mpsc, watch};
 use tokio_util::sync::CancellationToken;
 
 use gbe_nexus::{
-    Envelope, MessageHandler, PublishOpts, StreamConfig, SubscribeOpts, TransportError,
+    ConsumerInfo, DeadLetter, Envelope, GroupInfo, MessageHandler, MessageStream, PublishOpts,
+    Sink, StartPosition, StreamConfig, SubscribeOpts, TransportError, is_wildcard, now_millis,
+    validate_group, validate_pattern,
 };
 
 use crate::config::RedisTransportConfig;
-use crate::consumer::{ConsumerParams, run_consumer_loop};
+use crate::consumer::{ConsumerParams, discover_streams, position_id, run_consumer_loop};
+use crate::deadletter::{dead_letter_key, entry_millis, read_entries};
 use crate::error::map_redis_err;
-use crate::subject::subject_to_key;
-use crate::subscription::RedisSubscription;
+use crate::message::RedisMessage;
+use crate::registry::{self, ConfigCache, REGISTRY_KEY};
+use crate::subject::{dedup_key, delayed_key, subject_to_key};
+use crate::subscription::{ConsumerHandle, RedisSubscription};
+
+/// Append one message and enforce the stream's retention limits atomically.
+/// With an idempotency key, first claim it, or return the message ID that
+/// first claimed it.
+///
+/// `max_bytes` is approximate: when `MEMORY USAGE` exceeds it, the stream is
+/// cut to the proportional number of newest entries.
+///
+/// KEYS: stream key, [dedup key].
+/// ARGV: message ID, envelope JSON, `max_msgs` (0 = none),
+/// `MINID` for `max_age` ('' = none), `max_bytes` (0 = none), [window (ms)].
+const PUBLISH_SCRIPT: &str = r"
+if KEYS[2] and not redis.call('SET', KEYS[2], ARGV[1], 'NX', 'PX', ARGV[6]) then
+    return redis.call('GET', KEYS[2])
+end
+redis.call('XADD', KEYS[1], '*', 'envelope', ARGV[2])
+if ARGV[3] ~= '0' then
+    redis.call('XTRIM', KEYS[1], 'MAXLEN', ARGV[3])
+end
+if ARGV[4] ~= '' then
+    redis.call('XTRIM', KEYS[1], 'MINID', ARGV[4])
+end
+local max_bytes = tonumber(ARGV[5])
+if max_bytes > 0 then
+    local used = redis.call('MEMORY', 'USAGE', KEYS[1])
+    if used and used > max_bytes then
+        local len = redis.call('XLEN', KEYS[1])
+        redis.call('XTRIM', KEYS[1], 'MAXLEN', math.max(1, math.floor(len * max_bytes / used)))
+    end
+end
+return ARGV[1]
+";
+
+/// Create the stream empty unless it exists. Redis only creates streams as
+/// a side effect, so this makes a throwaway group with MKSTREAM and destroys
+/// it again.
+///
+/// KEYS: stream key.
+const CREATE_STREAM_SCRIPT: &str = r"
+if redis.call('EXISTS', KEYS[1]) == 0 then
+    redis.call('XGROUP', 'CREATE', KEYS[1], '_create', '$', 'MKSTREAM')
+    redis.call('XGROUP', 'DESTROY', KEYS[1], '_create')
+end
+return 0
+";
+
+/// Delete a consumer unless it holds pending messages, which would be lost
+/// with it. Returns 1 if deleted.
+///
+/// KEYS: stream key. ARGV: group, consumer.
+const DELETE_CONSUMER_SCRIPT: &str = r"
+if #redis.call('XPENDING', KEYS[1], ARGV[1], '-', '+', 1, ARGV[2]) > 0 then
+    return 0
+end
+redis.call('XGROUP', 'DELCONSUMER', KEYS[1], ARGV[1], ARGV[2])
+return 1
+";
 
 pub struct RedisTransport {
     conn: redis::aio::ConnectionManager,
     config: RedisTransportConfig,
+    stream_configs: ConfigCache,
     closed: AtomicBool,
+    /// Consumer loops to drain on `close`.
+    consumers: std::sync::Mutex<Vec<Arc<ConsumerHandle>>>,
 }
 
 impl RedisTransport {
@@ -33,10 +102,39 @@ impl RedisTransport {
         Ok(Self {
             conn,
             config,
+            stream_configs: ConfigCache::default(),
             closed: AtomicBool::new(false),
+            consumers: std::sync::Mutex::new(Vec::new()),
         })
     }
 
+    /// One-off migration for streams created by versions whose
+    /// `ensure_stream` left an `_init` group behind, which pins the stream's
+    /// oldest entry as never delivered: destroy that group on every stream
+    /// matching the subject `pattern`. Returns how many were destroyed.
+    ///
+    /// Group names starting with `_` are reserved, so no consumer's group
+    /// can be caught by this.
+    ///
+    /// # Errors
+    /// Returns an error if the streams cannot be listed or a group destroyed.
+    pub async fn drop_legacy_init_groups(&self, pattern: &str) -> Result<u64, TransportError> {
+        self.check_closed()?;
+        validate_pattern(pattern)?;
+        let mut conn = self.conn.clone();
+        let mut dropped = 0;
+        for key in discover_streams(&mut conn, pattern).await? {
+            dropped += redis::cmd("XGROUP")
+                .arg("DESTROY")
+                .arg(&key)
+                .arg("_init")
+                .query_async::<u64>(&mut conn)
+                .await
+                .map_err(map_redis_err)?;
+        }
+        Ok(dropped)
+    }
+
     fn check_closed(&self) -> Result<(), TransportError> {
         if self.closed.load(Ordering::Acquire) {
             return Err(TransportError::Other("transport is closed".to_string()));
@@ -44,23 +142,7 @@ impl RedisTransport {
         Ok(())
     }
 
-    fn consumer_id() -> String {
-        let host = hostname::get().map_or_else(
-            |_| "unknown".to_string(),
-            |h| h.to_string_lossy().to_string(),
-        );
-        format!("{host}-{}", ulid::Ulid::new())
-    }
-}
-
-#[async_trait]
-impl gbe_nexus::Transport for RedisTransport {
-    async fn publish(
-        &self,
-        subject: &str,
-        payload: Bytes,
-        opts: Option<PublishOpts>,
-    ) -> Result<String, TransportError> {
+    fn check_publishable(&self, subject: &str, payload: &Bytes) -> Result<(), TransportError> {
         self.check_closed()?;
 
         if payload.len() > self.config.max_payload_size {
@@ -70,53 +152,152 @@ impl gbe_nexus::Transport for RedisTransport {
             });
         }
 
-        let trace_id = opts.and_then(|o| o.trace_id);
-        let envelope = Envelope::new(subject.to_string(), payload, trace_id);
-        let message_id = envelope.message_id.clone();
+        if is_wildcard(subject) {
+            return Err(TransportError::Publish(format!(
+                "cannot publish to wildcard subject {subject}"
+            )));
+        }
+        Ok(())
+    }
 
-        let json = serde_json::to_string(&envelope).map_err(TransportError::Serialization)?;
-        let key = subject_to_key(subject);
+    /// Run `PUBLISH_SCRIPT` for `envelope` with its stream's limits.
+    async fn append(
+        &self,
+        envelope: &Envelope,
+        idempotency_key: Option<String>,
+    ) -> Result<String, TransportError> {
+        let json = serde_json::to_string(envelope).map_err(TransportError::Serialization)?;
+        let key = subject_to_key(&envelope.subject);
 
         let mut conn = self.conn.clone();
-        redis::cmd("XADD")
-            .arg(&key)
-            .arg("*")
-            .arg("envelope")
+        let config = self
+            .stream_configs
+            .get(&mut conn, &envelope.subject)
+            .await?;
+        let (max_msgs, min_id, max_bytes) = registry::trim_args(config.as_ref(), now_millis());
+
+        let script = redis::Script::new(PUBLISH_SCRIPT);
+        let mut invocation = script.prepare_invoke();
+        invocation
+            .key(&key)
+            .arg(&envelope.message_id)
             .arg(&json)
-            .query_async::<String>(&mut conn)
+            .arg(max_msgs)
+            .arg(min_id)
+            .arg(max_bytes);
+        if let Some(idempotency_key) = idempotency_key {
+            // Safety: millis fit in u64 for any practical window
+            #[allow(clippy::cast_possible_truncation)]
+            let window_ms = self.config.dedup_window.as_millis().max(1) as u64;
+            invocation
+                .key(dedup_key(&key, &idempotency_key))
+                .arg(window_ms);
+        }
+        invocation
+            .invoke_async::<String>(&mut conn)
             .await
-            .map_err(|e| TransportError::Publish(e.to_string()))?;
+            .map_err(|e| TransportError::Publish(e.to_string()))
+    }
 
-        Ok(message_id)
+    fn consumer_id() -> String {
+        let host = hostname::get().map_or_else(
+            |_| "unknown".to_string(),
+            |h| h.to_string_lossy().to_string(),
+        );
+        format!("{host}-{}", ulid::Ulid::new())
     }
 
-    async fn subscribe(
+    /// Spawn the consumer loop for `group` on `subject`; it joins the
+    /// streams itself.
+    fn start_consumer(
         &self,
         subject: &str,
         group: &str,
-        handler: Box<dyn MessageHandler>,
-        opts: Option<SubscribeOpts>,
-    ) -> Result<Box<dyn gbe_nexus::Subscription>, TransportError> {
+        sink: Sink<RedisMessage>,
+        opts: SubscribeOpts,
+    ) -> Result<RedisSubscription, TransportError> {
         self.check_closed()?;
 
-        let opts = opts.unwrap_or_default();
-        let stream_key = subject_to_key(subject);
+        validate_pattern(subject)?;
+        validate_group(group)?;
+        if is_wildcard(subject) && matches!(opts.start_from, StartPosition::Id(_)) {
+            return Err(TransportError::Subscribe(
+                "StartPosition::Id requires a literal subject".to_string(),
+            ));
+        }
         let consumer_id = Self::consumer_id();
         let token = CancellationToken::new();
         let active = Arc::new(AtomicBool::new(true));
 
+        let (done, done_rx) = watch::channel(false);
         tokio::spawn(run_consumer_loop(ConsumerParams {
             conn: self.conn.clone(),
-            stream_key,
+            subject: subject.to_string(),
             group: group.to_string(),
             consumer_id,
-            handler,
+            sink,
             opts,
             token: token.clone(),
             active: active.clone(),
+            done,
         }));
 
-        Ok(Box::new(RedisSubscription { token, active }))
+        let handle = Arc::new(ConsumerHandle {
+            token,
+            active,
+            done: done_rx,
+        });
+        let mut consumers = self.consumers.lock().unwrap();
+        consumers.retain(|c| !c.is_finished());
+        consumers.push(handle.clone());
+        Ok(RedisSubscription { handle })
+    }
+}
+
+#[async_trait]
+impl gbe_nexus::Transport for RedisTransport {
+    async fn publish(
+        &self,
+        subject: &str,
+        payload: Bytes,
+        opts: Option<PublishOpts>,
+    ) -> Result<String, TransportError> {
+        self.check_publishable(subject, &payload)?;
+
+        let opts = opts.unwrap_or_default();
+        let mut envelope = Envelope::new(subject.to_string(), payload, opts.trace_id);
+        envelope.ordering_key = opts.ordering_key;
+        self.append(&envelope, opts.idempotency_key).await
+    }
+
+    async fn publish_envelope(&self, envelope: Envelope) -> Result<String, TransportError> {
+        self.check_publishable(&envelope.subject, &envelope.payload)?;
+        self.append(&envelope, None).await
+    }
+
+    async fn subscribe(
+        &self,
+        subject: &str,
+        group: &str,
+        handler: Box<dyn MessageHandler>,
+        opts: Option<SubscribeOpts>,
+    ) -> Result<Box<dyn gbe_nexus::Subscription>, TransportError> {
+        let opts = opts.unwrap_or_default();
+        let sink = Sink::handler(handler, &opts);
+        let subscription = self.start_consumer(subject, group, sink, opts)?;
+        Ok(Box::new(subscription))
+    }
+
+    async fn pull(
+        &self,
+        subject: &str,
+        group: &str,
+        opts: Option<SubscribeOpts>,
+    ) -> Result<MessageStream, TransportError> {
+        let opts = opts.unwrap_or_default();
+        let (tx, rx) = mpsc::channel(opts.batch_size.max(1) as usize);
+        let subscription = self.start_consumer(subject, group, Sink::channel(tx), opts)?;
+        Ok(MessageStream::new(rx, Box::new(subscription)))
     }
 
     async fn ensure_stream(&self, config: StreamConfig) -> Result<(), TransportError> {
@@ -125,40 +306,77 @@ impl gbe_nexus::Transport for RedisTransport {
         let key = subject_to_key(&config.subject);
         let mut conn = self.conn.clone();
 
-        // XGROUP CREATE with MKSTREAM ensures the stream exists.
-        // Use a sentinel group name; real groups are created by subscribe.
-        let result: Result<String, redis::RedisError> = redis::cmd("XGROUP")
-            .arg("CREATE")
-            .arg(&key)
-            .arg("_init")
-            .arg("$")
-            .arg("MKSTREAM")
-            .query_async(&mut conn)
-            .await;
+        redis::Script::new(CREATE_STREAM_SCRIPT)
+            .key(&key)
+            .invoke_async::<i64>(&mut conn)
+            .await
+            .map_err(|e| TransportError::Stream(e.to_string()))?;
 
-        match result {
-            Ok(_) => Ok(()),
-            Err(e) if e.to_string().contains("BUSYGROUP") => Ok(()),
-            Err(e) => Err(TransportError::Stream(e.to_string())),
+        redis::cmd("HSET")
+            .arg(REGISTRY_KEY)
+            .arg(&config.subject)
+            .arg(registry::encode(&config)?)
+            .query_async::<u64>(&mut conn)
+            .await
+            .map_err(|e| TransportError::Stream(e.to_string()))?;
+
+        // Apply count and age limits to what is already there; the publish
+        // script keeps enforcing them from here on.
+        let (max_msgs, min_id, _) = registry::trim_args(Some(&config), now_millis());
+        if max_msgs > 0 {
+            redis::cmd("XTRIM")
+                .arg(&key)
+                .arg("MAXLEN")
+                .arg(max_msgs)
+                .query_async::<u64>(&mut conn)
+                .await
+                .map_err(map_redis_err)?;
+        }
+        if !min_id.is_empty() {
+            redis::cmd("XTRIM")
+                .arg(&key)
+                .arg("MINID")
+                .arg(&min_id)
+                .query_async::<u64>(&mut conn)
+                .await
+                .map_err(map_redis_err)?;
         }
+
+        self.stream_configs.insert(config);
+        Ok(())
+    }
+
+    async fn stream_config(&self, subject: &str) -> Result<Option<StreamConfig>, TransportError> {
+        self.check_closed()?;
+        let mut conn = self.conn.clone();
+        registry::fetch(&mut conn, subject).await
+    }
+
+    async fn stream_configs(&self) -> Result<Vec<StreamConfig>, TransportError> {
+        self.check_closed()?;
+        let mut conn = self.conn.clone();
+        registry::fetch_all(&mut conn).await
     }
 
     #[allow(clippy::cast_possible_truncation)] // millis since epoch fits in u64 until year 584556
     async fn trim_stream(&self, subject: &str, max_age: Duration) -> Result<u64, TransportError> {
+        let min_ms = now_millis().saturating_sub(max_age.as_millis() as u64);
+        self.trim_stream_before(subject, min_ms).await
+    }
+
+    async fn trim_stream_before(
+        &self,
+        subject: &str,
+        before_ms: u64,
+    ) -> Result<u64, TransportError> {
         self.check_closed()?;
         let key = subject_to_key(subject);
-        let now_ms = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis() as u64;
-        let min_ms = now_ms.saturating_sub(max_age.as_millis() as u64);
-        let min_id = format!("{min_ms}-0");
+        let min_id = format!("{before_ms}-0");
 
         let mut conn = self.conn.clone();
         let trimmed: u64 = redis::cmd("XTRIM")
             .arg(&key)
             .arg("MINID")
-            .arg("~")
             .arg(&min_id)
             .query_async(&mut conn)
             .await
@@ -166,6 +384,329 @@ impl gbe_nexus::Transport for RedisTransport {
         Ok(trimmed)
     }
 
+    async fn oldest_unacked(&self, subject: &str) -> Result<Option<u64>, TransportError> {
+        self.check_closed()?;
+        let key = subject_to_key(subject);
+        let mut conn = self.conn.clone();
+
+        let groups: StreamInfoGroupsReply = match redis::cmd("XINFO")
+            .arg("GROUPS")
+            .arg(&key)
+            .query_async(&mut conn)
+            .await
+        {
+            Ok(reply) => reply,
+            Err(e) if e.to_string().contains("no such key") => return Ok(None),
+            Err(e) => return Err(map_redis_err(e)),
+        };
+
+        let mut oldest: Option<String> = None;
+        let mut consider = |id: String| {
+            if oldest
+                .as_ref()
+                .is_none_or(|o| entry_millis(&id) < entry_millis(o))
+            {
+                oldest = Some(id);
+            }
+        };
+        for group in groups.groups {
+            if group.pending > 0 {
+                let pending: StreamPendingReply = redis::cmd("XPENDING")
+                    .arg(&key)
+                    .arg(&group.name)
+                    .query_async(&mut conn)
+                    .await
+                    .map_err(map_redis_err)?;
+                if let StreamPendingReply::Data(data) = pending {
+                    consider(data.start_id);
+                }
+            }
+            let next: StreamRangeReply = redis::cmd("XRANGE")
+                .arg(&key)
+                .arg(format!("({}", group.last_delivered_id))
+                .arg("+")
+                .arg("COUNT")
+                .arg(1)
+                .query_async(&mut conn)
+                .await
+                .map_err(map_redis_err)?;
+            if let Some(entry) = next.ids.into_iter().next() {
+                consider(entry.id);
+            }
+        }
+        Ok(oldest.map(|id| entry_millis(&id)))
+    }
+
+    async fn group_info(
+        &self,
+        subject: &str,
+        group: &str,
+    ) -> Result<Option<GroupInfo>, TransportError> {
+        self.check_closed()?;
+        let key = subject_to_key(subject);
+        let mut conn = self.conn.clone();
+
+        let groups: StreamInfoGroupsReply = match redis::cmd("XINFO")
+            .arg("GROUPS")
+            .arg(&key)
+            .query_async(&mut conn)
+            .await
+        {
+            Ok(reply) => reply,
+            Err(e) if e.to_string().contains("no such key") => return Ok(None),
+            Err(e) => return Err(map_redis_err(e)),
+        };
+        let Some(info) = groups.groups.into_iter().find(|g| g.name == group) else {
+            return Ok(None);
+        };
+
+        let length: u64 = redis::cmd("XLEN")
+            .arg(&key)
+            .query_async(&mut conn)
+            .await
+            .map_err(map_redis_err)?;
+        let consumers: StreamInfoConsumersReply = redis::cmd("XINFO")
+            .arg("CONSUMERS")
+            .arg(&key)
+            .arg(group)
+            .query_async(&mut conn)
+            .await
+            .map_err(map_redis_err)?;
+
+        let last_delivered_id = if info.last_delivered_id == "0-0" {
+            None
+        } else {
+            message_id_at(&mut conn, &key, &info.last_delivered_id).await?
+        };
+        // Redis reports no lag before 7.0, or after entries were deleted
+        // from the middle of the stream; count the rest then.
+        let lag = match info.lag {
+            Some(lag) => lag as u64,
+            None => count_after(&mut conn, &key, &info.last_delivered_id).await?,
+        };
+
+        Ok(Some(GroupInfo {
+            subject: subject.to_string(),
+            group: group.to_string(),
+            length,
+            last_delivered_id,
+            pending: info.pending as u64,
+            lag,
+            consumers: consumers
+                .consumers
+                .into_iter()
+                .map(|c| ConsumerInfo {
+                    name: c.name,
+                    pending: c.pending as u64,
+                    idle: Duration::from_millis(c.idle as u64),
+                })
+                .collect(),
+        }))
+    }
+
+    async fn list_groups(&self, subject: &str) -> Result<Vec<String>, TransportError> {
+        self.check_closed()?;
+        let key = subject_to_key(subject);
+        let mut conn = self.conn.clone();
+
+        let groups: StreamInfoGroupsReply = match redis::cmd("XINFO")
+            .arg("GROUPS")
+            .arg(&key)
+            .query_async(&mut conn)
+            .await
+        {
+            Ok(reply) => reply,
+            Err(e) if e.to_string().contains("no such key") => return Ok(Vec::new()),
+            Err(e) => return Err(map_redis_err(e)),
+        };
+        let mut names: Vec<String> = groups.groups.into_iter().map(|g| g.name).collect();
+        names.sort_unstable();
+        Ok(names)
+    }
+
+    async fn delete_group(&self, subject: &str, group: &str) -> Result<bool, TransportError> {
+        self.check_closed()?;
+        let key = subject_to_key(subject);
+        let mut conn = self.conn.clone();
+
+        let destroyed: i64 = match redis::cmd("XGROUP")
+            .arg("DESTROY")
+            .arg(&key)
+            .arg(group)
+            .query_async(&mut conn)
+            .await
+        {
+            Ok(destroyed) => destroyed,
+            Err(e) if e.to_string().contains("requires the key to exist") => return Ok(false),
+            Err(e) => return Err(map_redis_err(e)),
+        };
+        redis::cmd("DEL")
+            .arg(delayed_key(&key, group))
+            .query_async::<i64>(&mut conn)
+            .await
+            .map_err(map_redis_err)?;
+        Ok(destroyed > 0)
+    }
+
+    async fn set_group_position(
+        &self,
+        subject: &str,
+        group: &str,
+        position: StartPosition,
+    ) -> Result<(), TransportError> {
+        self.check_closed()?;
+        let key = subject_to_key(subject);
+        let mut conn = self.conn.clone();
+
+        let id = position_id(&mut conn, &key, &position).await?;
+        match redis::cmd("XGROUP")
+            .arg("SETID")
+            .arg(&key)
+            .arg(group)
+            .arg(&id)
+            .query_async::<String>(&mut conn)
+            .await
+        {
+            Ok(_) => Ok(()),
+            Err(e)
+                if e.to_string().contains("NOGROUP")
+                    || e.to_string().contains("requires the key to exist") =>
+            {
+                Err(TransportError::Stream(format!(
+                    "no group {group} on {subject}"
+                )))
+            }
+            Err(e) => Err(map_redis_err(e)),
+        }
+    }
+
+    async fn remove_idle_consumers(
+        &self,
+        subject: &str,
+        group: &str,
+        min_idle: Duration,
+    ) -> Result<Vec<String>, TransportError> {
+        self.check_closed()?;
+        let key = subject_to_key(subject);
+        let mut conn = self.conn.clone();
+
+        let consumers: StreamInfoConsumersReply = match redis::cmd("XINFO")
+            .arg("CONSUMERS")
+            .arg(&key)
+            .arg(group)
+            .query_async(&mut conn)
+            .await
+        {
+            Ok(reply) => reply,
+            Err(e)
+                if e.to_string().contains("NOGROUP") || e.to_string().contains("no such key") =>
+            {
+                return Ok(Vec::new());
+            }
+            Err(e) => return Err(map_redis_err(e)),
+        };
+
+        let script = redis::Script::new(DELETE_CONSUMER_SCRIPT);
+        let mut removed = Vec::new();
+        for consumer in consumers.consumers {
+            if consumer.pending > 0 || (consumer.idle as u128) < min_idle.as_millis() {
+                continue;
+            }
+            let deleted: i64 = script
+                .key(&key)
+                .arg(group)
+                .arg(&consumer.name)
+                .invoke_async(&mut conn)
+                .await
+                .map_err(map_redis_err)?;
+            if deleted > 0 {
+                removed.push(consumer.name);
+            }
+        }
+        Ok(removed)
+    }
+
+    async fn list_dead_letters(
+        &self,
+        domain: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<DeadLetter>, TransportError> {
+        self.check_closed()?;
+        let mut conn = self.conn.clone();
+        let entries = read_entries(&mut conn, &dead_letter_key(domain), limit).await?;
+        Ok(entries.into_iter().filter_map(|(_, dl)| dl).collect())
+    }
+
+    async fn replay_dead_letters(
+        &self,
+        domain: &str,
+        ids: Option<&[String]>,
+    ) -> Result<u64, TransportError> {
+        self.check_closed()?;
+        let key = dead_letter_key(domain);
+        let mut conn = self.conn.clone();
+        let entries = read_entries(&mut conn, &key, None).await?;
+
+        // One at a time, so a failed publish leaves the rest dead-lettered
+        let mut replayed = 0;
+        for (entry_id, dl) in entries {
+            let Some(dl) = dl.filter(|dl| is_selected(ids, &dl.id)) else {
+                continue;
+            };
+            let opts = PublishOpts {
+                trace_id: dl.original.trace_id.clone(),
+                ordering_key: dl.original.ordering_key.clone(),
+                ..Default::default()
+            };
+            self.publish(
+                dl.original_subject(),
+                dl.original.payload.clone(),
+                Some(opts),
+            )
+            .await?;
+            redis::cmd("XDEL")
+                .arg(&key)
+                .arg(&entry_id)
+                .query_async::<u64>(&mut conn)
+                .await
+                .map_err(map_redis_err)?;
+            replayed += 1;
+        }
+        Ok(replayed)
+    }
+
+    async fn purge_dead_letters(
+        &self,
+        domain: &str,
+        ids: Option<&[String]>,
+    ) -> Result<u64, TransportError> {
+        self.check_closed()?;
+        let key = dead_letter_key(domain);
+        let mut conn = self.conn.clone();
+        let entries = read_entries(&mut conn, &key, None).await?;
+
+        // Purging everything also drops malformed entries.
+        let selected: Vec<String> = entries
+            .into_iter()
+            .filter(|(_, dl)| match dl {
+                Some(dl) => is_selected(ids, &dl.id),
+                None => ids.is_none(),
+            })
+            .map(|(entry_id, _)| entry_id)
+            .collect();
+
+        let mut purged = 0;
+        for chunk in selected.chunks(500) {
+            purged += redis::cmd("XDEL")
+                .arg(&key)
+                .arg(chunk)
+                .query_async::<u64>(&mut conn)
+                .await
+                .map_err(map_redis_err)?;
+        }
+        Ok(purged)
+    }
+
     async fn ping(&self) -> Result<bool, TransportError> {
         let mut conn = self.conn.clone();
         let pong: String = redis::cmd("PING")
@@ -177,6 +718,65 @@ impl gbe_nexus::Transport for RedisTransport {
 
     async fn close(&self) -> Result<(), TransportError> {
         self.closed.store(true, Ordering::Release);
+        let consumers = std::mem::take(&mut *self.consumers.lock().unwrap());
+        // Cancel them all first so they drain side by side.
+        for consumer in &consumers {
+            consumer.cancel();
+        }
+        for consumer in &consumers {
+            consumer.finished().await;
+        }
         Ok(())
     }
 }
+
+fn is_selected(ids: Option<&[String]>, id: &str) -> bool {
+    ids.is_none_or(|ids| ids.iter().any(|selected| selected == id))
+}
+
+/// `Envelope::message_id` of the entry `entry_id`, if it is still there.
+async fn message_id_at(
+    conn: &mut redis::aio::ConnectionManager,
+    key: &str,
+    entry_id: &str,
+) -> Result<Option<String>, TransportError> {
+    let reply: StreamRangeReply = redis::cmd("XRANGE")
+        .arg(key)
+        .arg(entry_id)
+        .arg(entry_id)
+        .query_async(conn)
+        .await
+        .map_err(map_redis_err)?;
+    Ok(reply.ids.first().and_then(|entry| {
+        let json: String = entry.get("envelope")?;
+        serde_json::from_str::<Envelope>(&json)
+            .ok()
+            .map(|envelope| envelope.message_id)
+    }))
+}
+
+/// Number of entries after `entry_id`.
+async fn count_after(
+    conn: &mut redis::aio::ConnectionManager,
+    key: &str,
+    entry_id: &str,
+) -> Result<u64, TransportError> {
+    let mut count = 0;
+    let mut start = format!("({entry_id}");
+    loop {
+        let reply: StreamRangeReply = redis::cmd("XRANGE")
+            .arg(key)
+            .arg(&start)
+            .arg("+")
+            .arg("COUNT")
+            .arg(500)
+            .query_async(conn)
+            .await
+            .map_err(map_redis_err)?;
+        let Some(last) = reply.ids.last() else {
+            return Ok(count);
+        };
+        start = format!("({}", last.id);
+        count += reply.ids.len() as u64;
+    }
+}
@@ -0,0 +1,23 @@
+//! Shared transport conformance suite against the Redis Streams backend.
+//!
+//! Requires a running Redis instance. Set `REDIS_URL` to enable these tests.
+//!
+//! Run with: `REDIS_URL=redis://localhost:6379` cargo test --package gbe-nexus-redis
+
+use std::sync::Arc;
+
+use gbe_nexus::Transport;
+use gbe_nexus_redis::{RedisTransport, RedisTransportConfig};
+
+async fn transport() -> Option<Arc<dyn Transport>> {
+    let url = std::env::var("REDIS_URL").ok()?;
+    let transport = RedisTransport::connect(RedisTransportConfig {
+        url,
+        ..Default::default()
+    })
+    .await
+    .expect("failed to connect to Redis");
+    Some(Arc::new(transport))
+}
+
+gbe_nexus_testkit::transport_conformance_tests!(transport);
@@ -116,6 +116,52 @@ async fn test_ensure_stream_idempotent() {
     cleanup_stream(&subject).await;
 }
 
+#[tokio::test]
+async fn test_drop_legacy_init_groups() {
+    if redis_url().is_none() {
+        return;
+    }
+    let transport = connect().await;
+    let subject = test_subject("legacy-init");
+    let key = subject.replace('.', ":");
+
+    // As left behind by earlier versions of ensure_stream
+    let client = redis::Client::open(redis_url().unwrap().as_str()).unwrap();
+    let mut conn = client.get_multiplexed_async_connection().await.unwrap();
+    let _: () = redis::cmd("XGROUP")
+        .arg("CREATE")
+        .arg(&key)
+        .arg("_init")
+        .arg("$")
+        .arg("MKSTREAM")
+        .query_async(&mut conn)
+        .await
+        .unwrap();
+
+    // ensure_stream leaves it alone; the migration removes it once
+    transport
+        .ensure_stream(StreamConfig {
+            subject: subject.clone(),
+            max_age: Duration::ZERO,
+            max_bytes: None,
+            max_msgs: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(transport.list_groups(&subject).await.unwrap(), ["_init"]);
+    assert_eq!(
+        transport.drop_legacy_init_groups(&subject).await.unwrap(),
+        1
+    );
+    assert!(transport.list_groups(&subject).await.unwrap().is_empty());
+    assert_eq!(
+        transport.drop_legacy_init_groups(&subject).await.unwrap(),
+        0
+    );
+
+    cleanup_stream(&subject).await;
+}
+
 #[tokio::test]
 async fn test_publish_returns_message_id() {
     if redis_url().is_none() {
@@ -153,6 +199,7 @@ async fn test_payload_too_large() {
     let transport = RedisTransport::connect(RedisTransportConfig {
         url: redis_url().unwrap(),
         max_payload_size: 100,
+        ..Default::default()
     })
     .await
     .unwrap();
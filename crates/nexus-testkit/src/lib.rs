@@ -0,0 +1,119 @@
+//! Backend conformance suites for `Transport` and `StateStore`.
+//!
+//! Every backend runs the same behavioural contract so they cannot drift
+//! apart. A backend crate wires the suite up from its own test file:
+//!
+//! ```ignore
+//! async fn transport() -> Option<Arc<dyn Transport>> {
+//!     Some(Arc::new(MemoryTransport::new(MemoryTransportConfig::default())))
+//! }
+//!
+//! gbe_nexus_testkit::transport_conformance_tests!(transport);
+//! ```
+//!
+//! The factory returns `None` to skip the suite (e.g. when `REDIS_URL` is unset).
+//! Each case uses fresh, ULID-suffixed subjects and keys, so suites can run
+//! in parallel against a shared server.
+
+pub mod state_store;
+pub mod support;
+pub mod transport;
+
+/// Generate one `#[tokio::test]` per transport conformance case.
+///
+/// `$factory` is an `async fn() -> Option<Arc<dyn Transport>>`, called once per case.
+#[macro_export]
+macro_rules! transport_conformance_tests {
+    ($factory:ident) => {
+        $crate::transport_conformance_tests!(@cases $factory;
+            publish_returns_message_id,
+            ordering,
+            trace_id_propagation,
+            idempotent_publish,
+            publish_envelope,
+            fan_out_across_groups,
+            competing_consumers_in_group,
+            nak_redelivery,
+            nak_delay_redelivery,
+            handler_error_redelivery,
+            dead_letter,
+            dead_letter_replay_and_purge,
+            max_deliveries_dead_letters,
+            wildcard_subscription,
+            start_earliest,
+            start_latest,
+            start_timestamp,
+            start_id,
+            max_inflight,
+            concurrent_handlers,
+            concurrent_unsubscribe,
+            ordered_by_key,
+            trim,
+            trim_before,
+            trim_boundary,
+            oldest_unacked,
+            group_info,
+            group_admin,
+            stream_config_registry,
+            retention_limits,
+            unsubscribe_stops_delivery,
+            unsubscribe_drains_handlers,
+            drain_timeout_redelivers,
+            pull_fetch_and_settle,
+            pull_backpressure,
+            reserved_group_names,
+            close_drains_subscriptions,
+            close_rejects_operations,
+        );
+    };
+    (@cases $factory:ident; $($case:ident),* $(,)?) => {
+        mod transport_conformance {
+            $(
+                #[tokio::test]
+                async fn $case() {
+                    if let Some(transport) = super::$factory().await {
+                        $crate::transport::$case(transport).await;
+                    }
+                }
+            )*
+        }
+    };
+}
+
+/// Generate one `#[tokio::test]` per state store conformance case.
+///
+/// `$factory` is an `async fn() -> Option<Arc<dyn StateStore>>`, called once per case.
+#[macro_export]
+macro_rules! state_store_conformance_tests {
+    ($factory:ident) => {
+        $crate::state_store_conformance_tests!(@cases $factory;
+            ping,
+            put_get_roundtrip,
+            put_merges_fields,
+            get_missing_key,
+            delete,
+            field_ops,
+            set_fields_batch,
+            compare_and_swap,
+            compare_and_swap_missing,
+            compare_and_set_fields,
+            scan_prefix,
+            scan_filter,
+            scan_max_results,
+            ttl_expiry,
+            close_rejects_operations,
+        );
+    };
+    (@cases $factory:ident; $($case:ident),* $(,)?) => {
+        mod state_store_conformance {
+            $(
+                #[tokio::test]
+                async fn $case() {
+                    if let Some(store) = super::$factory().await {
+                        $crate::state_store::$case(store).await;
+                    }
+                }
+            )*
+        }
+    };
+}
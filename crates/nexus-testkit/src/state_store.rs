@@ -0,0 +1,414 @@
+//! `StateStore` conformance cases.
+//!
+//! Each case works under its own key prefix and deletes what it wrote.
+
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use gbe_state_store::{Record, ScanFilter, ScanOp, StateStore};
+
+use crate::support::unique_prefix;
+
+fn make_record(fields: &[(&str, &str)]) -> Record {
+    Record {
+        fields: fields
+            .iter()
+            .map(|(k, v)| ((*k).to_string(), Bytes::from((*v).to_string())))
+            .collect(),
+        ttl: None,
+    }
+}
+
+async fn cleanup(store: &Arc<dyn StateStore>, keys: &[&str]) {
+    for key in keys {
+        store.delete(key).await.unwrap();
+    }
+}
+
+/// A live store answers `ping`.
+pub async fn ping(store: Arc<dyn StateStore>) {
+    assert!(store.ping().await.unwrap());
+}
+
+/// `put` then `get` returns every field.
+pub async fn put_get_roundtrip(store: Arc<dyn StateStore>) {
+    let key = format!("{}rec", unique_prefix("roundtrip"));
+
+    store
+        .put(
+            &key,
+            make_record(&[("state", "pending"), ("task_type", "email-send")]),
+            None,
+        )
+        .await
+        .unwrap();
+
+    let got = store.get(&key).await.unwrap().expect("expected record");
+    assert_eq!(got.fields.len(), 2);
+    assert_eq!(got.fields["state"].as_ref(), b"pending");
+    assert_eq!(got.fields["task_type"].as_ref(), b"email-send");
+
+    cleanup(&store, &[&key]).await;
+}
+
+/// `put` merges into an existing record rather than replacing it.
+pub async fn put_merges_fields(store: Arc<dyn StateStore>) {
+    let key = format!("{}rec", unique_prefix("merge"));
+
+    store
+        .put(
+            &key,
+            make_record(&[("state", "pending"), ("step", "1")]),
+            None,
+        )
+        .await
+        .unwrap();
+    store
+        .put(&key, make_record(&[("state", "running")]), None)
+        .await
+        .unwrap();
+
+    let got = store.get(&key).await.unwrap().expect("expected record");
+    assert_eq!(got.fields.len(), 2);
+    assert_eq!(got.fields["state"].as_ref(), b"running");
+    assert_eq!(got.fields["step"].as_ref(), b"1");
+
+    cleanup(&store, &[&key]).await;
+}
+
+/// Missing keys and fields read as `None`.
+pub async fn get_missing_key(store: Arc<dyn StateStore>) {
+    let key = format!("{}missing", unique_prefix("missing"));
+    assert!(store.get(&key).await.unwrap().is_none());
+    assert!(store.get_field(&key, "state").await.unwrap().is_none());
+}
+
+/// `delete` removes the record; deleting again is not an error.
+pub async fn delete(store: Arc<dyn StateStore>) {
+    let key = format!("{}rec", unique_prefix("delete"));
+
+    store
+        .put(&key, make_record(&[("x", "1")]), None)
+        .await
+        .unwrap();
+    store.delete(&key).await.unwrap();
+    assert!(store.get(&key).await.unwrap().is_none());
+    store.delete(&key).await.unwrap();
+}
+
+/// `set_field` creates the record if needed; `get_field` reads single fields.
+pub async fn field_ops(store: Arc<dyn StateStore>) {
+    let key = format!("{}rec", unique_prefix("fieldops"));
+
+    store
+        .set_field(&key, "state", Bytes::from("pending"))
+        .await
+        .unwrap();
+
+    let val = store.get_field(&key, "state").await.unwrap();
+    assert_eq!(val.as_deref(), Some(b"pending".as_ref()));
+    assert!(
+        store
+            .get_field(&key, "nonexistent")
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    cleanup(&store, &[&key]).await;
+}
+
+/// `set_fields` writes all fields at once.
+pub async fn set_fields_batch(store: Arc<dyn StateStore>) {
+    let key = format!("{}rec", unique_prefix("batch"));
+
+    let fields = HashMap::from([
+        ("state".to_string(), Bytes::from("running")),
+        ("step".to_string(), Bytes::from("3")),
+        ("worker".to_string(), Bytes::from("host-1")),
+    ]);
+    store.set_fields(&key, fields).await.unwrap();
+
+    let got = store.get(&key).await.unwrap().expect("expected record");
+    assert_eq!(got.fields.len(), 3);
+    assert_eq!(got.fields["state"].as_ref(), b"running");
+    assert_eq!(got.fields["step"].as_ref(), b"3");
+    assert_eq!(got.fields["worker"].as_ref(), b"host-1");
+
+    cleanup(&store, &[&key]).await;
+}
+
+/// CAS swaps only when the current value matches.
+pub async fn compare_and_swap(store: Arc<dyn StateStore>) {
+    let key = format!("{}rec", unique_prefix("cas"));
+
+    store
+        .set_field(&key, "state", Bytes::from("pending"))
+        .await
+        .unwrap();
+
+    let swapped = store
+        .compare_and_swap(
+            &key,
+            "state",
+            Bytes::from("pending"),
+            Bytes::from("claimed"),
+        )
+        .await
+        .unwrap();
+    assert!(swapped);
+
+    let swapped_again = store
+        .compare_and_swap(
+            &key,
+            "state",
+            Bytes::from("pending"),
+            Bytes::from("claimed"),
+        )
+        .await
+        .unwrap();
+    assert!(!swapped_again, "stale expectation must not swap");
+
+    let val = store.get_field(&key, "state").await.unwrap();
+    assert_eq!(val.as_deref(), Some(b"claimed".as_ref()));
+
+    cleanup(&store, &[&key]).await;
+}
+
+/// CAS against a missing key or field fails without creating anything.
+pub async fn compare_and_swap_missing(store: Arc<dyn StateStore>) {
+    let key = format!("{}rec", unique_prefix("cas-missing"));
+
+    let swapped = store
+        .compare_and_swap(
+            &key,
+            "state",
+            Bytes::from("pending"),
+            Bytes::from("claimed"),
+        )
+        .await
+        .unwrap();
+    assert!(!swapped);
+    assert!(store.get(&key).await.unwrap().is_none());
+
+    store
+        .set_field(&key, "other", Bytes::from("x"))
+        .await
+        .unwrap();
+    let swapped = store
+        .compare_and_swap(
+            &key,
+            "state",
+            Bytes::from("pending"),
+            Bytes::from("claimed"),
+        )
+        .await
+        .unwrap();
+    assert!(!swapped);
+    assert!(store.get_field(&key, "state").await.unwrap().is_none());
+
+    cleanup(&store, &[&key]).await;
+}
+
+/// `compare_and_set_fields` writes and removes fields only when every
+/// expected field matches, and changes nothing otherwise.
+pub async fn compare_and_set_fields(store: Arc<dyn StateStore>) {
+    let key = format!("{}rec", unique_prefix("cas-fields"));
+    let fields = |pairs: &[(&str, &str)]| make_record(pairs).fields;
+
+    store
+        .put(
+            &key,
+            make_record(&[("state", "running"), ("retry", "1"), ("deadline", "100")]),
+            None,
+        )
+        .await
+        .unwrap();
+
+    let swapped = store
+        .compare_and_set_fields(
+            &key,
+            fields(&[("state", "running"), ("retry", "0")]),
+            fields(&[("state", "completed"), ("outcome", "ok")]),
+            &["deadline"],
+        )
+        .await
+        .unwrap();
+    assert!(!swapped, "one stale expectation must not write");
+    let got = store.get(&key).await.unwrap().unwrap();
+    assert_eq!(got.fields["state"].as_ref(), b"running");
+    assert!(!got.fields.contains_key("outcome"));
+    assert!(got.fields.contains_key("deadline"));
+
+    let swapped = store
+        .compare_and_set_fields(
+            &key,
+            fields(&[("state", "running"), ("retry", "1")]),
+            fields(&[("state", "completed"), ("outcome", "ok")]),
+            &["deadline"],
+        )
+        .await
+        .unwrap();
+    assert!(swapped);
+    let got = store.get(&key).await.unwrap().unwrap();
+    assert_eq!(got.fields["state"].as_ref(), b"completed");
+    assert_eq!(got.fields["outcome"].as_ref(), b"ok");
+    assert_eq!(got.fields["retry"].as_ref(), b"1");
+    assert!(!got.fields.contains_key("deadline"));
+
+    let missing = format!("{}rec", unique_prefix("cas-fields-missing"));
+    let swapped = store
+        .compare_and_set_fields(
+            &missing,
+            fields(&[("state", "running")]),
+            fields(&[("state", "completed")]),
+            &[],
+        )
+        .await
+        .unwrap();
+    assert!(!swapped);
+    assert!(store.get(&missing).await.unwrap().is_none());
+
+    cleanup(&store, &[&key]).await;
+}
+
+/// `scan` returns only keys under the prefix.
+pub async fn scan_prefix(store: Arc<dyn StateStore>) {
+    let prefix = unique_prefix("scan");
+    let key1 = format!("{prefix}job1");
+    let key2 = format!("{prefix}job2");
+    let decoy = format!("{}job1", unique_prefix("scan-decoy"));
+
+    for (key, state) in [(&key1, "pending"), (&key2, "running"), (&decoy, "pending")] {
+        store
+            .put(key, make_record(&[("state", state)]), None)
+            .await
+            .unwrap();
+    }
+
+    let results = store.scan(&prefix, None).await.unwrap();
+    let mut found: Vec<&str> = results.iter().map(|(k, _)| k.as_str()).collect();
+    found.sort_unstable();
+    assert_eq!(found, [key1.as_str(), key2.as_str()]);
+
+    cleanup(&store, &[&key1, &key2, &decoy]).await;
+}
+
+/// `scan` applies the field filter; records missing the field are excluded.
+pub async fn scan_filter(store: Arc<dyn StateStore>) {
+    let prefix = unique_prefix("filter");
+    let key1 = format!("{prefix}job1");
+    let key2 = format!("{prefix}job2");
+    let key3 = format!("{prefix}job3");
+
+    store
+        .put(&key1, make_record(&[("timeout_at", "100")]), None)
+        .await
+        .unwrap();
+    store
+        .put(&key2, make_record(&[("timeout_at", "300")]), None)
+        .await
+        .unwrap();
+    store
+        .put(&key3, make_record(&[("state", "blocked")]), None)
+        .await
+        .unwrap();
+
+    let filter = |op, value: &'static str| ScanFilter {
+        field: "timeout_at".to_string(),
+        op,
+        value: Bytes::from(value),
+        max_results: None,
+    };
+
+    let eq = store
+        .scan(&prefix, Some(filter(ScanOp::Eq, "100")))
+        .await
+        .unwrap();
+    assert_eq!(eq.len(), 1);
+    assert_eq!(eq[0].0, key1);
+
+    let lt = store
+        .scan(&prefix, Some(filter(ScanOp::Lt, "200")))
+        .await
+        .unwrap();
+    assert_eq!(lt.len(), 1);
+    assert_eq!(lt[0].0, key1);
+
+    let gt = store
+        .scan(&prefix, Some(filter(ScanOp::Gt, "200")))
+        .await
+        .unwrap();
+    assert_eq!(gt.len(), 1);
+    assert_eq!(gt[0].0, key2);
+
+    cleanup(&store, &[&key1, &key2, &key3]).await;
+}
+
+/// `ScanFilter::max_results` caps the result count.
+pub async fn scan_max_results(store: Arc<dyn StateStore>) {
+    let prefix = unique_prefix("max");
+    let keys: Vec<String> = (0..5).map(|i| format!("{prefix}job{i}")).collect();
+
+    for key in &keys {
+        store
+            .put(key, make_record(&[("state", "pending")]), None)
+            .await
+            .unwrap();
+    }
+
+    let results = store
+        .scan(
+            &prefix,
+            Some(ScanFilter {
+                field: "state".to_string(),
+                op: ScanOp::Eq,
+                value: Bytes::from("pending"),
+                max_results: Some(3),
+            }),
+        )
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 3);
+
+    let refs: Vec<&str> = keys.iter().map(String::as_str).collect();
+    cleanup(&store, &refs).await;
+}
+
+/// A record put with a TTL disappears from `get` and `scan` after it expires.
+/// TTLs keep millisecond precision, so a sub-second TTL is not rounded away.
+pub async fn ttl_expiry(store: Arc<dyn StateStore>) {
+    let prefix = unique_prefix("ttl");
+    let key = format!("{prefix}rec");
+
+    store
+        .put(
+            &key,
+            make_record(&[("state", "temp")]),
+            Some(Duration::from_millis(500)),
+        )
+        .await
+        .unwrap();
+    assert!(store.get(&key).await.unwrap().is_some());
+
+    tokio::time::sleep(Duration::from_millis(800)).await;
+
+    assert!(store.get(&key).await.unwrap().is_none());
+    assert!(store.scan(&prefix, None).await.unwrap().is_empty());
+}
+
+/// A closed store rejects reads and writes.
+pub async fn close_rejects_operations(store: Arc<dyn StateStore>) {
+    let key = format!("{}rec", unique_prefix("closed"));
+    store.close().await.unwrap();
+
+    assert!(store.get(&key).await.is_err());
+    assert!(
+        store
+            .set_field(&key, "state", Bytes::from("x"))
+            .await
+            .is_err()
+    );
+}
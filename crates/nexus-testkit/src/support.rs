@@ -0,0 +1,228 @@
+//! Shared handlers and helpers for conformance cases.
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use gbe_nexus::{Envelope, Message, MessageHandler, TransportError};
+
+/// How long to wait for an expected delivery. Generous enough for
+/// claim-based redelivery on Redis (`ack_timeout` + one blocking read).
+pub const DELIVERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long to wait before concluding a message was NOT delivered.
+pub const QUIET_PERIOD: Duration = Duration::from_millis(500);
+
+/// Pause after subscribe so backends that create groups asynchronously are ready.
+pub const SETTLE: Duration = Duration::from_millis(200);
+
+/// Unique subject under a unique domain: `gbe.testkit{ulid}.{name}`.
+///
+/// A per-case domain keeps dead-letter streams isolated too.
+#[must_use]
+pub fn unique_subject(name: &str) -> String {
+    format!(
+        "gbe.testkit{}.{name}",
+        ulid::Ulid::new().to_string().to_lowercase()
+    )
+}
+
+/// Unique state store key prefix: `gbe:testkit:{ulid}:{name}:`.
+#[must_use]
+pub fn unique_prefix(name: &str) -> String {
+    format!(
+        "gbe:testkit:{}:{name}:",
+        ulid::Ulid::new().to_string().to_lowercase()
+    )
+}
+
+/// Domain (second token) of `subject`, as taken by the dead-letter API.
+#[must_use]
+pub fn domain_of(subject: &str) -> &str {
+    subject.split('.').nth(1).unwrap_or("unknown")
+}
+
+/// What a `RecordingHandler` does with each delivery.
+#[derive(Debug, Clone, Copy)]
+pub enum Behavior {
+    /// Ack every message.
+    Ack,
+    /// Nak the first delivery of the subscription, ack the rest.
+    NakFirst,
+    /// Nak the first delivery with the given redelivery delay, ack the rest.
+    NakFirstDelayed(Duration),
+    /// Return an error for the first delivery, ack the rest.
+    ErrorFirst,
+    /// Return an error for every delivery.
+    AlwaysError,
+    /// Dead-letter every message.
+    DeadLetter,
+    /// Never settle — messages stay pending.
+    Hold,
+}
+
+/// Handler that forwards every delivered envelope to a channel, then
+/// settles it according to its `Behavior`.
+pub struct RecordingHandler {
+    tx: mpsc::UnboundedSender<Envelope>,
+    behavior: Behavior,
+    calls: AtomicU32,
+}
+
+impl RecordingHandler {
+    #[must_use]
+    pub fn new(behavior: Behavior) -> (Box<Self>, mpsc::UnboundedReceiver<Envelope>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let handler = Box::new(Self {
+            tx,
+            behavior,
+            calls: AtomicU32::new(0),
+        });
+        (handler, rx)
+    }
+}
+
+#[async_trait]
+impl MessageHandler for RecordingHandler {
+    async fn handle(&self, msg: &dyn Message) -> Result<(), TransportError> {
+        let call = self.calls.fetch_add(1, Ordering::AcqRel);
+        let _ = self.tx.send(msg.envelope().clone());
+        match self.behavior {
+            Behavior::Ack => msg.ack().await,
+            Behavior::NakFirst if call == 0 => msg.nak(None).await,
+            Behavior::NakFirstDelayed(delay) if call == 0 => msg.nak(Some(delay)).await,
+            Behavior::ErrorFirst if call == 0 => {
+                Err(TransportError::Other("testkit: forced failure".to_string()))
+            }
+            Behavior::AlwaysError => {
+                Err(TransportError::Other("testkit: forced failure".to_string()))
+            }
+            Behavior::NakFirst | Behavior::NakFirstDelayed(_) | Behavior::ErrorFirst => {
+                msg.ack().await
+            }
+            Behavior::DeadLetter => msg.dead_letter("testkit: forced dead letter").await,
+            Behavior::Hold => Ok(()),
+        }
+    }
+}
+
+/// Most handler invocations a `SlowHandler` saw at once.
+#[derive(Debug, Default)]
+pub struct Peaks {
+    /// Across all messages.
+    pub all: AtomicU32,
+    /// Among messages sharing an ordering key.
+    pub per_key: AtomicU32,
+}
+
+/// Handler that forwards each envelope on arrival, holds the message for a
+/// while, then acks it, recording how many invocations overlap.
+pub struct SlowHandler {
+    tx: mpsc::UnboundedSender<Envelope>,
+    hold: Duration,
+    running: AtomicU32,
+    running_per_key: Mutex<HashMap<String, u32>>,
+    peaks: Arc<Peaks>,
+}
+
+impl SlowHandler {
+    /// Returns the handler, its delivery channel and its peaks.
+    #[must_use]
+    pub fn new(hold: Duration) -> (Box<Self>, mpsc::UnboundedReceiver<Envelope>, Arc<Peaks>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let peaks = Arc::new(Peaks::default());
+        let handler = Box::new(Self {
+            tx,
+            hold,
+            running: AtomicU32::new(0),
+            running_per_key: Mutex::new(HashMap::new()),
+            peaks: peaks.clone(),
+        });
+        (handler, rx, peaks)
+    }
+
+    fn enter_key(&self, key: Option<&String>, delta: i32) {
+        let Some(key) = key else {
+            return;
+        };
+        let mut running = self.running_per_key.lock().unwrap();
+        let count = running.entry(key.clone()).or_default();
+        *count = count.saturating_add_signed(delta);
+        self.peaks.per_key.fetch_max(*count, Ordering::AcqRel);
+    }
+}
+
+#[async_trait]
+impl MessageHandler for SlowHandler {
+    async fn handle(&self, msg: &dyn Message) -> Result<(), TransportError> {
+        let key = msg.envelope().ordering_key.as_ref();
+        let running = self.running.fetch_add(1, Ordering::AcqRel) + 1;
+        self.peaks.all.fetch_max(running, Ordering::AcqRel);
+        self.enter_key(key, 1);
+        let _ = self.tx.send(msg.envelope().clone());
+        tokio::time::sleep(self.hold).await;
+        let acked = msg.ack().await;
+        self.enter_key(key, -1);
+        self.running.fetch_sub(1, Ordering::AcqRel);
+        acked
+    }
+}
+
+/// Receive the next envelope, panicking after `DELIVERY_TIMEOUT`.
+///
+/// # Panics
+/// Panics if nothing arrives in time.
+pub async fn expect_delivery(rx: &mut mpsc::UnboundedReceiver<Envelope>) -> Envelope {
+    tokio::time::timeout(DELIVERY_TIMEOUT, rx.recv())
+        .await
+        .expect("timed out waiting for delivery")
+        .expect("handler channel closed")
+}
+
+/// Receive exactly `n` envelopes in delivery order.
+///
+/// # Panics
+/// Panics if fewer than `n` arrive in time.
+pub async fn expect_deliveries(
+    rx: &mut mpsc::UnboundedReceiver<Envelope>,
+    n: usize,
+) -> Vec<Envelope> {
+    let mut out = Vec::with_capacity(n);
+    for _ in 0..n {
+        out.push(expect_delivery(rx).await);
+    }
+    out
+}
+
+/// Assert nothing further is delivered for `QUIET_PERIOD`.
+///
+/// # Panics
+/// Panics if an envelope arrives.
+pub async fn expect_quiet(rx: &mut mpsc::UnboundedReceiver<Envelope>) {
+    if let Ok(Some(env)) = tokio::time::timeout(QUIET_PERIOD, rx.recv()).await {
+        panic!("unexpected delivery of {}", env.message_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unique_subject_has_own_domain() {
+        let a = unique_subject("x");
+        let b = unique_subject("x");
+        assert_ne!(a, b);
+        assert!(a.starts_with("gbe.testkit"));
+        assert!(a.ends_with(".x"));
+        assert_ne!(domain_of(&a), domain_of(&b));
+    }
+
+    #[test]
+    fn domain_of_second_token() {
+        assert_eq!(domain_of("gbe.tasks.email-send.queue"), "tasks");
+    }
+}
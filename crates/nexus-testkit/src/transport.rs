@@ -0,0 +1,1463 @@
+//! `Transport` conformance cases.
+//!
+//! Each case takes a fresh transport, panics on contract violations, and
+//! leaves the transport open unless it is testing `close`.
+
+use bytes::Bytes;
+use futures_core::Stream;
+use std::pin::Pin;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use gbe_nexus::{
+    Envelope, PublishOpts, StartPosition, StreamConfig, SubscribeOpts, Transport, TransportError,
+    dead_letter_subject, max_deliveries_reason,
+};
+
+use crate::support::{
+    Behavior, DELIVERY_TIMEOUT, QUIET_PERIOD, RecordingHandler, SETTLE, SlowHandler, domain_of,
+    expect_deliveries, expect_delivery, expect_quiet, unique_subject,
+};
+
+/// Short ack timeout so claim-based backends redeliver quickly.
+const ACK_TIMEOUT: Duration = Duration::from_millis(500);
+
+fn opts(start_from: StartPosition) -> Option<SubscribeOpts> {
+    Some(SubscribeOpts {
+        start_from,
+        ack_timeout: ACK_TIMEOUT,
+        ..Default::default()
+    })
+}
+
+async fn publish_all(
+    transport: &Arc<dyn Transport>,
+    subject: &str,
+    payloads: &[&str],
+) -> Vec<String> {
+    let mut ids = Vec::with_capacity(payloads.len());
+    for p in payloads {
+        ids.push(
+            transport
+                .publish(subject, Bytes::from(p.to_string()), None)
+                .await
+                .expect("publish failed"),
+        );
+    }
+    ids
+}
+
+fn payloads_of(envs: &[gbe_nexus::Envelope]) -> Vec<String> {
+    envs.iter()
+        .map(|e| String::from_utf8_lossy(&e.payload).to_string())
+        .collect()
+}
+
+/// `publish` returns the envelope's ULID message ID.
+pub async fn publish_returns_message_id(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("pubid");
+    let id = transport
+        .publish(&subject, Bytes::from("hello"), None)
+        .await
+        .unwrap();
+    assert!(ulid::Ulid::from_string(&id).is_ok(), "not a ULID: {id}");
+}
+
+/// Messages on one subject arrive in publish order, with matching IDs.
+pub async fn ordering(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("ordering");
+    let (handler, mut rx) = RecordingHandler::new(Behavior::Ack);
+    let sub = transport
+        .subscribe(&subject, "g", handler, opts(StartPosition::Earliest))
+        .await
+        .unwrap();
+    tokio::time::sleep(SETTLE).await;
+
+    let ids = publish_all(&transport, &subject, &["m0", "m1", "m2", "m3", "m4"]).await;
+    let got = expect_deliveries(&mut rx, 5).await;
+
+    assert_eq!(payloads_of(&got), ["m0", "m1", "m2", "m3", "m4"]);
+    let got_ids: Vec<String> = got.iter().map(|e| e.message_id.clone()).collect();
+    assert_eq!(got_ids, ids);
+    assert!(got.iter().all(|e| e.subject == subject));
+    expect_quiet(&mut rx).await;
+
+    assert!(sub.is_active());
+    sub.unsubscribe().await.unwrap();
+}
+
+/// `PublishOpts::trace_id` is carried on the delivered envelope.
+pub async fn trace_id_propagation(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("trace");
+    let (handler, mut rx) = RecordingHandler::new(Behavior::Ack);
+    let sub = transport
+        .subscribe(&subject, "g", handler, opts(StartPosition::Earliest))
+        .await
+        .unwrap();
+    tokio::time::sleep(SETTLE).await;
+
+    transport
+        .publish(
+            &subject,
+            Bytes::from("traced"),
+            Some(PublishOpts {
+                trace_id: Some("trace-abc".to_string()),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+    transport
+        .publish(&subject, Bytes::from("untraced"), None)
+        .await
+        .unwrap();
+
+    let got = expect_deliveries(&mut rx, 2).await;
+    assert_eq!(got[0].trace_id.as_deref(), Some("trace-abc"));
+    assert_eq!(got[1].trace_id, None);
+
+    sub.unsubscribe().await.unwrap();
+}
+
+/// `publish_envelope` keeps the envelope's identity: `message_id`,
+/// `timestamp` and `trace_id` arrive unchanged.
+pub async fn publish_envelope(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("envelope");
+    let mut envelope = Envelope::new(
+        subject.clone(),
+        Bytes::from("restored"),
+        Some("trace-old".to_string()),
+    );
+    envelope.timestamp -= 3_600_000;
+
+    let id = transport.publish_envelope(envelope.clone()).await.unwrap();
+    assert_eq!(id, envelope.message_id);
+
+    let (handler, mut rx) = RecordingHandler::new(Behavior::Ack);
+    let sub = transport
+        .subscribe(&subject, "g", handler, opts(StartPosition::Earliest))
+        .await
+        .unwrap();
+    let got = expect_delivery(&mut rx).await;
+    assert_eq!(got.message_id, envelope.message_id);
+    assert_eq!(got.timestamp, envelope.timestamp);
+    assert_eq!(got.trace_id.as_deref(), Some("trace-old"));
+    assert_eq!(got.payload, envelope.payload);
+    sub.unsubscribe().await.unwrap();
+
+    let wildcard = Envelope::new(format!("{subject}.*"), Bytes::from("x"), None);
+    assert!(transport.publish_envelope(wildcard).await.is_err());
+}
+
+/// A repeated `idempotency_key` on the same subject returns the original
+/// message ID and appends nothing.
+pub async fn idempotent_publish(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("dedup");
+    let other_subject = unique_subject("dedup-other");
+    let keyed = |key: &str| {
+        Some(PublishOpts {
+            idempotency_key: Some(key.to_string()),
+            ..Default::default()
+        })
+    };
+
+    let first = transport
+        .publish(&subject, Bytes::from("once"), keyed("order-42"))
+        .await
+        .unwrap();
+    let retry = transport
+        .publish(&subject, Bytes::from("once"), keyed("order-42"))
+        .await
+        .unwrap();
+    assert_eq!(first, retry, "retry must return the original message ID");
+
+    let other_key = transport
+        .publish(&subject, Bytes::from("twice"), keyed("order-43"))
+        .await
+        .unwrap();
+    assert_ne!(first, other_key);
+    let other_subject_id = transport
+        .publish(&other_subject, Bytes::from("once"), keyed("order-42"))
+        .await
+        .unwrap();
+    assert_ne!(first, other_subject_id, "keys are scoped per subject");
+
+    let (handler, mut rx) = RecordingHandler::new(Behavior::Ack);
+    let sub = transport
+        .subscribe(&subject, "g", handler, opts(StartPosition::Earliest))
+        .await
+        .unwrap();
+    let got = expect_deliveries(&mut rx, 2).await;
+    assert_eq!(got[0].message_id, first);
+    assert_eq!(got[1].message_id, other_key);
+    expect_quiet(&mut rx).await;
+
+    sub.unsubscribe().await.unwrap();
+}
+
+/// Every group sees every message.
+pub async fn fan_out_across_groups(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("fanout");
+    let (handler_a, mut rx_a) = RecordingHandler::new(Behavior::Ack);
+    let (handler_b, mut rx_b) = RecordingHandler::new(Behavior::Ack);
+    let sub_a = transport
+        .subscribe(
+            &subject,
+            "group-a",
+            handler_a,
+            opts(StartPosition::Earliest),
+        )
+        .await
+        .unwrap();
+    let sub_b = transport
+        .subscribe(
+            &subject,
+            "group-b",
+            handler_b,
+            opts(StartPosition::Earliest),
+        )
+        .await
+        .unwrap();
+    tokio::time::sleep(SETTLE).await;
+
+    publish_all(&transport, &subject, &["x", "y"]).await;
+
+    assert_eq!(
+        payloads_of(&expect_deliveries(&mut rx_a, 2).await),
+        ["x", "y"]
+    );
+    assert_eq!(
+        payloads_of(&expect_deliveries(&mut rx_b, 2).await),
+        ["x", "y"]
+    );
+
+    sub_a.unsubscribe().await.unwrap();
+    sub_b.unsubscribe().await.unwrap();
+}
+
+/// Subscribers sharing a group split the stream: each message is handled once.
+pub async fn competing_consumers_in_group(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("compete");
+    let (handler_a, mut rx_a) = RecordingHandler::new(Behavior::Ack);
+    let (handler_b, mut rx_b) = RecordingHandler::new(Behavior::Ack);
+    let sub_a = transport
+        .subscribe(&subject, "shared", handler_a, opts(StartPosition::Earliest))
+        .await
+        .unwrap();
+    let sub_b = transport
+        .subscribe(&subject, "shared", handler_b, opts(StartPosition::Earliest))
+        .await
+        .unwrap();
+    tokio::time::sleep(SETTLE).await;
+
+    let ids = publish_all(&transport, &subject, &["0", "1", "2", "3", "4", "5"]).await;
+
+    let mut seen = Vec::new();
+    let deadline = tokio::time::Instant::now() + crate::support::DELIVERY_TIMEOUT;
+    while seen.len() < ids.len() && tokio::time::Instant::now() < deadline {
+        tokio::select! {
+            Some(env) = rx_a.recv() => seen.push(env.message_id),
+            Some(env) = rx_b.recv() => seen.push(env.message_id),
+            () = tokio::time::sleep_until(deadline) => {}
+        }
+    }
+    expect_quiet(&mut rx_a).await;
+    expect_quiet(&mut rx_b).await;
+
+    seen.sort();
+    let mut expected = ids;
+    expected.sort();
+    assert_eq!(seen, expected, "each message must be handled exactly once");
+
+    sub_a.unsubscribe().await.unwrap();
+    sub_b.unsubscribe().await.unwrap();
+}
+
+/// A nak'd message is delivered again without waiting out `ack_timeout`.
+pub async fn nak_redelivery(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("nak");
+    let (handler, mut rx) = RecordingHandler::new(Behavior::NakFirst);
+    let sub = transport
+        .subscribe(
+            &subject,
+            "g",
+            handler,
+            Some(SubscribeOpts {
+                start_from: StartPosition::Earliest,
+                ack_timeout: DELIVERY_TIMEOUT * 2,
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+    tokio::time::sleep(SETTLE).await;
+
+    let ids = publish_all(&transport, &subject, &["retry-me"]).await;
+
+    let first = expect_delivery(&mut rx).await;
+    let second = expect_delivery(&mut rx).await;
+    assert_eq!(first.message_id, ids[0]);
+    assert_eq!(second.message_id, ids[0]);
+    expect_quiet(&mut rx).await;
+
+    sub.unsubscribe().await.unwrap();
+}
+
+/// `nak(Some(delay))` holds the message back for the delay, even past
+/// `ack_timeout`, then delivers it again.
+pub async fn nak_delay_redelivery(transport: Arc<dyn Transport>) {
+    let delay = ACK_TIMEOUT * 3;
+    let subject = unique_subject("nak-delay");
+    let (handler, mut rx) = RecordingHandler::new(Behavior::NakFirstDelayed(delay));
+    let sub = transport
+        .subscribe(&subject, "g", handler, opts(StartPosition::Earliest))
+        .await
+        .unwrap();
+    tokio::time::sleep(SETTLE).await;
+
+    let ids = publish_all(&transport, &subject, &["later"]).await;
+
+    let first = expect_delivery(&mut rx).await;
+    let naked_at = tokio::time::Instant::now();
+    let second = expect_delivery(&mut rx).await;
+    let waited = naked_at.elapsed();
+    assert_eq!(first.message_id, ids[0]);
+    assert_eq!(second.message_id, ids[0]);
+    assert!(
+        waited >= delay - Duration::from_millis(50),
+        "redelivered after {waited:?}, before the {delay:?} delay"
+    );
+    expect_quiet(&mut rx).await;
+
+    sub.unsubscribe().await.unwrap();
+}
+
+/// A handler error leaves the message unacked, so it is delivered again.
+pub async fn handler_error_redelivery(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("handler-err");
+    let (handler, mut rx) = RecordingHandler::new(Behavior::ErrorFirst);
+    let sub = transport
+        .subscribe(&subject, "g", handler, opts(StartPosition::Earliest))
+        .await
+        .unwrap();
+    tokio::time::sleep(SETTLE).await;
+
+    let ids = publish_all(&transport, &subject, &["flaky"]).await;
+
+    assert_eq!(expect_delivery(&mut rx).await.message_id, ids[0]);
+    assert_eq!(expect_delivery(&mut rx).await.message_id, ids[0]);
+    expect_quiet(&mut rx).await;
+
+    sub.unsubscribe().await.unwrap();
+}
+
+/// A dead-lettered message lands on `gbe._deadletter.{domain}` and is not redelivered.
+pub async fn dead_letter(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("deadletter");
+    let (handler, mut rx) = RecordingHandler::new(Behavior::DeadLetter);
+    let sub = transport
+        .subscribe(&subject, "g", handler, opts(StartPosition::Earliest))
+        .await
+        .unwrap();
+    tokio::time::sleep(SETTLE).await;
+
+    let ids = publish_all(&transport, &subject, &["doomed"]).await;
+    expect_delivery(&mut rx).await;
+    // Longer than ack_timeout: a claim-based backend would have redelivered by now.
+    tokio::time::sleep(ACK_TIMEOUT * 2).await;
+    expect_quiet(&mut rx).await;
+
+    // The dead-letter stream is an ordinary subject...
+    let (dl_handler, mut dl_rx) = RecordingHandler::new(Behavior::Ack);
+    let dl_sub = transport
+        .subscribe(
+            &dead_letter_subject(&subject),
+            "dl-check",
+            dl_handler,
+            opts(StartPosition::Earliest),
+        )
+        .await
+        .unwrap();
+    let stored = expect_delivery(&mut dl_rx).await;
+    expect_quiet(&mut dl_rx).await;
+
+    // ...in the shared format the dead-letter API reads.
+    let listed = transport
+        .list_dead_letters(domain_of(&subject), None)
+        .await
+        .unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].id, stored.message_id);
+    assert_eq!(listed[0].dead_lettered_at, stored.timestamp);
+    assert_eq!(listed[0].reason, "testkit: forced dead letter");
+    assert_eq!(listed[0].original_subject(), subject);
+    assert_eq!(listed[0].original.message_id, ids[0]);
+    assert_eq!(listed[0].original.payload, Bytes::from("doomed"));
+
+    sub.unsubscribe().await.unwrap();
+    dl_sub.unsubscribe().await.unwrap();
+}
+
+/// Dead letters can be listed, replayed to their original subject, and purged.
+pub async fn dead_letter_replay_and_purge(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("dl-replay");
+    let domain = domain_of(&subject);
+    let (handler, mut rx) = RecordingHandler::new(Behavior::DeadLetter);
+    let sub = transport
+        .subscribe(&subject, "g", handler, opts(StartPosition::Earliest))
+        .await
+        .unwrap();
+    tokio::time::sleep(SETTLE).await;
+
+    publish_all(&transport, &subject, &["a", "b", "c"]).await;
+    expect_deliveries(&mut rx, 3).await;
+    sub.unsubscribe().await.unwrap();
+    tokio::time::sleep(SETTLE).await;
+
+    let listed = transport.list_dead_letters(domain, None).await.unwrap();
+    let payloads: Vec<_> = listed
+        .iter()
+        .map(|dl| dl.original.payload.clone())
+        .collect();
+    assert_eq!(payloads, ["a", "b", "c"]);
+    let limited = transport.list_dead_letters(domain, Some(2)).await.unwrap();
+    assert_eq!(limited.len(), 2);
+
+    let (handler, mut rx) = RecordingHandler::new(Behavior::Ack);
+    let sub = transport
+        .subscribe(&subject, "replayed", handler, opts(StartPosition::Latest))
+        .await
+        .unwrap();
+    tokio::time::sleep(SETTLE).await;
+
+    // Purge "a", replay "b"
+    let purged = transport
+        .purge_dead_letters(domain, Some(&[listed[0].id.clone()]))
+        .await
+        .unwrap();
+    assert_eq!(purged, 1);
+    let replayed = transport
+        .replay_dead_letters(domain, Some(&[listed[1].id.clone()]))
+        .await
+        .unwrap();
+    assert_eq!(replayed, 1);
+    assert_eq!(expect_delivery(&mut rx).await.payload, Bytes::from("b"));
+
+    let remaining = transport.list_dead_letters(domain, None).await.unwrap();
+    assert_eq!(remaining.len(), 1);
+    assert_eq!(remaining[0].id, listed[2].id);
+
+    // Replay the rest
+    assert_eq!(
+        transport.replay_dead_letters(domain, None).await.unwrap(),
+        1
+    );
+    assert_eq!(expect_delivery(&mut rx).await.payload, Bytes::from("c"));
+    expect_quiet(&mut rx).await;
+    assert!(
+        transport
+            .list_dead_letters(domain, None)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    assert_eq!(transport.purge_dead_letters(domain, None).await.unwrap(), 0);
+
+    sub.unsubscribe().await.unwrap();
+}
+
+/// A message that keeps failing is dead-lettered once it has been
+/// delivered `max_deliveries` times.
+pub async fn max_deliveries_dead_letters(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("poison");
+    let (handler, mut rx) = RecordingHandler::new(Behavior::AlwaysError);
+    let sub = transport
+        .subscribe(
+            &subject,
+            "g",
+            handler,
+            Some(SubscribeOpts {
+                start_from: StartPosition::Earliest,
+                ack_timeout: ACK_TIMEOUT,
+                max_deliveries: Some(2),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+    tokio::time::sleep(SETTLE).await;
+
+    let ids = publish_all(&transport, &subject, &["poison"]).await;
+    let got = expect_deliveries(&mut rx, 2).await;
+    assert!(got.iter().all(|env| env.message_id == ids[0]));
+    // Past another reclaim cycle: no third attempt.
+    tokio::time::sleep(ACK_TIMEOUT * 2).await;
+    expect_quiet(&mut rx).await;
+
+    let listed = transport
+        .list_dead_letters(domain_of(&subject), None)
+        .await
+        .unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].original.message_id, ids[0]);
+    assert_eq!(listed[0].reason, max_deliveries_reason(2));
+
+    sub.unsubscribe().await.unwrap();
+}
+
+/// `*` and `>` subscriptions consume every matching stream, including
+/// streams created after subscribing, and nothing else.
+pub async fn wildcard_subscription(transport: Arc<dyn Transport>) {
+    let base = unique_subject("wild");
+    let existing = format!("{base}.existing");
+    let created_later = format!("{base}.later");
+    let deeper = format!("{base}.later.deeper");
+    let outside = format!("{base}x.other");
+
+    publish_all(&transport, &existing, &["existing"]).await;
+
+    let (star, mut star_rx) = RecordingHandler::new(Behavior::Ack);
+    let star_sub = transport
+        .subscribe(
+            &format!("{base}.*"),
+            "star",
+            star,
+            opts(StartPosition::Earliest),
+        )
+        .await
+        .unwrap();
+    let (gt, mut gt_rx) = RecordingHandler::new(Behavior::Ack);
+    let gt_sub = transport
+        .subscribe(
+            &format!("{base}.>"),
+            "gt",
+            gt,
+            opts(StartPosition::Earliest),
+        )
+        .await
+        .unwrap();
+    tokio::time::sleep(SETTLE).await;
+
+    publish_all(&transport, &created_later, &["later"]).await;
+    publish_all(&transport, &deeper, &["deeper"]).await;
+    publish_all(&transport, &outside, &["outside"]).await;
+
+    let mut star_got: Vec<_> = expect_deliveries(&mut star_rx, 2)
+        .await
+        .into_iter()
+        .map(|env| env.subject)
+        .collect();
+    star_got.sort();
+    assert_eq!(star_got, [existing.clone(), created_later.clone()]);
+    expect_quiet(&mut star_rx).await;
+
+    let mut gt_got: Vec<_> = expect_deliveries(&mut gt_rx, 3)
+        .await
+        .into_iter()
+        .map(|env| env.subject)
+        .collect();
+    gt_got.sort();
+    assert_eq!(gt_got, [existing, created_later, deeper]);
+    expect_quiet(&mut gt_rx).await;
+
+    assert!(
+        transport
+            .publish(&format!("{base}.*"), Bytes::from("x"), None)
+            .await
+            .is_err(),
+        "publishing to a wildcard must fail"
+    );
+
+    star_sub.unsubscribe().await.unwrap();
+    gt_sub.unsubscribe().await.unwrap();
+}
+
+/// `StartPosition::Earliest` replays messages published before subscribe.
+pub async fn start_earliest(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("earliest");
+    publish_all(&transport, &subject, &["before-0", "before-1"]).await;
+
+    let (handler, mut rx) = RecordingHandler::new(Behavior::Ack);
+    let sub = transport
+        .subscribe(&subject, "g", handler, opts(StartPosition::Earliest))
+        .await
+        .unwrap();
+    tokio::time::sleep(SETTLE).await;
+    publish_all(&transport, &subject, &["after"]).await;
+
+    let got = expect_deliveries(&mut rx, 3).await;
+    assert_eq!(payloads_of(&got), ["before-0", "before-1", "after"]);
+
+    sub.unsubscribe().await.unwrap();
+}
+
+/// `StartPosition::Latest` skips messages published before subscribe.
+pub async fn start_latest(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("latest");
+    publish_all(&transport, &subject, &["before"]).await;
+
+    let (handler, mut rx) = RecordingHandler::new(Behavior::Ack);
+    let sub = transport
+        .subscribe(&subject, "g", handler, opts(StartPosition::Latest))
+        .await
+        .unwrap();
+    tokio::time::sleep(SETTLE).await;
+    publish_all(&transport, &subject, &["after"]).await;
+
+    assert_eq!(payloads_of(&[expect_delivery(&mut rx).await]), ["after"]);
+    expect_quiet(&mut rx).await;
+
+    sub.unsubscribe().await.unwrap();
+}
+
+/// `StartPosition::Timestamp` delivers messages published at or after the timestamp.
+#[allow(clippy::cast_possible_truncation)] // millis since epoch fits in u64 until year 584556
+pub async fn start_timestamp(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("timestamp");
+    publish_all(&transport, &subject, &["old"]).await;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    let ts = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as u64;
+    tokio::time::sleep(Duration::from_millis(50)).await;
+    publish_all(&transport, &subject, &["new"]).await;
+
+    let (handler, mut rx) = RecordingHandler::new(Behavior::Ack);
+    let sub = transport
+        .subscribe(&subject, "g", handler, opts(StartPosition::Timestamp(ts)))
+        .await
+        .unwrap();
+
+    assert_eq!(payloads_of(&[expect_delivery(&mut rx).await]), ["new"]);
+    expect_quiet(&mut rx).await;
+
+    sub.unsubscribe().await.unwrap();
+}
+
+/// `StartPosition::Id` delivers messages published after the given message ID.
+pub async fn start_id(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("start-id");
+    let ids = publish_all(&transport, &subject, &["a", "b", "c"]).await;
+
+    let (handler, mut rx) = RecordingHandler::new(Behavior::Ack);
+    let sub = transport
+        .subscribe(
+            &subject,
+            "g",
+            handler,
+            opts(StartPosition::Id(ids[0].clone())),
+        )
+        .await
+        .unwrap();
+
+    let got = expect_deliveries(&mut rx, 2).await;
+    assert_eq!(payloads_of(&got), ["b", "c"]);
+    expect_quiet(&mut rx).await;
+
+    sub.unsubscribe().await.unwrap();
+}
+
+/// No more than `max_inflight` unacked messages are handed out at once.
+pub async fn max_inflight(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("inflight");
+    let (handler, mut rx) = RecordingHandler::new(Behavior::Hold);
+    let sub = transport
+        .subscribe(
+            &subject,
+            "g",
+            handler,
+            Some(SubscribeOpts {
+                start_from: StartPosition::Earliest,
+                max_inflight: 2,
+                // Long enough that nothing is reclaimed during the case.
+                ack_timeout: Duration::from_secs(60),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+    tokio::time::sleep(SETTLE).await;
+
+    publish_all(&transport, &subject, &["0", "1", "2", "3", "4"]).await;
+
+    expect_deliveries(&mut rx, 2).await;
+    expect_quiet(&mut rx).await;
+
+    sub.unsubscribe().await.unwrap();
+}
+
+/// Up to `concurrency` handler invocations run at once, and each message
+/// is still acked exactly once.
+pub async fn concurrent_handlers(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("concurrent");
+    let (handler, mut rx, peaks) = SlowHandler::new(Duration::from_millis(200));
+    let sub = transport
+        .subscribe(
+            &subject,
+            "g",
+            handler,
+            Some(SubscribeOpts {
+                concurrency: 3,
+                ..opts(StartPosition::Earliest).unwrap()
+            }),
+        )
+        .await
+        .unwrap();
+    tokio::time::sleep(SETTLE).await;
+
+    let ids = publish_all(
+        &transport,
+        &subject,
+        &["0", "1", "2", "3", "4", "5", "6", "7", "8"],
+    )
+    .await;
+
+    let mut seen: Vec<String> = expect_deliveries(&mut rx, ids.len())
+        .await
+        .into_iter()
+        .map(|e| e.message_id)
+        .collect();
+    // Past ack_timeout: anything left unacked would be redelivered.
+    expect_quiet(&mut rx).await;
+    assert_eq!(peaks.all.load(std::sync::atomic::Ordering::Acquire), 3);
+
+    seen.sort();
+    let mut expected = ids;
+    expected.sort();
+    assert_eq!(seen, expected, "each message must be handled exactly once");
+
+    sub.unsubscribe().await.unwrap();
+}
+
+/// Unsubscribing lets running handlers finish and acks nothing else; the
+/// group gets the rest.
+pub async fn concurrent_unsubscribe(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("concurrent-unsub");
+    let (handler, mut rx, _) = SlowHandler::new(Duration::from_millis(300));
+    let sub = transport
+        .subscribe(
+            &subject,
+            "g",
+            handler,
+            Some(SubscribeOpts {
+                concurrency: 2,
+                ..opts(StartPosition::Earliest).unwrap()
+            }),
+        )
+        .await
+        .unwrap();
+    tokio::time::sleep(SETTLE).await;
+
+    let ids = publish_all(&transport, &subject, &["0", "1", "2", "3", "4", "5"]).await;
+    let mut seen: Vec<String> = expect_deliveries(&mut rx, 2)
+        .await
+        .into_iter()
+        .map(|e| e.message_id)
+        .collect();
+    sub.unsubscribe().await.unwrap();
+    expect_quiet(&mut rx).await;
+
+    let (handler, mut rx) = RecordingHandler::new(Behavior::Ack);
+    let sub = transport
+        .subscribe(&subject, "g", handler, opts(StartPosition::Earliest))
+        .await
+        .unwrap();
+    seen.extend(
+        expect_deliveries(&mut rx, 4)
+            .await
+            .into_iter()
+            .map(|e| e.message_id),
+    );
+    expect_quiet(&mut rx).await;
+
+    seen.sort();
+    let mut expected = ids;
+    expected.sort();
+    assert_eq!(seen, expected, "each message must be handled exactly once");
+
+    sub.unsubscribe().await.unwrap();
+}
+
+/// An `ordered` subscription handles each ordering key one message at a
+/// time in publish order, and different keys side by side.
+pub async fn ordered_by_key(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("ordered");
+    let (handler, mut rx, peaks) = SlowHandler::new(Duration::from_millis(100));
+    let sub = transport
+        .subscribe(
+            &subject,
+            "g",
+            handler,
+            Some(SubscribeOpts {
+                concurrency: 4,
+                ordered: true,
+                ..opts(StartPosition::Earliest).unwrap()
+            }),
+        )
+        .await
+        .unwrap();
+    tokio::time::sleep(SETTLE).await;
+
+    let mut published = Vec::new();
+    for i in 0..4 {
+        for key in ["job-a", "job-b", "job-c"] {
+            let payload = format!("{key}/{i}");
+            transport
+                .publish(
+                    &subject,
+                    Bytes::from(payload.clone()),
+                    Some(PublishOpts {
+                        ordering_key: Some(key.to_string()),
+                        ..Default::default()
+                    }),
+                )
+                .await
+                .unwrap();
+            published.push(payload);
+        }
+    }
+
+    let got = expect_deliveries(&mut rx, published.len()).await;
+    expect_quiet(&mut rx).await;
+    for key in ["job-a", "job-b", "job-c"] {
+        let handled: Vec<String> = got
+            .iter()
+            .filter(|e| e.ordering_key.as_deref() == Some(key))
+            .map(|e| String::from_utf8_lossy(&e.payload).to_string())
+            .collect();
+        let expected: Vec<String> = (0..4).map(|i| format!("{key}/{i}")).collect();
+        assert_eq!(handled, expected, "{key} handled out of order");
+    }
+    assert_eq!(got.len(), published.len());
+    assert_eq!(peaks.per_key.load(std::sync::atomic::Ordering::Acquire), 1);
+    assert!(peaks.all.load(std::sync::atomic::Ordering::Acquire) > 1);
+
+    sub.unsubscribe().await.unwrap();
+}
+
+/// `trim_stream` removes entries older than `max_age` and reports the count.
+pub async fn trim(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("trim");
+    publish_all(&transport, &subject, &["old-0", "old-1", "old-2"]).await;
+    tokio::time::sleep(Duration::from_millis(20)).await;
+
+    let kept = transport
+        .trim_stream(&subject, Duration::from_secs(3600))
+        .await
+        .unwrap();
+    assert_eq!(kept, 0, "nothing is older than an hour");
+
+    let trimmed = transport
+        .trim_stream(&subject, Duration::ZERO)
+        .await
+        .unwrap();
+    assert_eq!(trimmed, 3);
+
+    publish_all(&transport, &subject, &["fresh"]).await;
+    let (handler, mut rx) = RecordingHandler::new(Behavior::Ack);
+    let sub = transport
+        .subscribe(&subject, "g", handler, opts(StartPosition::Earliest))
+        .await
+        .unwrap();
+    assert_eq!(payloads_of(&[expect_delivery(&mut rx).await]), ["fresh"]);
+    expect_quiet(&mut rx).await;
+    sub.unsubscribe().await.unwrap();
+
+    let missing = transport
+        .trim_stream(&unique_subject("trim-missing"), Duration::ZERO)
+        .await
+        .unwrap();
+    assert_eq!(missing, 0);
+}
+
+/// `trim_stream_before` removes exactly the entries published before the cutoff.
+pub async fn trim_before(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("trim-before");
+    publish_all(&transport, &subject, &["m0", "m1", "m2"]).await;
+
+    assert_eq!(transport.trim_stream_before(&subject, 0).await.unwrap(), 0);
+
+    let future_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis()
+        + 60_000;
+    let trimmed = transport
+        .trim_stream_before(&subject, u64::try_from(future_ms).unwrap())
+        .await
+        .unwrap();
+    assert_eq!(trimmed, 3);
+}
+
+/// Trims keep an entry appended exactly at the cutoff, as Redis `MINID`
+/// does, and remove it once the cutoff is past it.
+pub async fn trim_boundary(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("trim-boundary");
+    publish_all(&transport, &subject, &["m0"]).await;
+    let (handler, mut rx) = RecordingHandler::new(Behavior::Hold);
+    let sub = transport
+        .subscribe(&subject, "g", handler, opts(StartPosition::Earliest))
+        .await
+        .unwrap();
+    expect_delivery(&mut rx).await;
+    let appended = transport
+        .oldest_unacked(&subject)
+        .await
+        .unwrap()
+        .expect("held message is unacked");
+
+    assert_eq!(
+        transport
+            .trim_stream_before(&subject, appended)
+            .await
+            .unwrap(),
+        0,
+        "an entry appended at the cutoff is kept"
+    );
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis();
+    let age = Duration::from_millis(u64::try_from(now_ms).unwrap() - appended);
+    assert_eq!(
+        transport
+            .trim_stream(&subject, age + Duration::from_secs(60))
+            .await
+            .unwrap(),
+        0
+    );
+    assert_eq!(
+        transport
+            .trim_stream_before(&subject, appended + 1)
+            .await
+            .unwrap(),
+        1,
+        "an entry appended before the cutoff is removed"
+    );
+
+    sub.unsubscribe().await.unwrap();
+}
+
+/// `oldest_unacked` reports the oldest entry a group has not acked, and
+/// trimming up to it keeps that entry.
+pub async fn oldest_unacked(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("oldest-unacked");
+    publish_all(&transport, &subject, &["m0", "m1", "m2"]).await;
+    assert_eq!(transport.oldest_unacked(&subject).await.unwrap(), None);
+
+    let (handler, mut acked_rx) = RecordingHandler::new(Behavior::Ack);
+    let acker = transport
+        .subscribe(&subject, "acker", handler, opts(StartPosition::Earliest))
+        .await
+        .unwrap();
+    expect_deliveries(&mut acked_rx, 3).await;
+    tokio::time::sleep(SETTLE).await;
+    assert_eq!(
+        transport.oldest_unacked(&subject).await.unwrap(),
+        None,
+        "a caught-up group holds nothing back"
+    );
+
+    let (handler, mut held_rx) = RecordingHandler::new(Behavior::Hold);
+    let holder = transport
+        .subscribe(&subject, "holder", handler, opts(StartPosition::Earliest))
+        .await
+        .unwrap();
+    let held = expect_deliveries(&mut held_rx, 3).await;
+    let oldest = transport
+        .oldest_unacked(&subject)
+        .await
+        .unwrap()
+        .expect("held messages are unacked");
+    assert!(oldest >= held[0].timestamp);
+
+    assert_eq!(
+        transport
+            .trim_stream_before(&subject, oldest)
+            .await
+            .unwrap(),
+        0,
+        "the oldest unacked entry survives a trim up to it"
+    );
+
+    acker.unsubscribe().await.unwrap();
+    holder.unsubscribe().await.unwrap();
+}
+
+/// `group_info` reports the stream length, what the group has been handed
+/// and what it has yet to see.
+pub async fn group_info(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("group-info");
+    assert!(transport.group_info(&subject, "g").await.unwrap().is_none());
+
+    let (handler, mut rx) = RecordingHandler::new(Behavior::Hold);
+    let sub = transport
+        .subscribe(
+            &subject,
+            "g",
+            handler,
+            Some(SubscribeOpts {
+                start_from: StartPosition::Earliest,
+                max_inflight: 2,
+                // Long enough that nothing is reclaimed during the case.
+                ack_timeout: Duration::from_secs(60),
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+    tokio::time::sleep(SETTLE).await;
+
+    let ids = publish_all(&transport, &subject, &["0", "1", "2"]).await;
+    expect_deliveries(&mut rx, 2).await;
+    expect_quiet(&mut rx).await;
+
+    let info = transport.group_info(&subject, "g").await.unwrap().unwrap();
+    assert_eq!(info.subject, subject);
+    assert_eq!(info.group, "g");
+    assert_eq!(info.length, 3);
+    assert_eq!(info.last_delivered_id.as_deref(), Some(ids[1].as_str()));
+    assert_eq!(info.pending, 2);
+    assert_eq!(info.lag, 1);
+    if !info.consumers.is_empty() {
+        let pending: u64 = info.consumers.iter().map(|c| c.pending).sum();
+        assert_eq!(pending, 2);
+    }
+    assert!(
+        transport
+            .group_info(&subject, "other")
+            .await
+            .unwrap()
+            .is_none()
+    );
+
+    sub.unsubscribe().await.unwrap();
+}
+
+/// Groups can be listed, moved, pruned of idle consumers and deleted, and
+/// `ensure_stream` creates none of its own.
+pub async fn group_admin(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("group-admin");
+    transport
+        .ensure_stream(StreamConfig {
+            subject: subject.clone(),
+            max_age: Duration::ZERO,
+            max_bytes: None,
+            max_msgs: None,
+        })
+        .await
+        .unwrap();
+    assert!(transport.list_groups(&subject).await.unwrap().is_empty());
+
+    let ids = publish_all(&transport, &subject, &["0", "1", "2"]).await;
+    let (handler, mut rx) = RecordingHandler::new(Behavior::Ack);
+    let sub = transport
+        .subscribe(&subject, "a", handler, opts(StartPosition::Earliest))
+        .await
+        .unwrap();
+    expect_deliveries(&mut rx, 3).await;
+    sub.unsubscribe().await.unwrap();
+    assert_eq!(transport.list_groups(&subject).await.unwrap(), vec!["a"]);
+
+    // Back to the start: an existing group ignores start_from.
+    transport
+        .set_group_position(&subject, "a", StartPosition::Earliest)
+        .await
+        .unwrap();
+    let (handler, mut rx) = RecordingHandler::new(Behavior::Ack);
+    let sub = transport
+        .subscribe(&subject, "a", handler, opts(StartPosition::Latest))
+        .await
+        .unwrap();
+    let replayed: Vec<String> = expect_deliveries(&mut rx, 3)
+        .await
+        .into_iter()
+        .map(|e| e.message_id)
+        .collect();
+    assert_eq!(replayed, ids);
+    sub.unsubscribe().await.unwrap();
+
+    transport
+        .set_group_position(&subject, "a", StartPosition::Id(ids[1].clone()))
+        .await
+        .unwrap();
+    let info = transport.group_info(&subject, "a").await.unwrap().unwrap();
+    assert_eq!(info.last_delivered_id.as_deref(), Some(ids[1].as_str()));
+    assert_eq!(info.lag, 1);
+    assert!(
+        transport
+            .set_group_position(&subject, "missing", StartPosition::Earliest)
+            .await
+            .is_err()
+    );
+
+    // Both consumers acked everything, so neither holds messages back.
+    transport
+        .remove_idle_consumers(&subject, "a", Duration::ZERO)
+        .await
+        .unwrap();
+    let info = transport.group_info(&subject, "a").await.unwrap().unwrap();
+    assert!(info.consumers.is_empty());
+
+    assert!(transport.delete_group(&subject, "a").await.unwrap());
+    assert!(!transport.delete_group(&subject, "a").await.unwrap());
+    assert!(transport.list_groups(&subject).await.unwrap().is_empty());
+    assert!(
+        !transport
+            .delete_group(&unique_subject("no-stream"), "a")
+            .await
+            .unwrap()
+    );
+}
+
+/// `ensure_stream` records the stream's limits so they can be read back.
+pub async fn stream_config_registry(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("registry");
+    assert!(transport.stream_config(&subject).await.unwrap().is_none());
+
+    let config = StreamConfig {
+        subject: subject.clone(),
+        max_age: Duration::from_secs(3600),
+        max_bytes: Some(1_048_576),
+        max_msgs: None,
+    };
+    transport.ensure_stream(config.clone()).await.unwrap();
+    assert_eq!(
+        transport.stream_config(&subject).await.unwrap(),
+        Some(config.clone())
+    );
+
+    let updated = StreamConfig {
+        max_msgs: Some(10),
+        ..config
+    };
+    transport.ensure_stream(updated.clone()).await.unwrap();
+    let all = transport.stream_configs().await.unwrap();
+    assert_eq!(
+        all.iter()
+            .filter(|c| c.subject == subject)
+            .collect::<Vec<_>>(),
+        [&updated]
+    );
+}
+
+/// Publishing evicts the oldest messages beyond `max_msgs` or `max_age`.
+pub async fn retention_limits(transport: Arc<dyn Transport>) {
+    let counted = unique_subject("retention-msgs");
+    transport
+        .ensure_stream(StreamConfig {
+            subject: counted.clone(),
+            max_age: Duration::ZERO,
+            max_bytes: None,
+            max_msgs: Some(3),
+        })
+        .await
+        .unwrap();
+    publish_all(&transport, &counted, &["m0", "m1", "m2", "m3", "m4"]).await;
+
+    let (handler, mut rx) = RecordingHandler::new(Behavior::Ack);
+    let sub = transport
+        .subscribe(&counted, "g", handler, opts(StartPosition::Earliest))
+        .await
+        .unwrap();
+    let got = expect_deliveries(&mut rx, 3).await;
+    assert_eq!(payloads_of(&got), ["m2", "m3", "m4"]);
+    expect_quiet(&mut rx).await;
+    sub.unsubscribe().await.unwrap();
+
+    let aged = unique_subject("retention-age");
+    transport
+        .ensure_stream(StreamConfig {
+            subject: aged.clone(),
+            max_age: Duration::from_millis(300),
+            max_bytes: None,
+            max_msgs: None,
+        })
+        .await
+        .unwrap();
+    publish_all(&transport, &aged, &["old-0", "old-1"]).await;
+    tokio::time::sleep(Duration::from_millis(500)).await;
+    publish_all(&transport, &aged, &["fresh"]).await;
+
+    let (handler, mut rx) = RecordingHandler::new(Behavior::Ack);
+    let sub = transport
+        .subscribe(&aged, "g", handler, opts(StartPosition::Earliest))
+        .await
+        .unwrap();
+    assert_eq!(payloads_of(&[expect_delivery(&mut rx).await]), ["fresh"]);
+    expect_quiet(&mut rx).await;
+    sub.unsubscribe().await.unwrap();
+}
+
+/// After `unsubscribe`, the handler sees no further messages.
+pub async fn unsubscribe_stops_delivery(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("unsub");
+    let (handler, mut rx) = RecordingHandler::new(Behavior::Ack);
+    let sub = transport
+        .subscribe(&subject, "g", handler, opts(StartPosition::Earliest))
+        .await
+        .unwrap();
+    tokio::time::sleep(SETTLE).await;
+
+    publish_all(&transport, &subject, &["before"]).await;
+    expect_delivery(&mut rx).await;
+
+    sub.unsubscribe().await.unwrap();
+    assert!(!sub.is_active());
+
+    publish_all(&transport, &subject, &["after"]).await;
+    expect_quiet(&mut rx).await;
+}
+
+/// `unsubscribe` returns once the running handler has finished and settled
+/// its message, and `finished` resolves with it.
+pub async fn unsubscribe_drains_handlers(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("drain");
+    let hold = Duration::from_millis(300);
+    let (handler, mut rx, _) = SlowHandler::new(hold);
+    let sub = transport
+        .subscribe(&subject, "g", handler, opts(StartPosition::Earliest))
+        .await
+        .unwrap();
+    tokio::time::sleep(SETTLE).await;
+
+    publish_all(&transport, &subject, &["slow"]).await;
+    expect_delivery(&mut rx).await;
+    let started = Instant::now();
+    sub.unsubscribe().await.unwrap();
+    assert!(
+        started.elapsed() >= hold / 2,
+        "unsubscribe returned before the handler finished"
+    );
+    tokio::time::timeout(SETTLE, sub.finished())
+        .await
+        .expect("finished must resolve once drained");
+
+    // The handler acked before unsubscribe returned; nothing is left.
+    let (handler, mut rx) = RecordingHandler::new(Behavior::Ack);
+    let sub = transport
+        .subscribe(&subject, "g", handler, opts(StartPosition::Earliest))
+        .await
+        .unwrap();
+    expect_quiet(&mut rx).await;
+    sub.unsubscribe().await.unwrap();
+}
+
+/// Handlers still running at `drain_timeout` are abandoned and their
+/// messages go back to the group.
+pub async fn drain_timeout_redelivers(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("drain-timeout");
+    let (handler, mut rx, _) = SlowHandler::new(Duration::from_secs(60));
+    let sub = transport
+        .subscribe(
+            &subject,
+            "g",
+            handler,
+            Some(SubscribeOpts {
+                drain_timeout: Duration::from_millis(200),
+                ..opts(StartPosition::Earliest).unwrap()
+            }),
+        )
+        .await
+        .unwrap();
+    tokio::time::sleep(SETTLE).await;
+
+    let ids = publish_all(&transport, &subject, &["stuck"]).await;
+    expect_delivery(&mut rx).await;
+    let started = Instant::now();
+    sub.unsubscribe().await.unwrap();
+    assert!(
+        started.elapsed() < DELIVERY_TIMEOUT,
+        "unsubscribe must give up at drain_timeout"
+    );
+
+    let (handler, mut rx) = RecordingHandler::new(Behavior::Ack);
+    let sub = transport
+        .subscribe(&subject, "g", handler, opts(StartPosition::Earliest))
+        .await
+        .unwrap();
+    assert_eq!(expect_delivery(&mut rx).await.message_id, ids[0]);
+    sub.unsubscribe().await.unwrap();
+}
+
+/// Pulled messages arrive through `fetch`, `recv` and the `Stream` impl,
+/// and are settled like handled ones.
+pub async fn pull_fetch_and_settle(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("pull");
+    let mut stream = transport
+        .pull(&subject, "g", opts(StartPosition::Earliest))
+        .await
+        .unwrap();
+    tokio::time::sleep(SETTLE).await;
+
+    let ids = publish_all(&transport, &subject, &["a", "b", "c"]).await;
+
+    let mut got = Vec::new();
+    while got.len() < 2 {
+        let batch = stream.fetch(2 - got.len(), DELIVERY_TIMEOUT).await;
+        assert!(!batch.is_empty(), "timed out waiting for delivery");
+        got.extend(batch);
+    }
+    assert_eq!(got[0].envelope().message_id, ids[0]);
+    assert_eq!(got[1].envelope().message_id, ids[1]);
+    got[0].ack().await.unwrap();
+    got[1].nak(None).await.unwrap();
+
+    let mut redelivered = Vec::new();
+    while redelivered.len() < 2 {
+        let msg = std::future::poll_fn(|cx| Pin::new(&mut stream).poll_next(cx))
+            .await
+            .expect("stream ended");
+        redelivered.push((msg.envelope().message_id.clone(), msg.delivery_count()));
+        msg.ack().await.unwrap();
+    }
+    redelivered.sort();
+    let mut expected = vec![(ids[1].clone(), 2), (ids[2].clone(), 1)];
+    expected.sort();
+    assert_eq!(redelivered, expected);
+
+    let quiet = tokio::time::timeout(QUIET_PERIOD, stream.recv()).await;
+    assert!(quiet.is_err(), "unexpected delivery");
+
+    stream.close().await.unwrap();
+    assert!(!stream.is_active());
+}
+
+/// A pull stream reads ahead no further than `batch_size`; the rest stays
+/// with the group, and `close` hands back what was read ahead.
+pub async fn pull_backpressure(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("pull-bp");
+    let pull_opts = SubscribeOpts {
+        start_from: StartPosition::Earliest,
+        batch_size: 1,
+        // Long enough that nothing is reclaimed during the case.
+        ack_timeout: Duration::from_secs(60),
+        ..Default::default()
+    };
+    let mut stream = transport
+        .pull(&subject, "g", Some(pull_opts.clone()))
+        .await
+        .unwrap();
+    tokio::time::sleep(SETTLE).await;
+
+    let ids = publish_all(&transport, &subject, &["0", "1", "2", "3", "4", "5"]).await;
+    tokio::time::sleep(SETTLE).await;
+
+    // Nothing is pulled yet, so the stream holds a buffered message plus
+    // at most one waiting for room.
+    let (handler, mut rx) = RecordingHandler::new(Behavior::Ack);
+    let sub = transport
+        .subscribe(&subject, "g", handler, Some(pull_opts))
+        .await
+        .unwrap();
+    let mut seen: Vec<String> = expect_deliveries(&mut rx, 4)
+        .await
+        .into_iter()
+        .map(|e| e.message_id)
+        .collect();
+
+    let first = stream
+        .fetch(1, DELIVERY_TIMEOUT)
+        .await
+        .pop()
+        .expect("timed out waiting for delivery");
+    first.ack().await.unwrap();
+    seen.push(first.envelope().message_id.clone());
+    stream.close().await.unwrap();
+
+    seen.extend(
+        expect_deliveries(&mut rx, 1)
+            .await
+            .into_iter()
+            .map(|e| e.message_id),
+    );
+    expect_quiet(&mut rx).await;
+    seen.sort();
+    let mut expected = ids;
+    expected.sort();
+    assert_eq!(seen, expected, "each message must be handled exactly once");
+
+    sub.unsubscribe().await.unwrap();
+}
+
+/// `close` drains every subscription, as `unsubscribe` does, before
+/// returning.
+pub async fn close_drains_subscriptions(transport: Arc<dyn Transport>) {
+    let hold = Duration::from_millis(300);
+    let mut subs = Vec::new();
+    let mut deliveries = Vec::new();
+    for name in ["close-drain-a", "close-drain-b"] {
+        let subject = unique_subject(name);
+        let (handler, rx, _) = SlowHandler::new(hold);
+        let sub = transport
+            .subscribe(&subject, "g", handler, opts(StartPosition::Earliest))
+            .await
+            .unwrap();
+        subs.push(sub);
+        deliveries.push((subject, rx));
+    }
+    tokio::time::sleep(SETTLE).await;
+
+    for (subject, rx) in &mut deliveries {
+        publish_all(&transport, subject, &["slow"]).await;
+        expect_delivery(rx).await;
+    }
+    let started = Instant::now();
+    transport.close().await.unwrap();
+    assert!(
+        started.elapsed() >= hold / 2,
+        "close returned before the handlers finished"
+    );
+    for sub in &subs {
+        assert!(!sub.is_active());
+        tokio::time::timeout(SETTLE, sub.finished())
+            .await
+            .expect("finished must resolve once closed");
+    }
+}
+
+/// Group names starting with `_` are reserved: subscribe and pull reject
+/// them.
+pub async fn reserved_group_names(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("reserved-group");
+    for group in ["_init", "_anything", ""] {
+        let (handler, _rx) = RecordingHandler::new(Behavior::Ack);
+        assert!(
+            matches!(
+                transport.subscribe(&subject, group, handler, None).await,
+                Err(TransportError::Subscribe(_))
+            ),
+            "subscribe accepted group {group:?}"
+        );
+        assert!(
+            matches!(
+                transport.pull(&subject, group, None).await,
+                Err(TransportError::Subscribe(_))
+            ),
+            "pull accepted group {group:?}"
+        );
+    }
+}
+
+/// A closed transport rejects publish, subscribe and pull.
+pub async fn close_rejects_operations(transport: Arc<dyn Transport>) {
+    let subject = unique_subject("closed");
+    transport.close().await.unwrap();
+
+    assert!(
+        transport
+            .publish(&subject, Bytes::from("nope"), None)
+            .await
+            .is_err()
+    );
+    let (handler, _rx) = RecordingHandler::new(Behavior::Ack);
+    assert!(
+        transport
+            .subscribe(&subject, "g", handler, None)
+            .await
+            .is_err()
+    );
+    assert!(transport.pull(&subject, "g", None).await.is_err());
+}
@@ -0,0 +1,150 @@
+//! Shared dead-letter storage format.
+//!
+//! Every backend stores dead letters the same way: as an ordinary message on
+//! `gbe._deadletter.{domain}` (Redis key `gbe:_deadletter:{domain}`), so a
+//! dead-letter stream can be subscribed to like any other subject.
+//!
+//! - `message_id`: ID of the dead-letter entry (selects it for replay/purge)
+//! - `timestamp`: when the message was dead-lettered (unix millis)
+//! - `trace_id`: copied from the original message
+//! - `payload`: JSON object
+//!
+//! ```json
+//! {
+//!   "original_envelope": { "message_id": "01HQ...", "subject": "gbe.tasks.email-send.queue", ... },
+//!   "reason": "max deliveries exceeded after 5 attempts"
+//! }
+//! ```
+
+use bytes::Bytes;
+use serde::{Deserialize, Serialize};
+
+use crate::envelope::Envelope;
+use crate::error::TransportError;
+
+/// Dead-letter subject for the domain of `subject` (its second token).
+///
+/// `gbe.tasks.email-send.queue` → `gbe._deadletter.tasks`
+#[must_use]
+pub fn dead_letter_subject(subject: &str) -> String {
+    domain_dead_letter_subject(subject.split('.').nth(1).unwrap_or("unknown"))
+}
+
+/// Dead-letter subject for `domain`: `gbe._deadletter.{domain}`.
+#[must_use]
+pub fn domain_dead_letter_subject(domain: &str) -> String {
+    format!("gbe._deadletter.{domain}")
+}
+
+/// Dead-letter reason used when a message exceeds `SubscribeOpts::max_deliveries`.
+#[must_use]
+pub fn max_deliveries_reason(attempts: u32) -> String {
+    format!("max deliveries exceeded after {attempts} attempts")
+}
+
+/// A dead-lettered message, as listed by `Transport::list_dead_letters`.
+#[derive(Debug, Clone)]
+pub struct DeadLetter {
+    /// ID of the dead-letter entry (not of the original message).
+    pub id: String,
+    /// When the message was dead-lettered (unix millis).
+    pub dead_lettered_at: u64,
+    /// Why it was dead-lettered.
+    pub reason: String,
+    /// The message as it was originally delivered.
+    pub original: Envelope,
+}
+
+/// JSON payload of a dead-letter entry.
+#[derive(Serialize, Deserialize)]
+struct DeadLetterPayload {
+    original_envelope: Envelope,
+    reason: String,
+}
+
+impl DeadLetter {
+    /// Wrap `original` into the envelope stored on its dead-letter subject.
+    ///
+    /// # Errors
+    /// Returns `TransportError::Serialization` if the payload cannot be encoded.
+    pub fn envelope(original: &Envelope, reason: &str) -> Result<Envelope, TransportError> {
+        let payload = serde_json::to_vec(&DeadLetterPayload {
+            original_envelope: original.clone(),
+            reason: reason.to_string(),
+        })?;
+        Ok(Envelope::new(
+            dead_letter_subject(&original.subject),
+            Bytes::from(payload),
+            original.trace_id.clone(),
+        ))
+    }
+
+    /// Read a dead letter back from its stored envelope.
+    ///
+    /// # Errors
+    /// Returns `TransportError::Serialization` if the payload is not a dead letter.
+    pub fn from_envelope(envelope: &Envelope) -> Result<Self, TransportError> {
+        let payload: DeadLetterPayload = serde_json::from_slice(&envelope.payload)?;
+        Ok(Self {
+            id: envelope.message_id.clone(),
+            dead_lettered_at: envelope.timestamp,
+            reason: payload.reason,
+            original: payload.original_envelope,
+        })
+    }
+
+    /// Subject the message was originally published to; replay sends it back here.
+    #[must_use]
+    pub fn original_subject(&self) -> &str {
+        &self.original.subject
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dead_letter_subject_uses_domain() {
+        assert_eq!(
+            dead_letter_subject("gbe.tasks.email-send.queue"),
+            "gbe._deadletter.tasks"
+        );
+        assert_eq!(dead_letter_subject("single"), "gbe._deadletter.unknown");
+        assert_eq!(domain_dead_letter_subject("tasks"), "gbe._deadletter.tasks");
+    }
+
+    #[test]
+    fn envelope_round_trip() {
+        let original = Envelope::new(
+            "gbe.tasks.email-send.queue".to_string(),
+            Bytes::from("hello"),
+            Some("trace-1".to_string()),
+        );
+
+        let stored = DeadLetter::envelope(&original, "boom").unwrap();
+        assert_eq!(stored.subject, "gbe._deadletter.tasks");
+        assert_eq!(stored.trace_id.as_deref(), Some("trace-1"));
+
+        let dl = DeadLetter::from_envelope(&stored).unwrap();
+        assert_eq!(dl.id, stored.message_id);
+        assert_eq!(dl.dead_lettered_at, stored.timestamp);
+        assert_eq!(dl.reason, "boom");
+        assert_eq!(dl.original_subject(), "gbe.tasks.email-send.queue");
+        assert_eq!(dl.original.message_id, original.message_id);
+        assert_eq!(dl.original.payload, original.payload);
+    }
+
+    #[test]
+    fn from_envelope_rejects_other_payloads() {
+        let env = Envelope::new(
+            "gbe._deadletter.tasks".to_string(),
+            Bytes::from("not json"),
+            None,
+        );
+        assert!(matches!(
+            DeadLetter::from_envelope(&env),
+            Err(TransportError::Serialization(_))
+        ));
+    }
+}
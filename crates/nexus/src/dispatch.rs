@@ -0,0 +1,227 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tokio::sync::{Semaphore, mpsc};
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+
+use crate::transport::{Message, MessageHandler, SubscribeOpts};
+
+/// Messages waiting behind the one in flight, per ordering key. A key is
+/// present while a worker owns it.
+type KeyQueues<M> = Arc<Mutex<HashMap<String, VecDeque<M>>>>;
+
+/// Handler tasks still running, and the message each is working on.
+struct Running<M> {
+    tasks: JoinSet<()>,
+    messages: HashMap<String, Arc<M>>,
+}
+
+impl<M> Default for Running<M> {
+    fn default() -> Self {
+        Self {
+            tasks: JoinSet::new(),
+            messages: HashMap::new(),
+        }
+    }
+}
+
+enum Target<M> {
+    Handler {
+        handler: Arc<dyn MessageHandler>,
+        /// One permit per handler invocation allowed at once.
+        slots: Arc<Semaphore>,
+        /// Set for `SubscribeOpts::ordered`.
+        queues: Option<KeyQueues<M>>,
+        running: Arc<Mutex<Running<M>>>,
+    },
+    Channel(mpsc::Sender<Box<dyn Message>>),
+}
+
+/// Where a backend's consumer loop hands its messages: a `MessageHandler`
+/// for `Transport::subscribe`, or a channel for `Transport::pull`.
+///
+/// For backends: it applies `SubscribeOpts::concurrency` and `ordered`,
+/// and on unsubscribe settles whatever the loop has handed over but the
+/// consumer has not finished, so each backend only reads and acks.
+pub struct Sink<M> {
+    target: Target<M>,
+}
+
+impl<M: Message + 'static> Sink<M> {
+    /// Run `handler` on each message, within `opts.concurrency` and in
+    /// ordering-key order if `opts.ordered`.
+    #[must_use]
+    pub fn handler(handler: Box<dyn MessageHandler>, opts: &SubscribeOpts) -> Self {
+        Self {
+            target: Target::Handler {
+                handler: Arc::from(handler),
+                slots: Arc::new(Semaphore::new(opts.concurrency.max(1) as usize)),
+                queues: opts.ordered.then(KeyQueues::default),
+                running: Arc::default(),
+            },
+        }
+    }
+
+    /// Send each message to `tx`, whose capacity bounds the read-ahead.
+    #[must_use]
+    pub fn channel(tx: mpsc::Sender<Box<dyn Message>>) -> Self {
+        Self {
+            target: Target::Channel(tx),
+        }
+    }
+
+    /// Hand `msg` over. When nobody is consuming anymore, `msg` is nak'd
+    /// and `token` cancelled so the loop stops.
+    ///
+    /// Handlers wait for a free slot, unless `msg` can queue behind an
+    /// in-flight message with the same ordering key; that key's worker
+    /// then handles it, in order, in the same slot. A handler error naks
+    /// the message.
+    pub async fn deliver(&self, msg: M, token: &CancellationToken) {
+        match &self.target {
+            Target::Handler {
+                handler,
+                slots,
+                queues,
+                running,
+            } => {
+                let key = queues
+                    .as_ref()
+                    .and_then(|queues| Some((queues, msg.envelope().ordering_key.clone()?)));
+                if let Some((queues, key)) = &key
+                    && let Some(queue) = queues.lock().unwrap().get_mut(key)
+                {
+                    queue.push_back(msg);
+                    return;
+                }
+
+                let slot = tokio::select! {
+                    biased;
+                    () = token.cancelled() => None,
+                    slot = slots.clone().acquire_owned() => slot.ok(),
+                };
+                let Some(slot) = slot else {
+                    let _ = msg.nak(None).await;
+                    return;
+                };
+                let key = key.map(|(queues, key)| {
+                    queues.lock().unwrap().insert(key.clone(), VecDeque::new());
+                    (queues.clone(), key)
+                });
+                let handler = handler.clone();
+                let token = token.clone();
+                let tracked = running.clone();
+                let mut running = running.lock().unwrap();
+                while running.tasks.try_join_next().is_some() {}
+                running.tasks.spawn(async move {
+                    let mut next = Some(msg);
+                    while let Some(msg) = next.take() {
+                        let msg = Arc::new(msg);
+                        let id = msg.envelope().message_id.clone();
+                        tracked
+                            .lock()
+                            .unwrap()
+                            .messages
+                            .insert(id.clone(), msg.clone());
+                        handle(&*handler, &*msg).await;
+                        tracked.lock().unwrap().messages.remove(&id);
+                        if let Some((queues, key)) = &key {
+                            next = next_in_line(queues, key, &token).await;
+                        }
+                    }
+                    drop(slot);
+                });
+            }
+            Target::Channel(tx) => {
+                let permit = tokio::select! {
+                    biased;
+                    () = token.cancelled() => None,
+                    permit = tx.reserve() => permit.ok(),
+                };
+                match permit {
+                    Some(permit) => permit.send(Box::new(msg)),
+                    None => {
+                        let _ = msg.nak(None).await;
+                        token.cancel();
+                    }
+                }
+            }
+        }
+    }
+
+    /// Wait up to `timeout` for the handler invocations still running.
+    /// Any still running then are aborted, and their messages nak'd along
+    /// with those queued behind them.
+    pub async fn drain(&self, timeout: Duration) {
+        let Target::Handler {
+            queues, running, ..
+        } = &self.target
+        else {
+            return;
+        };
+        let mut tasks = std::mem::take(&mut running.lock().unwrap().tasks);
+        let joined = tokio::time::timeout(timeout, async {
+            while tasks.join_next().await.is_some() {}
+        })
+        .await;
+        if joined.is_ok() {
+            return;
+        }
+
+        tasks.abort_all();
+        while tasks.join_next().await.is_some() {}
+        let aborted: Vec<_> = running.lock().unwrap().messages.drain().collect();
+        let queued: Vec<_> = queues.as_ref().map_or_else(Vec::new, |queues| {
+            queues
+                .lock()
+                .unwrap()
+                .drain()
+                .flat_map(|(_, q)| q)
+                .collect()
+        });
+        tracing::warn!(
+            aborted = aborted.len(),
+            queued = queued.len(),
+            "drain timed out, nak'ing unfinished messages"
+        );
+        for (_, msg) in aborted {
+            let _ = msg.nak(None).await;
+        }
+        for msg in queued {
+            let _ = msg.nak(None).await;
+        }
+    }
+}
+
+/// The message queued next behind `key`, releasing the key when there is
+/// none. Once unsubscribed, the queue is nak'd instead.
+async fn next_in_line<M: Message>(
+    queues: &KeyQueues<M>,
+    key: &str,
+    token: &CancellationToken,
+) -> Option<M> {
+    let rest = {
+        let mut queues = queues.lock().unwrap();
+        if !token.is_cancelled() {
+            let next = queues.get_mut(key).and_then(VecDeque::pop_front);
+            if next.is_none() {
+                queues.remove(key);
+            }
+            return next;
+        }
+        queues.remove(key).unwrap_or_default()
+    };
+    for msg in rest {
+        let _ = msg.nak(None).await;
+    }
+    None
+}
+
+async fn handle(handler: &dyn MessageHandler, msg: &dyn Message) {
+    if let Err(e) = handler.handle(msg).await {
+        tracing::warn!(message_id = %msg.envelope().message_id, "handler error, auto-nak: {e}");
+        let _ = msg.nak(None).await;
+    }
+}
@@ -2,6 +2,7 @@ use std::sync::Arc;
 
 use serde::Serialize;
 
+use crate::envelope::now_millis;
 use crate::error::TransportError;
 use crate::payload::DomainPayload;
 use crate::transport::{PublishOpts, Transport};
@@ -66,7 +67,7 @@ impl EventEmitter {
         let bytes = payload.to_bytes()?;
         let opts = PublishOpts {
             trace_id: Some(trace_id.into()),
-            idempotency_key: None,
+            ..Default::default()
         };
         self.transport.publish(subject, bytes, Some(opts)).await
     }
@@ -80,12 +81,8 @@ impl EventEmitter {
 /// Helper to generate a dedup ID from component, instance, and event kind.
 ///
 /// Format: `{component}-{instance_id}-{event}-{timestamp_millis}`
-#[allow(clippy::cast_possible_truncation)]
 pub fn dedup_id(component: &str, instance_id: &str, event: &str) -> String {
-    let ts = std::time::SystemTime::now()
-        .duration_since(std::time::UNIX_EPOCH)
-        .expect("system clock before epoch")
-        .as_millis() as u64;
+    let ts = now_millis();
     format!("{component}-{instance_id}-{event}-{ts}")
 }
 
@@ -169,6 +166,14 @@ mod tests {
         }
     }
 
+    #[tokio::test]
+    async fn group_admin_defaults_to_unsupported() {
+        let transport = MockTransport::new();
+        let err = transport.group_info("gbe.test", "g").await.unwrap_err();
+        assert!(matches!(err, TransportError::Other(ref m) if m == "unsupported"));
+        assert!(transport.list_groups("gbe.test").await.is_err());
+    }
+
     #[tokio::test]
     async fn emit_wraps_in_domain_payload() {
         let transport = Arc::new(MockTransport::new());
@@ -1,6 +1,20 @@
 use bytes::Bytes;
 use serde::{Deserialize, Serialize};
 
+/// Current wall-clock time as unix milliseconds, the unit of every
+/// timestamp on the transport and in the state store.
+///
+/// # Panics
+/// Panics if the system clock is before the Unix epoch.
+#[must_use]
+#[allow(clippy::cast_possible_truncation)] // millis since epoch fits in u64 until year 584556
+pub fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("system clock before epoch")
+        .as_millis() as u64
+}
+
 /// Wire envelope wrapping every message on the transport.
 ///
 /// The transport creates and reads the envelope. Domain code only sees the payload.
@@ -20,6 +34,10 @@ pub struct Envelope {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub trace_id: Option<String>,
 
+    /// Messages sharing a key are handled in order by `ordered` subscriptions.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ordering_key: Option<String>,
+
     /// Opaque payload bytes — domain-specific schema.
     #[serde(with = "base64_bytes")]
     pub payload: Bytes,
@@ -28,19 +46,16 @@ pub struct Envelope {
 impl Envelope {
     /// # Panics
     /// Panics if the system clock is before the Unix epoch.
-    #[allow(clippy::cast_possible_truncation)] // millis since epoch fits in u64 until year 584556
     pub fn new(subject: String, payload: Bytes, trace_id: Option<String>) -> Self {
         let id = ulid::Ulid::new();
-        let ts = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .expect("system clock before epoch")
-            .as_millis() as u64;
+        let ts = now_millis();
 
         Self {
             message_id: id.to_string(),
             subject,
             timestamp: ts,
             trace_id,
+            ordering_key: None,
             payload,
         }
     }
@@ -23,3 +23,31 @@ pub enum TransportError {
     #[error("{0}")]
     Other(String),
 }
+
+/// Why a payload could not be decoded into the consumer's schema.
+#[derive(Debug, Error)]
+pub enum PayloadError {
+    #[error("{0}")]
+    Decode(#[from] serde_json::Error),
+
+    #[error("no schema registered for {type_name}")]
+    UnknownType { type_name: String },
+
+    /// The producer is ahead of this consumer.
+    #[error("{type_name} v{version} is newer than supported v{current}")]
+    UnsupportedVersion {
+        type_name: String,
+        version: u32,
+        current: u32,
+    },
+
+    #[error("no upcaster for {type_name} v{version}")]
+    MissingUpcaster { type_name: String, version: u32 },
+
+    #[error("upcasting {type_name} v{version} failed: {reason}")]
+    Upcast {
+        type_name: String,
+        version: u32,
+        reason: String,
+    },
+}
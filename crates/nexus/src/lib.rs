@@ -1,14 +1,28 @@
+mod deadletter;
+mod dispatch;
 mod emitter;
 mod envelope;
 mod error;
 mod payload;
+mod pull;
+mod subject;
 mod transport;
+mod typed;
+mod upcast;
 
+pub use deadletter::{
+    DeadLetter, dead_letter_subject, domain_dead_letter_subject, max_deliveries_reason,
+};
+pub use dispatch::Sink;
 pub use emitter::{EventEmitter, dedup_id};
-pub use envelope::Envelope;
-pub use error::TransportError;
+pub use envelope::{Envelope, now_millis};
+pub use error::{PayloadError, TransportError};
 pub use payload::DomainPayload;
+pub use pull::MessageStream;
+pub use subject::{is_wildcard, subject_matches, validate_pattern};
 pub use transport::{
-    Message, MessageHandler, PublishOpts, StartPosition, StreamConfig, SubscribeOpts, Subscription,
-    Transport, TransportConfig,
+    ConsumerInfo, GroupInfo, Message, MessageHandler, PublishOpts, StartPosition, StreamConfig,
+    SubscribeOpts, Subscription, Transport, TransportConfig, validate_group,
 };
+pub use typed::{HandlerOutcome, TypedHandler, TypedMessageHandler};
+pub use upcast::{Upcaster, UpcasterRegistry};
@@ -1,6 +1,8 @@
 use bytes::Bytes;
 use serde::{Serialize, de::DeserializeOwned};
 
+use crate::envelope::now_millis;
+
 /// Enforced schema contract for all domain payloads on the transport.
 ///
 /// Wraps domain-specific data with required metadata fields.
@@ -25,12 +27,8 @@ impl<T: Serialize> DomainPayload<T> {
     ///
     /// # Panics
     /// Panics if the system clock is before the Unix epoch.
-    #[allow(clippy::cast_possible_truncation)] // millis since epoch fits in u64 until year 584556
     pub fn new(v: u32, id: impl Into<String>, data: T) -> Self {
-        let ts = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .expect("system clock before epoch")
-            .as_millis() as u64;
+        let ts = now_millis();
 
         Self {
             v,
@@ -0,0 +1,77 @@
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use futures_core::Stream;
+use tokio::sync::mpsc;
+
+use crate::error::TransportError;
+use crate::transport::{Message, Subscription};
+
+/// Pull-style consumer returned by `Transport::pull`.
+///
+/// Yields owned messages, through `recv`, `fetch` or as a `Stream`, that
+/// are settled like those passed to a `MessageHandler`. The backend reads
+/// ahead at most `SubscribeOpts::batch_size` messages and then waits for
+/// them to be taken, so delivery keeps pace with the caller's polling.
+/// Messages taken but never settled stay pending for the group.
+pub struct MessageStream {
+    rx: mpsc::Receiver<Box<dyn Message>>,
+    subscription: Box<dyn Subscription>,
+}
+
+impl MessageStream {
+    /// For backends: `rx` is fed by the consumer that `subscription` stops.
+    /// A consumer finding `rx` closed should nak the message it holds.
+    #[must_use]
+    pub fn new(rx: mpsc::Receiver<Box<dyn Message>>, subscription: Box<dyn Subscription>) -> Self {
+        Self { rx, subscription }
+    }
+
+    /// Next message, waiting as long as it takes. `None` once the stream
+    /// is closed.
+    pub async fn recv(&mut self) -> Option<Box<dyn Message>> {
+        self.rx.recv().await
+    }
+
+    /// Up to `max` messages, returning early once `timeout` has elapsed
+    /// with whatever has arrived (possibly none).
+    pub async fn fetch(&mut self, max: usize, timeout: Duration) -> Vec<Box<dyn Message>> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        let mut batch = Vec::with_capacity(max);
+        while batch.len() < max {
+            match tokio::time::timeout_at(deadline, self.rx.recv()).await {
+                Ok(Some(msg)) => batch.push(msg),
+                Ok(None) | Err(_) => break,
+            }
+        }
+        batch
+    }
+
+    /// Stop consuming and nak the messages read ahead but not yet taken,
+    /// so the group can redeliver them right away.
+    ///
+    /// # Errors
+    /// Returns an error if unsubscribing or a nak fails.
+    pub async fn close(&mut self) -> Result<(), TransportError> {
+        self.subscription.unsubscribe().await?;
+        self.rx.close();
+        while let Some(msg) = self.rx.recv().await {
+            msg.nak(None).await?;
+        }
+        Ok(())
+    }
+
+    #[must_use]
+    pub fn is_active(&self) -> bool {
+        self.subscription.is_active()
+    }
+}
+
+impl Stream for MessageStream {
+    type Item = Box<dyn Message>;
+
+    fn poll_next(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        self.rx.poll_recv(cx)
+    }
+}
@@ -0,0 +1,105 @@
+//! NATS-style subject wildcards.
+//!
+//! Subjects are dot-delimited tokens. In a subscription pattern, `*` matches
+//! exactly one token and `>` (last token only) matches one or more tokens:
+//!
+//! - `gbe.jobs.*.created` matches `gbe.jobs.etl.created`
+//! - `gbe.events.lifecycle.>` matches `gbe.events.lifecycle.operative.started`
+
+use crate::error::TransportError;
+
+/// Whether `subject` contains a `*` or `>` wildcard token.
+#[must_use]
+pub fn is_wildcard(subject: &str) -> bool {
+    subject.split('.').any(|t| t == "*" || t == ">")
+}
+
+/// Check a subject or pattern is well formed: no empty tokens, and `>` only
+/// as the last token.
+///
+/// # Errors
+/// Returns `TransportError::Subscribe` describing the problem.
+pub fn validate_pattern(pattern: &str) -> Result<(), TransportError> {
+    let tokens: Vec<&str> = pattern.split('.').collect();
+    if tokens.iter().any(|t| t.is_empty()) {
+        return Err(TransportError::Subscribe(format!(
+            "invalid subject {pattern:?}: empty token"
+        )));
+    }
+    if tokens[..tokens.len() - 1].contains(&">") {
+        return Err(TransportError::Subscribe(format!(
+            "invalid subject {pattern:?}: '>' must be the last token"
+        )));
+    }
+    Ok(())
+}
+
+/// Whether the literal `subject` matches `pattern`.
+#[must_use]
+pub fn subject_matches(pattern: &str, subject: &str) -> bool {
+    let mut subject_tokens = subject.split('.');
+    for p in pattern.split('.') {
+        match (p, subject_tokens.next()) {
+            (">", Some(_)) => return true,
+            (_, None) => return false,
+            ("*", Some(_)) => {}
+            (p, Some(s)) if p == s => {}
+            _ => return false,
+        }
+    }
+    subject_tokens.next().is_none()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_wildcards() {
+        assert!(is_wildcard("gbe.jobs.*.created"));
+        assert!(is_wildcard("gbe.events.>"));
+        assert!(!is_wildcard("gbe.tasks.email-send.queue"));
+        assert!(!is_wildcard("gbe.tasks.a*b.queue"));
+    }
+
+    #[test]
+    fn star_matches_one_token() {
+        assert!(subject_matches("gbe.jobs.*", "gbe.jobs.etl"));
+        assert!(subject_matches(
+            "gbe.events.lifecycle.*.*",
+            "gbe.events.lifecycle.operative.heartbeat"
+        ));
+        assert!(!subject_matches("gbe.jobs.*", "gbe.jobs"));
+        assert!(!subject_matches("gbe.jobs.*", "gbe.jobs.etl.created"));
+    }
+
+    #[test]
+    fn gt_matches_rest() {
+        assert!(subject_matches("gbe.events.>", "gbe.events.lifecycle"));
+        assert!(subject_matches(
+            "gbe.events.>",
+            "gbe.events.lifecycle.a.started"
+        ));
+        assert!(!subject_matches("gbe.events.>", "gbe.events"));
+        assert!(!subject_matches("gbe.events.>", "gbe.jobs.etl"));
+    }
+
+    #[test]
+    fn literal_matches_exactly() {
+        assert!(subject_matches("gbe.tasks.x.queue", "gbe.tasks.x.queue"));
+        assert!(!subject_matches(
+            "gbe.tasks.x.queue",
+            "gbe.tasks.x.queue.more"
+        ));
+        assert!(!subject_matches("gbe.tasks.x.queue", "gbe.tasks.y.queue"));
+    }
+
+    #[test]
+    fn validates_patterns() {
+        assert!(validate_pattern("gbe.events.lifecycle.*.*").is_ok());
+        assert!(validate_pattern("gbe.>").is_ok());
+        assert!(validate_pattern("gbe.>.started").is_err());
+        assert!(validate_pattern("gbe..jobs").is_err());
+        assert!(validate_pattern("").is_err());
+    }
+}
@@ -2,10 +2,16 @@ use async_trait::async_trait;
 use bytes::Bytes;
 use std::time::Duration;
 
+use crate::deadletter::DeadLetter;
 use crate::envelope::Envelope;
 use crate::error::TransportError;
+use crate::pull::MessageStream;
 
 /// Core transport trait. Created once, shared across the application.
+///
+/// Only publishing, subscribing, stream setup and trimming by age, `ping`
+/// and `close` are required. Every other method defaults to returning
+/// `TransportError::Other` for backends that do not support it.
 #[async_trait]
 pub trait Transport: Send + Sync {
     async fn publish(
@@ -15,6 +21,19 @@ pub trait Transport: Send + Sync {
         opts: Option<PublishOpts>,
     ) -> Result<String, TransportError>;
 
+    /// Append `envelope` to `envelope.subject` unchanged, keeping its
+    /// `message_id`, `timestamp` and `trace_id`. For restoring archived
+    /// messages; new messages go through `publish`.
+    async fn publish_envelope(&self, _envelope: Envelope) -> Result<String, TransportError> {
+        Err(unsupported())
+    }
+
+    /// Subscribe `group` to `subject`, which may be a wildcard pattern
+    /// (`*` = one token, `>` = the rest). A pattern consumes every matching
+    /// stream, including streams created after subscribing (read from their
+    /// start). `max_inflight` applies per matched stream, and
+    /// `StartPosition::Id` requires a literal subject. Group names starting
+    /// with `_` are reserved for backends; see `validate_group`.
     async fn subscribe(
         &self,
         subject: &str,
@@ -23,23 +42,170 @@ pub trait Transport: Send + Sync {
         opts: Option<SubscribeOpts>,
     ) -> Result<Box<dyn Subscription>, TransportError>;
 
+    /// Like `subscribe`, but the caller pulls messages from the returned
+    /// stream instead of handing the transport a handler. A handler error
+    /// has no counterpart here: settle every message explicitly.
+    async fn pull(
+        &self,
+        _subject: &str,
+        _group: &str,
+        _opts: Option<SubscribeOpts>,
+    ) -> Result<MessageStream, TransportError> {
+        Err(unsupported())
+    }
+
+    /// Create the stream if needed and record its retention limits in the
+    /// stream config registry. The limits are enforced on every publish.
     async fn ensure_stream(&self, config: StreamConfig) -> Result<(), TransportError>;
 
+    /// Limits recorded for `subject` by `ensure_stream`, if any.
+    async fn stream_config(&self, _subject: &str) -> Result<Option<StreamConfig>, TransportError> {
+        Err(unsupported())
+    }
+
+    /// Every stream recorded by `ensure_stream`, sorted by subject.
+    async fn stream_configs(&self) -> Result<Vec<StreamConfig>, TransportError> {
+        Err(unsupported())
+    }
+
     /// Trim entries older than `max_age` from the stream.
     /// Returns the number of entries removed. No-op for backends with native retention.
     async fn trim_stream(&self, subject: &str, max_age: Duration) -> Result<u64, TransportError>;
 
+    /// Trim entries published before `before_ms` (unix millis) from the stream.
+    /// Returns the number of entries removed.
+    async fn trim_stream_before(
+        &self,
+        _subject: &str,
+        _before_ms: u64,
+    ) -> Result<u64, TransportError> {
+        Err(unsupported())
+    }
+
+    /// Publish time (unix millis) of the oldest entry some consumer group has
+    /// not acknowledged yet, whether pending or not yet delivered. `None` when
+    /// every group is caught up or the stream has no groups.
+    async fn oldest_unacked(&self, _subject: &str) -> Result<Option<u64>, TransportError> {
+        Err(unsupported())
+    }
+
+    /// Where `group` stands on the stream for the literal `subject`. `None`
+    /// when the stream or the group does not exist.
+    async fn group_info(
+        &self,
+        _subject: &str,
+        _group: &str,
+    ) -> Result<Option<GroupInfo>, TransportError> {
+        Err(unsupported())
+    }
+
+    /// Consumer groups on the stream for the literal `subject`, sorted.
+    async fn list_groups(&self, _subject: &str) -> Result<Vec<String>, TransportError> {
+        Err(unsupported())
+    }
+
+    /// Delete `group` from the stream, dropping its pending messages.
+    /// Unsubscribe the group first. Returns whether the group existed.
+    async fn delete_group(&self, _subject: &str, _group: &str) -> Result<bool, TransportError> {
+        Err(unsupported())
+    }
+
+    /// Move `group` so it reads on from `position`, as a group created
+    /// there would. Messages already pending stay pending.
+    ///
+    /// Errors if the stream or the group does not exist.
+    async fn set_group_position(
+        &self,
+        _subject: &str,
+        _group: &str,
+        _position: StartPosition,
+    ) -> Result<(), TransportError> {
+        Err(unsupported())
+    }
+
+    /// Remove consumers of `group` idle for at least `min_idle` that hold
+    /// no pending messages; those that still do are left until their
+    /// messages are reclaimed. Returns the removed consumers' names, always
+    /// none for backends that do not track consumers by name.
+    async fn remove_idle_consumers(
+        &self,
+        _subject: &str,
+        _group: &str,
+        _min_idle: Duration,
+    ) -> Result<Vec<String>, TransportError> {
+        Err(unsupported())
+    }
+
+    /// List dead letters for `domain`, oldest first, up to `limit` entries.
+    async fn list_dead_letters(
+        &self,
+        _domain: &str,
+        _limit: Option<usize>,
+    ) -> Result<Vec<DeadLetter>, TransportError> {
+        Err(unsupported())
+    }
+
+    /// Republish dead letters to their original subject, then remove them.
+    /// `ids` selects entries by `DeadLetter::id`; `None` replays all of them.
+    /// Returns the number replayed.
+    async fn replay_dead_letters(
+        &self,
+        _domain: &str,
+        _ids: Option<&[String]>,
+    ) -> Result<u64, TransportError> {
+        Err(unsupported())
+    }
+
+    /// Remove dead letters without replaying them. `ids` selects entries by
+    /// `DeadLetter::id`; `None` purges the whole domain. Returns the number removed.
+    async fn purge_dead_letters(
+        &self,
+        _domain: &str,
+        _ids: Option<&[String]>,
+    ) -> Result<u64, TransportError> {
+        Err(unsupported())
+    }
+
     async fn ping(&self) -> Result<bool, TransportError>;
 
+    /// Stop every subscription, draining each as `Subscription::unsubscribe`
+    /// does, then reject further operations.
     async fn close(&self) -> Result<(), TransportError>;
 }
 
+fn unsupported() -> TransportError {
+    TransportError::Other("unsupported".to_string())
+}
+
+/// Check a consumer group name is usable: not empty, and not starting
+/// with `_`, which backends keep for groups of their own.
+///
+/// # Errors
+/// Returns `TransportError::Subscribe` describing the problem.
+pub fn validate_group(group: &str) -> Result<(), TransportError> {
+    if group.is_empty() {
+        return Err(TransportError::Subscribe("empty group name".to_string()));
+    }
+    if group.starts_with('_') {
+        return Err(TransportError::Subscribe(format!(
+            "invalid group {group:?}: names starting with '_' are reserved"
+        )));
+    }
+    Ok(())
+}
+
 /// What the message handler receives.
 #[async_trait]
 pub trait Message: Send + Sync {
     fn envelope(&self) -> &Envelope;
     fn payload(&self) -> &Bytes;
+    /// How many times this message has been delivered to the group,
+    /// counting this delivery (1 on first delivery).
+    fn delivery_count(&self) -> u32;
     async fn ack(&self) -> Result<(), TransportError>;
+    /// Return the message for redelivery. With `None` it is redelivered
+    /// right away; with `Some(delay)` it is held back until the delay has
+    /// elapsed. Either way, regardless of `ack_timeout`.
     async fn nak(&self, delay: Option<Duration>) -> Result<(), TransportError>;
     async fn dead_letter(&self, reason: &str) -> Result<(), TransportError>;
 }
@@ -53,8 +219,14 @@ pub trait MessageHandler: Send + Sync {
 /// Handle returned by subscribe.
 #[async_trait]
 pub trait Subscription: Send + Sync {
+    /// Stop fetching and wait for running handlers to finish, for up to
+    /// `SubscribeOpts::drain_timeout`. Handlers still running then are
+    /// aborted and their messages nak'd, so the group redelivers them.
     async fn unsubscribe(&self) -> Result<(), TransportError>;
     fn is_active(&self) -> bool;
+    /// Resolves once the subscription has stopped and drained, whether by
+    /// `unsubscribe`, `Transport::close` or its consumer giving up.
+    async fn finished(&self);
 }
 
 #[derive(Debug, Clone)]
@@ -75,7 +247,14 @@ impl Default for TransportConfig {
 #[derive(Debug, Clone, Default)]
 pub struct PublishOpts {
     pub trace_id: Option<String>,
+    /// Publishing the same key to the same subject again within the
+    /// backend's dedup window returns the original message ID instead of
+    /// appending a duplicate.
     pub idempotency_key: Option<String>,
+    /// Carried in `Envelope::ordering_key`. Subscriptions with
+    /// `SubscribeOpts::ordered` handle messages sharing a key one at a time,
+    /// in stream order.
+    pub ordering_key: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -84,6 +263,21 @@ pub struct SubscribeOpts {
     pub max_inflight: u32,
     pub ack_timeout: Duration,
     pub start_from: StartPosition,
+    /// Deliveries allowed per message before it is dead-lettered
+    /// automatically instead of being handed out again. `None` = unlimited.
+    pub max_deliveries: Option<u32>,
+    /// Handler invocations run at once, within `max_inflight`. With more
+    /// than one, messages may finish out of order. Ignored by `pull`.
+    pub concurrency: u32,
+    /// Handle messages with the same `Envelope::ordering_key` one at a time,
+    /// in stream order, while different keys use up to `concurrency` slots.
+    /// Holds within this subscription only, and a redelivered message
+    /// rejoins its key behind the messages already queued. Messages without
+    /// a key are unordered. Ignored by `pull`.
+    pub ordered: bool,
+    /// How long `unsubscribe` and `Transport::close` wait for running
+    /// handlers.
+    pub drain_timeout: Duration,
 }
 
 impl Default for SubscribeOpts {
@@ -93,6 +287,10 @@ impl Default for SubscribeOpts {
             max_inflight: 100,
             ack_timeout: Duration::from_secs(30),
             start_from: StartPosition::Latest,
+            max_deliveries: None,
+            concurrency: 1,
+            ordered: false,
+            drain_timeout: Duration::from_secs(30),
         }
     }
 }
@@ -105,10 +303,42 @@ pub enum StartPosition {
     Id(String),
 }
 
-#[derive(Debug, Clone)]
+/// Snapshot of one consumer group on one stream, from `Transport::group_info`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GroupInfo {
+    pub subject: String,
+    pub group: String,
+    /// Entries in the stream.
+    pub length: u64,
+    /// `Envelope::message_id` of the last entry delivered to the group.
+    /// `None` before the first delivery, or once that entry is trimmed.
+    pub last_delivered_id: Option<String>,
+    /// Delivered but not yet acknowledged.
+    pub pending: u64,
+    /// Entries not yet delivered to the group.
+    pub lag: u64,
+    /// Empty for backends that do not track consumers by name.
+    pub consumers: Vec<ConsumerInfo>,
+}
+
+/// One consumer in a `GroupInfo`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsumerInfo {
+    pub name: String,
+    /// Messages delivered to this consumer and not yet acknowledged.
+    pub pending: u64,
+    /// Time since the consumer last read or claimed a message.
+    pub idle: Duration,
+}
+
+/// Retention limits for one stream. Oldest messages are evicted first.
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct StreamConfig {
     pub subject: String,
+    /// Evict messages older than this. `Duration::ZERO` = no age limit.
     pub max_age: Duration,
+    /// Approximate size cap. Backends measure size their own way (payload
+    /// bytes in memory, stream memory usage in Redis).
     pub max_bytes: Option<u64>,
     pub max_msgs: Option<u64>,
 }
@@ -0,0 +1,264 @@
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use serde::de::DeserializeOwned;
+
+use crate::envelope::Envelope;
+use crate::error::TransportError;
+use crate::payload::DomainPayload;
+use crate::transport::{Message, MessageHandler};
+use crate::upcast::UpcasterRegistry;
+
+/// What a `TypedMessageHandler` decided about a message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HandlerOutcome {
+    Ack,
+    /// Return the message for redelivery, held back for the delay if given.
+    Nak(Option<Duration>),
+    /// Move the message to the dead-letter stream with this reason.
+    DeadLetter(String),
+}
+
+/// Handler for messages whose payload is a `DomainPayload<T>`.
+///
+/// Wrap it in a `TypedHandler` to subscribe with it.
+#[async_trait]
+pub trait TypedMessageHandler<T>: Send + Sync {
+    async fn handle(&self, payload: DomainPayload<T>, envelope: &Envelope) -> HandlerOutcome;
+}
+
+/// `MessageHandler` adapter that decodes each payload as a
+/// `DomainPayload<T>` before calling a `TypedMessageHandler<T>`, then acks,
+/// naks or dead-letters the message as the handler decided.
+///
+/// A payload that does not decode is dead-lettered with the serde error as
+/// the reason; the handler never sees it. With `with_upcasters`, older
+/// schema versions are upcast first, and a version newer than the registry
+/// knows is dead-lettered too, to be replayed once consumers catch up.
+pub struct TypedHandler<T, H> {
+    handler: H,
+    upcasters: Option<(Arc<UpcasterRegistry>, String)>,
+    _payload: PhantomData<fn() -> T>,
+}
+
+impl<T, H> TypedHandler<T, H>
+where
+    T: DeserializeOwned + Send + 'static,
+    H: TypedMessageHandler<T> + 'static,
+{
+    pub fn new(handler: H) -> Self {
+        Self {
+            handler,
+            upcasters: None,
+            _payload: PhantomData,
+        }
+    }
+
+    /// Decode payloads as `type_name` through `registry`.
+    #[must_use]
+    pub fn with_upcasters(
+        mut self,
+        registry: Arc<UpcasterRegistry>,
+        type_name: impl Into<String>,
+    ) -> Self {
+        self.upcasters = Some((registry, type_name.into()));
+        self
+    }
+
+    /// Boxed for `Transport::subscribe`.
+    #[must_use]
+    pub fn boxed(handler: H) -> Box<dyn MessageHandler> {
+        Box::new(Self::new(handler))
+    }
+}
+
+#[async_trait]
+impl<T, H> MessageHandler for TypedHandler<T, H>
+where
+    T: DeserializeOwned + Send + 'static,
+    H: TypedMessageHandler<T>,
+{
+    async fn handle(&self, msg: &dyn Message) -> Result<(), TransportError> {
+        let decoded = match &self.upcasters {
+            Some((registry, type_name)) => registry.decode(type_name, msg.payload()),
+            None => DomainPayload::<T>::from_bytes(msg.payload()).map_err(Into::into),
+        };
+        let payload = match decoded {
+            Ok(payload) => payload,
+            Err(e) => return msg.dead_letter(&format!("undecodable payload: {e}")).await,
+        };
+        match self.handler.handle(payload, msg.envelope()).await {
+            HandlerOutcome::Ack => msg.ack().await,
+            HandlerOutcome::Nak(delay) => msg.nak(delay).await,
+            HandlerOutcome::DeadLetter(reason) => msg.dead_letter(&reason).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytes::Bytes;
+    use std::sync::Mutex;
+
+    #[derive(Debug, Clone, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Order {
+        sku: String,
+        qty: u32,
+    }
+
+    /// Records what the adapter did with the message.
+    struct FakeMessage {
+        envelope: Envelope,
+        settled: Mutex<Vec<String>>,
+    }
+
+    impl FakeMessage {
+        fn new(payload: Bytes) -> Self {
+            Self {
+                envelope: Envelope::new("gbe.orders.created".to_string(), payload, None),
+                settled: Mutex::new(Vec::new()),
+            }
+        }
+
+        fn settled(&self) -> Vec<String> {
+            self.settled.lock().unwrap().clone()
+        }
+    }
+
+    #[async_trait]
+    impl Message for FakeMessage {
+        fn envelope(&self) -> &Envelope {
+            &self.envelope
+        }
+        fn payload(&self) -> &Bytes {
+            &self.envelope.payload
+        }
+        fn delivery_count(&self) -> u32 {
+            1
+        }
+        async fn ack(&self) -> Result<(), TransportError> {
+            self.settled.lock().unwrap().push("ack".to_string());
+            Ok(())
+        }
+        async fn nak(&self, delay: Option<Duration>) -> Result<(), TransportError> {
+            self.settled.lock().unwrap().push(format!("nak {delay:?}"));
+            Ok(())
+        }
+        async fn dead_letter(&self, reason: &str) -> Result<(), TransportError> {
+            self.settled
+                .lock()
+                .unwrap()
+                .push(format!("dead_letter {reason}"));
+            Ok(())
+        }
+    }
+
+    /// Acks small orders, delays large ones and rejects empty ones.
+    struct OrderHandler {
+        seen: Mutex<Vec<(Order, String)>>,
+    }
+
+    #[async_trait]
+    impl TypedMessageHandler<Order> for OrderHandler {
+        async fn handle(
+            &self,
+            payload: DomainPayload<Order>,
+            envelope: &Envelope,
+        ) -> HandlerOutcome {
+            let order = payload.data;
+            self.seen
+                .lock()
+                .unwrap()
+                .push((order.clone(), envelope.subject.clone()));
+            match order.qty {
+                0 => HandlerOutcome::DeadLetter("empty order".to_string()),
+                1..=10 => HandlerOutcome::Ack,
+                _ => HandlerOutcome::Nak(Some(Duration::from_secs(5))),
+            }
+        }
+    }
+
+    fn order(qty: u32) -> Bytes {
+        DomainPayload::new(
+            1,
+            format!("order-{qty}"),
+            Order {
+                sku: "widget".to_string(),
+                qty,
+            },
+        )
+        .to_bytes()
+        .unwrap()
+    }
+
+    async fn deliver(payload: Bytes) -> (Vec<String>, Vec<(Order, String)>) {
+        let handler = TypedHandler::new(OrderHandler {
+            seen: Mutex::new(Vec::new()),
+        });
+        let msg = FakeMessage::new(payload);
+        handler.handle(&msg).await.unwrap();
+        let seen = handler.handler.seen.lock().unwrap().clone();
+        (msg.settled(), seen)
+    }
+
+    #[tokio::test]
+    async fn outcomes_map_to_ack_nak_and_dead_letter() {
+        let (settled, seen) = deliver(order(3)).await;
+        assert_eq!(settled, vec!["ack"]);
+        assert_eq!(seen[0].0.qty, 3);
+        assert_eq!(seen[0].1, "gbe.orders.created");
+
+        let (settled, _) = deliver(order(50)).await;
+        assert_eq!(settled, vec!["nak Some(5s)"]);
+
+        let (settled, _) = deliver(order(0)).await;
+        assert_eq!(settled, vec!["dead_letter empty order"]);
+    }
+
+    #[tokio::test]
+    async fn upcasters_decode_older_versions_and_reject_newer() {
+        let registry = Arc::new(UpcasterRegistry::new().with_type("order", 2).with_upcaster(
+            "order",
+            1,
+            |mut data| {
+                let count = data["count"].take();
+                data["qty"] = count;
+                Ok(data)
+            },
+        ));
+        let handler = TypedHandler::new(OrderHandler {
+            seen: Mutex::new(Vec::new()),
+        })
+        .with_upcasters(registry, "order");
+
+        let v1 = DomainPayload::new(
+            1,
+            "order-1",
+            serde_json::json!({"sku": "widget", "count": 2}),
+        );
+        let msg = FakeMessage::new(v1.to_bytes().unwrap());
+        handler.handle(&msg).await.unwrap();
+        assert_eq!(msg.settled(), vec!["ack"]);
+        assert_eq!(handler.handler.seen.lock().unwrap()[0].0.qty, 2);
+
+        let v3 = DomainPayload::new(3, "order-3", serde_json::json!({"sku": "widget", "qty": 2}));
+        let msg3 = FakeMessage::new(v3.to_bytes().unwrap());
+        handler.handle(&msg3).await.unwrap();
+        assert_eq!(
+            msg3.settled(),
+            vec!["dead_letter undecodable payload: order v3 is newer than supported v2"]
+        );
+    }
+
+    #[tokio::test]
+    async fn undecodable_payload_is_dead_lettered_without_calling_handler() {
+        let (settled, seen) =
+            deliver(Bytes::from(r#"{"v":1,"ts":0,"id":"x","data":{"sku":7}}"#)).await;
+        assert!(seen.is_empty());
+        assert_eq!(settled.len(), 1);
+        assert!(settled[0].starts_with("dead_letter undecodable payload: invalid type"));
+    }
+}
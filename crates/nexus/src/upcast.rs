@@ -0,0 +1,252 @@
+use std::collections::HashMap;
+
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+use crate::error::PayloadError;
+use crate::payload::DomainPayload;
+
+/// Rewrites the `data` of one schema version into the shape of the next.
+pub type Upcaster = Box<dyn Fn(Value) -> Result<Value, String> + Send + Sync>;
+
+struct Schema {
+    current: u32,
+    /// Keyed by the version each upcaster reads.
+    upcasters: HashMap<u32, Upcaster>,
+}
+
+/// Upcasters per payload type, keyed by the schema version they read.
+///
+/// `decode` brings an older `DomainPayload.data` up to the current version
+/// one step at a time (v1 → v2 → v3) before deserializing it, so producers
+/// can move to a new schema before or after their consumers do. A version
+/// newer than the consumer's current one is `PayloadError::UnsupportedVersion`.
+///
+/// ```
+/// use gbe_nexus::UpcasterRegistry;
+///
+/// let registry = UpcasterRegistry::new()
+///     .with_type("order", 2)
+///     .with_upcaster("order", 1, |mut data| {
+///         // v2 renamed `count` to `qty`.
+///         let count = data["count"].take();
+///         data["qty"] = count;
+///         Ok(data)
+///     });
+/// ```
+#[derive(Default)]
+pub struct UpcasterRegistry {
+    schemas: HashMap<String, Schema>,
+}
+
+impl UpcasterRegistry {
+    #[must_use]
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare `current` as the newest version of `type_name` this consumer
+    /// understands.
+    #[must_use]
+    pub fn with_type(mut self, type_name: impl Into<String>, current: u32) -> Self {
+        self.schemas
+            .entry(type_name.into())
+            .or_insert_with(|| Schema {
+                current,
+                upcasters: HashMap::new(),
+            })
+            .current = current;
+        self
+    }
+
+    /// Register the upcaster from version `from` of `type_name` to
+    /// `from + 1`. The type must be declared with `with_type` first.
+    ///
+    /// # Panics
+    /// Panics if `type_name` has not been declared.
+    #[must_use]
+    pub fn with_upcaster<F>(mut self, type_name: &str, from: u32, upcaster: F) -> Self
+    where
+        F: Fn(Value) -> Result<Value, String> + Send + Sync + 'static,
+    {
+        self.schemas
+            .get_mut(type_name)
+            .unwrap_or_else(|| panic!("upcaster registered for undeclared type {type_name}"))
+            .upcasters
+            .insert(from, Box::new(upcaster));
+        self
+    }
+
+    /// Current version of `type_name`, if declared.
+    #[must_use]
+    pub fn current_version(&self, type_name: &str) -> Option<u32> {
+        self.schemas.get(type_name).map(|s| s.current)
+    }
+
+    /// Bring `data` at `version` up to the current version of `type_name`.
+    ///
+    /// # Errors
+    /// Returns `UnsupportedVersion` for a version newer than the current
+    /// one, `UnknownType` or `MissingUpcaster` when there is no path to the
+    /// current version, and `Upcast` when an upcaster rejects the data.
+    pub fn upcast(
+        &self,
+        type_name: &str,
+        version: u32,
+        mut data: Value,
+    ) -> Result<Value, PayloadError> {
+        let schema = self
+            .schemas
+            .get(type_name)
+            .ok_or_else(|| PayloadError::UnknownType {
+                type_name: type_name.to_string(),
+            })?;
+        if version > schema.current {
+            return Err(PayloadError::UnsupportedVersion {
+                type_name: type_name.to_string(),
+                version,
+                current: schema.current,
+            });
+        }
+        for v in version..schema.current {
+            let upcaster =
+                schema
+                    .upcasters
+                    .get(&v)
+                    .ok_or_else(|| PayloadError::MissingUpcaster {
+                        type_name: type_name.to_string(),
+                        version: v,
+                    })?;
+            data = upcaster(data).map_err(|reason| PayloadError::Upcast {
+                type_name: type_name.to_string(),
+                version: v,
+                reason,
+            })?;
+        }
+        Ok(data)
+    }
+
+    /// Deserialize a `DomainPayload<T>` of `type_name` from transport bytes,
+    /// upcasting its `data` first. The result's `v` is the current version.
+    ///
+    /// # Errors
+    /// Returns the errors of `upcast`, or `Decode` if the bytes or the
+    /// upcasted data do not deserialize.
+    pub fn decode<T: DeserializeOwned>(
+        &self,
+        type_name: &str,
+        bytes: &[u8],
+    ) -> Result<DomainPayload<T>, PayloadError> {
+        let raw = DomainPayload::<Value>::from_bytes(bytes)?;
+        let data = self.upcast(type_name, raw.v, raw.data)?;
+        Ok(DomainPayload {
+            v: self.current_version(type_name).unwrap_or(raw.v),
+            ts: raw.ts,
+            id: raw.id,
+            data: serde_json::from_value(data)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[derive(Debug, PartialEq, serde::Deserialize)]
+    struct Order {
+        sku: String,
+        qty: u32,
+        priority: String,
+    }
+
+    /// v1 `{sku, count}` → v2 `{sku, qty}` → v3 `{sku, qty, priority}`.
+    fn registry() -> UpcasterRegistry {
+        UpcasterRegistry::new()
+            .with_type("order", 3)
+            .with_upcaster("order", 1, |mut data| {
+                let count = data["count"].take();
+                data["qty"] = count;
+                Ok(data)
+            })
+            .with_upcaster("order", 2, |mut data| {
+                data["priority"] = json!("normal");
+                Ok(data)
+            })
+    }
+
+    fn bytes(v: u32, data: &Value) -> Vec<u8> {
+        serde_json::to_vec(&DomainPayload::new(v, "order-1", data.clone())).unwrap()
+    }
+
+    #[test]
+    fn older_versions_are_upcast_to_current() {
+        let registry = registry();
+        let expected = Order {
+            sku: "widget".into(),
+            qty: 4,
+            priority: "normal".into(),
+        };
+
+        let v1 = bytes(1, &json!({"sku": "widget", "count": 4}));
+        let decoded: DomainPayload<Order> = registry.decode("order", &v1).unwrap();
+        assert_eq!(decoded.v, 3);
+        assert_eq!(decoded.id, "order-1");
+        assert_eq!(decoded.data, expected);
+
+        let v2 = bytes(2, &json!({"sku": "widget", "qty": 4}));
+        let decoded: DomainPayload<Order> = registry.decode("order", &v2).unwrap();
+        assert_eq!(decoded.data, expected);
+
+        let v3 = bytes(3, &json!({"sku": "widget", "qty": 4, "priority": "normal"}));
+        let decoded: DomainPayload<Order> = registry.decode("order", &v3).unwrap();
+        assert_eq!(decoded.data, expected);
+    }
+
+    #[test]
+    fn newer_version_is_unsupported() {
+        let v4 = bytes(4, &json!({"sku": "widget", "qty": 4, "priority": "high"}));
+        let err = registry().decode::<Order>("order", &v4).unwrap_err();
+        assert!(matches!(
+            err,
+            PayloadError::UnsupportedVersion {
+                version: 4,
+                current: 3,
+                ..
+            }
+        ));
+    }
+
+    #[test]
+    fn gaps_and_failures_are_reported() {
+        let registry = registry()
+            .with_type("refund", 2)
+            .with_type("invoice", 2)
+            .with_upcaster("invoice", 1, |_| Err("missing total".to_string()));
+
+        let err = registry.upcast("refund", 1, json!({})).unwrap_err();
+        assert!(matches!(
+            err,
+            PayloadError::MissingUpcaster { version: 1, .. }
+        ));
+
+        let err = registry.upcast("invoice", 1, json!({})).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "upcasting invoice v1 failed: missing total"
+        );
+
+        let err = registry.upcast("shipment", 1, json!({})).unwrap_err();
+        assert!(matches!(err, PayloadError::UnknownType { .. }));
+    }
+
+    #[test]
+    fn undecodable_data_after_upcast_is_a_decode_error() {
+        let v3 = bytes(
+            3,
+            &json!({"sku": "widget", "qty": "four", "priority": "normal"}),
+        );
+        let err = registry().decode::<Order>("order", &v3).unwrap_err();
+        assert!(matches!(err, PayloadError::Decode(_)));
+    }
+}
@@ -0,0 +1,25 @@
+use thiserror::Error;
+
+use gbe_jobs::JobsError;
+use gbe_jobs_domain::TaskId;
+use gbe_nexus::TransportError;
+use gbe_state_store::StateStoreError;
+
+#[derive(Debug, Error)]
+pub enum OperativeError {
+    #[error("transport error: {0}")]
+    Transport(#[from] TransportError),
+
+    #[error("state store error: {0}")]
+    State(#[from] StateStoreError),
+
+    #[error("jobs error: {0}")]
+    Jobs(#[from] JobsError),
+
+    #[error("serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+
+    /// The task's claim was taken over, e.g. by the watcher after a timeout.
+    #[error("lost the claim on task {0}")]
+    ClaimLost(TaskId),
+}
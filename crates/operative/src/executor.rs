@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use tokio::sync::Mutex;
+use tokio_util::sync::CancellationToken;
+
+use gbe_jobs::TaskRecord;
+use gbe_jobs_domain::keys::fields;
+use gbe_jobs_domain::payloads::{TaskProgress, TaskQueued};
+use gbe_jobs_domain::subjects::tasks;
+use gbe_jobs_domain::{TaskOutcome, TaskState};
+use gbe_nexus::{EventEmitter, now_millis};
+use gbe_state_store::StateStore;
+
+use crate::error::OperativeError;
+
+/// The work behind one task type.
+///
+/// The runtime claims the task before calling `execute` and records the
+/// returned outcome; a `Failed` outcome is retried while the task has
+/// retries left. Long-running work should report through `ctx.progress`
+/// (or call `ctx.extend_timeout`) more often than the task's timeout, and
+/// stop early once `ctx.is_cancelled()`.
+#[async_trait]
+pub trait TaskExecutor: Send + Sync {
+    async fn execute(&self, task: &TaskQueued, ctx: &TaskContext) -> TaskOutcome;
+}
+
+/// Handle on the claimed task given to a `TaskExecutor`.
+pub struct TaskContext {
+    task: TaskRecord,
+    store: Arc<dyn StateStore>,
+    emitter: Arc<EventEmitter>,
+    /// `timeout_at` as last written by this worker.
+    timeout_at: Mutex<u64>,
+    token: CancellationToken,
+}
+
+impl TaskContext {
+    pub(crate) fn new(
+        task: TaskRecord,
+        store: Arc<dyn StateStore>,
+        emitter: Arc<EventEmitter>,
+        timeout_at: u64,
+        token: CancellationToken,
+    ) -> Self {
+        Self {
+            task,
+            store,
+            emitter,
+            timeout_at: Mutex::new(timeout_at),
+            token,
+        }
+    }
+
+    /// The task record as claimed.
+    #[must_use]
+    pub fn task(&self) -> &TaskRecord {
+        &self.task
+    }
+
+    /// True once the worker is shutting down or has lost the claim.
+    #[must_use]
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    /// Resolves once the worker is shutting down or has lost the claim.
+    pub async fn cancelled(&self) {
+        self.token.cancelled().await;
+    }
+
+    /// Report a step: extends the claim, records the step on the task and
+    /// publishes `TaskProgress` on `gbe.tasks.{type}.progress`.
+    ///
+    /// # Errors
+    /// Returns `OperativeError::ClaimLost` if the task was taken over, or an
+    /// error if the record cannot be updated or the event published.
+    pub async fn progress(
+        &self,
+        current_step: u32,
+        step_count: Option<u32>,
+        message: Option<String>,
+    ) -> Result<(), OperativeError> {
+        self.extend_timeout().await?;
+
+        let mut updates = HashMap::from([(
+            fields::task::CURRENT_STEP.to_string(),
+            Bytes::from(current_step.to_string()),
+        )]);
+        if let Some(step_count) = step_count {
+            updates.insert(
+                fields::task::STEP_COUNT.to_string(),
+                Bytes::from(step_count.to_string()),
+            );
+        }
+        self.store.set_fields(&self.task.key(), updates).await?;
+
+        self.emitter
+            .emit_traced(
+                &tasks::progress(self.task.task_type.as_str()),
+                1,
+                format!(
+                    "{}-progress-{}-{current_step}",
+                    self.task.task_id, self.task.retry_count
+                ),
+                TaskProgress {
+                    task_id: self.task.task_id.clone(),
+                    job_id: self.task.job_id.clone(),
+                    current_step,
+                    step_count,
+                    message,
+                },
+                self.task.job_id.as_str(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    /// Push the claim's `timeout_at` to `timeout_secs` from now, keeping the
+    /// watcher away. Returns the new deadline (unix millis).
+    ///
+    /// The task must still be `running` and the write is a
+    /// `compare_and_swap` from the value this worker last wrote, so a claim
+    /// that was taken over is never extended; the context is cancelled
+    /// instead.
+    ///
+    /// # Errors
+    /// Returns `OperativeError::ClaimLost` if the task was taken over, or an
+    /// error if the record cannot be updated.
+    pub async fn extend_timeout(&self) -> Result<u64, OperativeError> {
+        let key = self.task.key();
+        let mut timeout_at = self.timeout_at.lock().await;
+        let state = self.store.get_field(&key, fields::task::STATE).await?;
+        let claimed = state.as_deref() == Some(TaskState::Running.as_str().as_bytes());
+        let next = now_millis() + self.task.timeout_secs * 1000;
+        if claimed && next <= *timeout_at {
+            return Ok(*timeout_at);
+        }
+        let swapped = claimed
+            && self
+                .store
+                .compare_and_swap(
+                    &key,
+                    fields::task::TIMEOUT_AT,
+                    Bytes::from(timeout_at.to_string()),
+                    Bytes::from(next.to_string()),
+                )
+                .await?;
+        if !swapped {
+            self.token.cancel();
+            return Err(OperativeError::ClaimLost(self.task.task_id.clone()));
+        }
+        *timeout_at = next;
+        Ok(next)
+    }
+}
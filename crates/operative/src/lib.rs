@@ -0,0 +1,16 @@
+//! Task worker runtime.
+//!
+//! A team implements `TaskExecutor` for its task type and hands it to an
+//! `Operative`, which claims queued tasks, runs them, reports progress,
+//! extends the claim's timeout, records the outcome on the task record and
+//! its terminal stream, acks or naks the queue message, and shuts down
+//! gracefully. State changes follow the same `compare_and_swap` rules as
+//! the driver and watcher in `gbe-jobs`.
+
+mod error;
+mod executor;
+mod operative;
+
+pub use error::OperativeError;
+pub use executor::{TaskContext, TaskExecutor};
+pub use operative::{OPERATIVE_COMPONENT, Operative, OperativeConfig};
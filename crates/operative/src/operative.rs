@@ -0,0 +1,416 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use tokio::sync::{Mutex, watch};
+use tokio::task::JoinSet;
+use tokio_util::sync::CancellationToken;
+
+use gbe_jobs::record::{claim_attempt, settle_attempt, transition_task};
+use gbe_jobs::{TaskRecord, emit_failed, fail_attempt};
+use gbe_jobs_domain::keys::fields;
+use gbe_jobs_domain::payloads::{TaskCompleted, TaskQueued};
+use gbe_jobs_domain::subjects::tasks;
+use gbe_jobs_domain::{TaskId, TaskOutcome, TaskState, TaskType};
+use gbe_nexus::{
+    DomainPayload, Envelope, EventEmitter, HandlerOutcome, StartPosition, SubscribeOpts,
+    Subscription, Transport, TypedHandler, TypedMessageHandler, now_millis,
+};
+use gbe_state_store::StateStore;
+
+use crate::error::OperativeError;
+use crate::executor::{TaskContext, TaskExecutor};
+
+/// Component name used in lifecycle events.
+pub const OPERATIVE_COMPONENT: &str = "operative";
+
+/// Configuration for an `Operative`.
+pub struct OperativeConfig {
+    /// Consumer group reading the queue streams, shared by every worker.
+    pub group: String,
+    /// Instance ID recorded as the task's `worker` and in emitted events.
+    pub instance_id: String,
+    /// Redelivery delay for a queued task that could not be claimed
+    /// because the state store failed.
+    pub nak_delay: Duration,
+    /// How long `shutdown` waits for running tasks before releasing them.
+    pub shutdown_timeout: Duration,
+    /// Tasks of one type this worker runs at once.
+    pub concurrency: u32,
+}
+
+impl Default for OperativeConfig {
+    fn default() -> Self {
+        Self {
+            group: OPERATIVE_COMPONENT.to_string(),
+            instance_id: format!("opr-{}", ulid::Ulid::new().to_string().to_lowercase()),
+            nak_delay: Duration::from_secs(1),
+            shutdown_timeout: Duration::from_secs(30),
+            concurrency: 1,
+        }
+    }
+}
+
+/// Worker runtime: consumes `gbe.tasks.{type}.queue` and runs each task
+/// through the `TaskExecutor` registered for its type.
+///
+/// For every queued task it:
+/// - claims it, `pending` → `claimed` → `running` by compare-and-swap,
+///   recording itself as `worker` and a `timeout_at` of `timeout_secs` from
+///   now along with the claim; a stale or already claimed task is acked and
+///   dropped,
+/// - runs the executor, whose `TaskContext` reports progress and extends
+///   the timeout,
+/// - records the outcome: `completed` with `TaskCompleted`, or a failed
+///   attempt through `fail_attempt`, requeued while retries remain,
+/// - acks the message once the outcome is recorded. A redelivered message
+///   for a task already settled sends its terminal event again.
+///
+/// A task whose worker dies stays claimed until the watcher times it out.
+pub struct Operative {
+    shared: Arc<Shared>,
+    subscriptions: Mutex<Vec<Box<dyn Subscription>>>,
+}
+
+struct Shared {
+    store: Arc<dyn StateStore>,
+    emitter: Arc<EventEmitter>,
+    config: OperativeConfig,
+    shutdown: CancellationToken,
+    /// Tasks currently executing, by ID.
+    running: watch::Sender<HashMap<TaskId, TaskRecord>>,
+}
+
+impl Operative {
+    pub fn new(
+        transport: Arc<dyn Transport>,
+        store: Arc<dyn StateStore>,
+        config: OperativeConfig,
+    ) -> Self {
+        let emitter = EventEmitter::new(transport, OPERATIVE_COMPONENT, config.instance_id.clone());
+        Self {
+            shared: Arc::new(Shared {
+                store,
+                emitter: Arc::new(emitter),
+                config,
+                shutdown: CancellationToken::new(),
+                running: watch::Sender::new(HashMap::new()),
+            }),
+            subscriptions: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Start executing tasks of `task_type` with `executor`.
+    ///
+    /// # Errors
+    /// Returns an error if the subscription fails.
+    pub async fn start(
+        &self,
+        task_type: &TaskType,
+        executor: Arc<dyn TaskExecutor>,
+    ) -> Result<(), OperativeError> {
+        let subscription = self
+            .shared
+            .emitter
+            .transport()
+            .subscribe(
+                &tasks::queue(task_type.as_str()),
+                &self.shared.config.group,
+                TypedHandler::boxed(QueueHandler {
+                    shared: self.shared.clone(),
+                    executor,
+                }),
+                // Tasks queued before the group existed still need a worker.
+                Some(SubscribeOpts {
+                    start_from: StartPosition::Earliest,
+                    concurrency: self.shared.config.concurrency,
+                    drain_timeout: self.shared.config.shutdown_timeout,
+                    ..Default::default()
+                }),
+            )
+            .await?;
+        self.subscriptions.lock().await.push(subscription);
+        Ok(())
+    }
+
+    /// IDs of the tasks currently executing.
+    pub fn running(&self) -> Vec<TaskId> {
+        self.shared.running.borrow().keys().cloned().collect()
+    }
+
+    /// Stop taking tasks and wait up to `shutdown_timeout` for running ones
+    /// to finish; their contexts are cancelled so executors can wrap up.
+    ///
+    /// Tasks still running at the deadline are released as a failed
+    /// attempt (requeued while retries remain) instead of waiting for the
+    /// watcher; their outcome is dropped if they finish later. Returns the
+    /// released task IDs.
+    ///
+    /// # Errors
+    /// Returns an error if a subscription cannot be closed.
+    pub async fn shutdown(&self) -> Result<Vec<TaskId>, OperativeError> {
+        self.shared.shutdown.cancel();
+        // Each subscription drains for up to shutdown_timeout; run them
+        // side by side so the deadline holds across task types.
+        let mut drains = JoinSet::new();
+        for subscription in self.subscriptions.lock().await.drain(..) {
+            drains.spawn(async move { subscription.unsubscribe().await });
+        }
+        let mut result = Ok(());
+        while let Some(drained) = drains.join_next().await {
+            if let Ok(Err(e)) = drained {
+                result = Err(e);
+            }
+        }
+        result?;
+
+        // Whatever is still running had its handler aborted by the drain.
+        let left: Vec<TaskRecord> = self.shared.running.borrow().values().cloned().collect();
+        let mut released = Vec::with_capacity(left.len());
+        for task in left {
+            let error = format!(
+                "worker {} shut down before the task finished",
+                self.shared.config.instance_id
+            );
+            match fail_attempt(
+                &self.shared.store,
+                &self.shared.emitter,
+                &task,
+                &error,
+                None,
+            )
+            .await
+            {
+                Ok(Some(_)) => released.push(task.task_id),
+                Ok(None) => {}
+                Err(e) => tracing::warn!(task_id = %task.task_id, "releasing task failed: {e}"),
+            }
+        }
+        Ok(released)
+    }
+}
+
+#[derive(Clone)]
+struct QueueHandler {
+    shared: Arc<Shared>,
+    executor: Arc<dyn TaskExecutor>,
+}
+
+#[async_trait]
+impl TypedMessageHandler<TaskQueued> for QueueHandler {
+    async fn handle(
+        &self,
+        payload: DomainPayload<TaskQueued>,
+        _envelope: &Envelope,
+    ) -> HandlerOutcome {
+        let queued = payload.data;
+        if self.shared.shutdown.is_cancelled() {
+            return HandlerOutcome::Nak(None);
+        }
+
+        let (task, timeout_at) = match self.shared.claim(&queued).await {
+            Ok(Some(claimed)) => claimed,
+            Ok(None) => return HandlerOutcome::Ack,
+            Err(e) => {
+                tracing::warn!(task_id = %queued.task_id, "claiming task failed: {e}");
+                return HandlerOutcome::Nak(Some(self.shared.config.nak_delay));
+            }
+        };
+
+        // On its own task so a drain deadline aborting this handler cannot
+        // cut the outcome short; shutdown releases the task meanwhile, and
+        // a late outcome is dropped with the lost claim.
+        let handler = self.clone();
+        let task_id = task.task_id.clone();
+        let settled =
+            tokio::spawn(async move { handler.run(&queued, &task, timeout_at).await }).await;
+        // Acked only once the outcome is recorded: a redelivery finds the
+        // task completed and sends its terminal event again. If this worker
+        // dies first, redeliveries find the task claimed and are dropped;
+        // the watcher recovers it.
+        match settled {
+            Ok(Ok(())) => HandlerOutcome::Ack,
+            Ok(Err(e)) => {
+                tracing::warn!(task_id = %task_id, "recording task outcome failed: {e}");
+                HandlerOutcome::Nak(Some(self.shared.config.nak_delay))
+            }
+            Err(e) => {
+                tracing::warn!(task_id = %task_id, "recording task outcome panicked: {e}");
+                HandlerOutcome::Nak(Some(self.shared.config.nak_delay))
+            }
+        }
+    }
+}
+
+impl QueueHandler {
+    async fn run(
+        &self,
+        queued: &TaskQueued,
+        task: &TaskRecord,
+        timeout_at: u64,
+    ) -> Result<(), OperativeError> {
+        self.shared
+            .running
+            .send_modify(|running| drop(running.insert(task.task_id.clone(), task.clone())));
+        let outcome = self.execute(queued, task, timeout_at).await;
+        let settled = self.shared.settle(task, &outcome).await;
+        self.shared
+            .running
+            .send_modify(|running| drop(running.remove(&task.task_id)));
+        settled
+    }
+
+    /// Run the executor on its own task so a panic fails the attempt
+    /// instead of the consumer.
+    async fn execute(
+        &self,
+        queued: &TaskQueued,
+        task: &TaskRecord,
+        timeout_at: u64,
+    ) -> TaskOutcome {
+        let ctx = TaskContext::new(
+            task.clone(),
+            self.shared.store.clone(),
+            self.shared.emitter.clone(),
+            timeout_at,
+            self.shared.shutdown.child_token(),
+        );
+        let executor = self.executor.clone();
+        let queued = queued.clone();
+        match tokio::spawn(async move { executor.execute(&queued, &ctx).await }).await {
+            Ok(outcome) => outcome,
+            Err(e) => TaskOutcome::Failed {
+                exit_code: -1,
+                error: format!("executor panicked: {e}"),
+            },
+        }
+    }
+}
+
+impl Shared {
+    /// Claim a queued task. Returns the running task and its `timeout_at`,
+    /// or `None` if the message is stale or another worker got there first.
+    async fn claim(
+        &self,
+        queued: &TaskQueued,
+    ) -> Result<Option<(TaskRecord, u64)>, OperativeError> {
+        let Some(task) = TaskRecord::load(&self.store, &queued.task_type, &queued.task_id).await?
+        else {
+            tracing::warn!(task_id = %queued.task_id, "dropping queued task without a record");
+            return Ok(None);
+        };
+        // Redelivered because the terminal event could not be sent; the
+        // idempotency key makes the repeat a no-op otherwise.
+        match task.state {
+            TaskState::Completed => {
+                if let Some(TaskOutcome::Completed { result_ref, .. }) = &task.outcome {
+                    self.emit_completed(&task, task.updated_at, result_ref.clone())
+                        .await?;
+                }
+                return Ok(None);
+            }
+            TaskState::Failed if task.retry_count == queued.retry_count => {
+                let error = task.error.as_deref().unwrap_or_default();
+                emit_failed(&self.emitter, &task, error, task.updated_at).await?;
+                return Ok(None);
+            }
+            _ => {}
+        }
+        // A requeue bumps retry_count, so an older message is a duplicate.
+        if task.state != TaskState::Pending || task.retry_count != queued.retry_count {
+            tracing::debug!(task_id = %task.task_id, state = %task.state, "dropping stale queued task");
+            return Ok(None);
+        }
+
+        let now = now_millis();
+        let timeout_at = now + task.timeout_secs * 1000;
+        if !claim_attempt(
+            &self.store,
+            &task,
+            &self.config.instance_id,
+            timeout_at,
+            now,
+        )
+        .await?
+        {
+            return Ok(None);
+        }
+        if !transition_task(
+            &self.store,
+            &task.key(),
+            TaskState::Claimed,
+            TaskState::Running,
+            now,
+        )
+        .await?
+        {
+            return Ok(None);
+        }
+
+        Ok(Some((
+            TaskRecord {
+                state: TaskState::Running,
+                timeout_at: Some(timeout_at),
+                worker: Some(self.config.instance_id.clone()),
+                ..task
+            },
+            timeout_at,
+        )))
+    }
+
+    /// Record the outcome of a running task, unless its claim was lost.
+    async fn settle(&self, task: &TaskRecord, outcome: &TaskOutcome) -> Result<(), OperativeError> {
+        let result_ref = match outcome {
+            TaskOutcome::Completed { result_ref, .. } => result_ref.clone(),
+            TaskOutcome::Failed { error, .. } => {
+                if fail_attempt(&self.store, &self.emitter, task, error, Some(outcome))
+                    .await?
+                    .is_none()
+                {
+                    tracing::warn!(task_id = %task.task_id, "claim lost; dropping failed attempt");
+                }
+                return Ok(());
+            }
+        };
+
+        // The outcome is written with the state: once the task reads as
+        // completed, the driver may resolve a dependant's inputs from it.
+        let now = now_millis();
+        let updates = HashMap::from([(
+            fields::task::OUTCOME.to_string(),
+            Bytes::from(serde_json::to_vec(outcome)?),
+        )]);
+        if !settle_attempt(&self.store, task, TaskState::Completed, now, updates).await? {
+            tracing::warn!(task_id = %task.task_id, "claim lost; dropping completed outcome");
+            return Ok(());
+        }
+        self.emit_completed(task, now, result_ref).await
+    }
+
+    /// Send the terminal event of a completed task.
+    async fn emit_completed(
+        &self,
+        task: &TaskRecord,
+        completed_at: u64,
+        result_ref: Option<String>,
+    ) -> Result<(), OperativeError> {
+        self.emitter
+            .emit_traced(
+                &tasks::terminal(task.task_type.as_str()),
+                1,
+                format!("{}-completed", task.task_id),
+                TaskCompleted {
+                    task_id: task.task_id.clone(),
+                    job_id: task.job_id.clone(),
+                    task_type: task.task_type.clone(),
+                    completed_at,
+                    result_ref,
+                },
+                task.job_id.as_str(),
+            )
+            .await?;
+        Ok(())
+    }
+}
@@ -0,0 +1,523 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time::Duration;
+use tokio::sync::{Notify, mpsc};
+
+use gbe_jobs::{DriverConfig, JobDriver, TaskRecord};
+use gbe_jobs_domain::keys::fields;
+use gbe_jobs_domain::payloads::{JobCompleted, JobFailed, TaskCompleted, TaskProgress, TaskQueued};
+use gbe_jobs_domain::subjects::{jobs, tasks};
+use gbe_jobs_domain::{
+    JobDefinition, JobId, OrgId, TaskDefinition, TaskOutcome, TaskParams, TaskState, TaskType,
+};
+use gbe_nexus::{
+    DomainPayload, Envelope, Message, MessageHandler, StartPosition, SubscribeOpts, Subscription,
+    Transport, TransportError,
+};
+use gbe_nexus_memory::{MemoryTransport, MemoryTransportConfig};
+use gbe_operative::{Operative, OperativeConfig, OperativeError, TaskContext, TaskExecutor};
+use gbe_state_store::StateStore;
+use gbe_state_store_memory::MemoryStateStore;
+
+struct Harness {
+    transport: Arc<dyn Transport>,
+    store: Arc<dyn StateStore>,
+    driver: Arc<JobDriver>,
+    operative: Operative,
+}
+
+fn harness(config: OperativeConfig) -> Harness {
+    let transport: Arc<dyn Transport> =
+        Arc::new(MemoryTransport::new(MemoryTransportConfig::default()));
+    let store: Arc<dyn StateStore> = Arc::new(MemoryStateStore::new());
+    let driver = Arc::new(JobDriver::new(
+        transport.clone(),
+        store.clone(),
+        DriverConfig::default(),
+    ));
+    let operative = Operative::new(transport.clone(), store.clone(), config);
+    Harness {
+        transport,
+        store,
+        driver,
+        operative,
+    }
+}
+
+fn fetch_type() -> TaskType {
+    TaskType::new("data-fetch").unwrap()
+}
+
+fn single_task(max_retries: u32) -> JobDefinition {
+    JobDefinition {
+        v: 1,
+        name: "Fetch".to_string(),
+        job_type: "fetch-only".to_string(),
+        tasks: vec![TaskDefinition {
+            name: "fetch".to_string(),
+            task_type: fetch_type(),
+            depends_on: vec![],
+            params: TaskParams::default(),
+            input_from: HashMap::new(),
+            timeout_secs: Some(60),
+            max_retries: Some(max_retries),
+        }],
+    }
+}
+
+async fn create_job(h: &Harness, max_retries: u32) -> JobId {
+    h.driver
+        .create_job(
+            &single_task(max_retries),
+            OrgId::new("org_acme").unwrap(),
+            None,
+        )
+        .await
+        .unwrap()
+}
+
+async fn the_task(h: &Harness, job_id: &JobId) -> TaskRecord {
+    let mut tasks = TaskRecord::load_for_job(&h.store, job_id).await.unwrap();
+    assert_eq!(tasks.len(), 1);
+    tasks.remove(0)
+}
+
+struct ChannelHandler {
+    tx: mpsc::UnboundedSender<Envelope>,
+}
+
+#[async_trait]
+impl MessageHandler for ChannelHandler {
+    async fn handle(&self, msg: &dyn Message) -> Result<(), TransportError> {
+        let _ = self.tx.send(msg.envelope().clone());
+        msg.ack().await
+    }
+}
+
+async fn watch(
+    transport: &Arc<dyn Transport>,
+    subject: &str,
+) -> (Box<dyn Subscription>, mpsc::UnboundedReceiver<Envelope>) {
+    let (tx, rx) = mpsc::unbounded_channel();
+    let sub = transport
+        .subscribe(
+            subject,
+            "test-observer",
+            Box::new(ChannelHandler { tx }),
+            Some(SubscribeOpts {
+                start_from: StartPosition::Earliest,
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+    (sub, rx)
+}
+
+async fn recv<T: serde::de::DeserializeOwned>(rx: &mut mpsc::UnboundedReceiver<Envelope>) -> T {
+    let env = tokio::time::timeout(Duration::from_secs(2), rx.recv())
+        .await
+        .expect("timed out waiting for event")
+        .unwrap();
+    DomainPayload::<T>::from_bytes(&env.payload).unwrap().data
+}
+
+async fn wait_for_state(h: &Harness, job_id: &JobId, state: TaskState) -> TaskRecord {
+    for _ in 0..100 {
+        let task = the_task(h, job_id).await;
+        if task.state == state {
+            return task;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("task never reached {state}");
+}
+
+fn completed(data: serde_json::Value) -> TaskOutcome {
+    TaskOutcome::Completed {
+        output: vec![],
+        result_ref: Some("s3://bucket/fetch.csv".to_string()),
+        data: Some(data),
+    }
+}
+
+/// Reports two steps of progress, then completes.
+struct Fetcher;
+
+#[async_trait]
+impl TaskExecutor for Fetcher {
+    async fn execute(&self, _task: &TaskQueued, ctx: &TaskContext) -> TaskOutcome {
+        ctx.progress(1, Some(2), Some("downloading".to_string()))
+            .await
+            .unwrap();
+        ctx.progress(2, Some(2), None).await.unwrap();
+        completed(serde_json::json!({"rows": 2}))
+    }
+}
+
+/// Fails every attempt, counting them.
+struct Flaky {
+    attempts: AtomicU32,
+}
+
+#[async_trait]
+impl TaskExecutor for Flaky {
+    async fn execute(&self, task: &TaskQueued, _ctx: &TaskContext) -> TaskOutcome {
+        self.attempts.fetch_add(1, Ordering::SeqCst);
+        TaskOutcome::Failed {
+            exit_code: 2,
+            error: format!("upstream unavailable (attempt {})", task.retry_count),
+        }
+    }
+}
+
+struct Panicky;
+
+#[async_trait]
+impl TaskExecutor for Panicky {
+    async fn execute(&self, _task: &TaskQueued, _ctx: &TaskContext) -> TaskOutcome {
+        panic!("bad input");
+    }
+}
+
+/// Signals `started`, then waits for `proceed` (or cancellation, if
+/// `honour_cancel`) before completing.
+struct Gated {
+    started: Arc<Notify>,
+    proceed: Arc<Notify>,
+    honour_cancel: bool,
+    result: mpsc::UnboundedSender<Result<u64, OperativeError>>,
+}
+
+#[async_trait]
+impl TaskExecutor for Gated {
+    async fn execute(&self, _task: &TaskQueued, ctx: &TaskContext) -> TaskOutcome {
+        self.started.notify_one();
+        if self.honour_cancel {
+            tokio::select! {
+                () = ctx.cancelled() => {}
+                () = self.proceed.notified() => {}
+            }
+        } else {
+            self.proceed.notified().await;
+        }
+        let _ = self.result.send(ctx.extend_timeout().await);
+        completed(serde_json::json!({"cancelled": ctx.is_cancelled()}))
+    }
+}
+
+fn gated(
+    honour_cancel: bool,
+) -> (
+    Arc<Gated>,
+    mpsc::UnboundedReceiver<Result<u64, OperativeError>>,
+) {
+    let (result, rx) = mpsc::unbounded_channel();
+    let executor = Arc::new(Gated {
+        started: Arc::new(Notify::new()),
+        proceed: Arc::new(Notify::new()),
+        honour_cancel,
+        result,
+    });
+    (executor, rx)
+}
+
+#[tokio::test]
+async fn executes_task_and_completes_job() {
+    let h = harness(OperativeConfig::default());
+    let _driver = h.driver.start().await.unwrap();
+    let (_p, mut progress) = watch(&h.transport, &tasks::progress("data-fetch")).await;
+    let (_c, mut job_done) = watch(&h.transport, &jobs::completed("fetch-only")).await;
+    h.operative
+        .start(&fetch_type(), Arc::new(Fetcher))
+        .await
+        .unwrap();
+
+    let job_id = create_job(&h, 0).await;
+    let step: TaskProgress = recv(&mut progress).await;
+    assert_eq!(step.current_step, 1);
+    assert_eq!(step.step_count, Some(2));
+    assert_eq!(step.message.as_deref(), Some("downloading"));
+    assert_eq!(step.job_id, job_id);
+    let step: TaskProgress = recv(&mut progress).await;
+    assert_eq!(step.current_step, 2);
+
+    let done: JobCompleted = recv(&mut job_done).await;
+    assert_eq!(done.job_id, job_id);
+
+    let task = the_task(&h, &job_id).await;
+    assert_eq!(task.state, TaskState::Completed);
+    assert!(task.worker.as_deref().unwrap().starts_with("opr-"));
+    assert_eq!(task.timeout_at, None, "settling drops the claim deadline");
+    assert_eq!(
+        task.outcome,
+        Some(completed(serde_json::json!({"rows": 2})))
+    );
+    let step = h
+        .store
+        .get_field(&task.key(), fields::task::CURRENT_STEP)
+        .await
+        .unwrap();
+    assert_eq!(step, Some(Bytes::from("2")));
+    assert!(h.operative.running().is_empty());
+}
+
+#[tokio::test]
+async fn redelivered_completed_task_resends_terminal_event() {
+    let h = harness(OperativeConfig::default());
+    let (_q, mut queue) = watch(&h.transport, &tasks::queue("data-fetch")).await;
+    let (_t, mut terminal) = watch(&h.transport, &tasks::terminal("data-fetch")).await;
+    h.operative
+        .start(&fetch_type(), Arc::new(Fetcher))
+        .await
+        .unwrap();
+
+    let job_id = create_job(&h, 0).await;
+    let queued = tokio::time::timeout(Duration::from_secs(2), queue.recv())
+        .await
+        .expect("timed out waiting for event")
+        .unwrap();
+    let first: TaskCompleted = recv(&mut terminal).await;
+    let task = wait_for_state(&h, &job_id, TaskState::Completed).await;
+
+    // As if the first terminal event had failed and the message come back.
+    h.transport
+        .publish(&tasks::queue("data-fetch"), queued.payload, None)
+        .await
+        .unwrap();
+    let again: TaskCompleted = recv(&mut terminal).await;
+    assert_eq!(again.task_id, first.task_id);
+    assert_eq!(again.completed_at, first.completed_at);
+    assert_eq!(again.result_ref, first.result_ref);
+    assert_eq!(the_task(&h, &job_id).await.updated_at, task.updated_at);
+}
+
+#[tokio::test]
+async fn failed_attempts_are_retried_then_fail_the_job() {
+    let h = harness(OperativeConfig::default());
+    let _driver = h.driver.start().await.unwrap();
+    let (_f, mut job_failed) = watch(&h.transport, &jobs::failed("fetch-only")).await;
+    let executor = Arc::new(Flaky {
+        attempts: AtomicU32::new(0),
+    });
+    h.operative
+        .start(&fetch_type(), executor.clone())
+        .await
+        .unwrap();
+
+    let job_id = create_job(&h, 1).await;
+    let failed: JobFailed = recv(&mut job_failed).await;
+    assert_eq!(failed.job_id, job_id);
+    assert_eq!(executor.attempts.load(Ordering::SeqCst), 2);
+
+    let task = the_task(&h, &job_id).await;
+    assert_eq!(task.state, TaskState::Failed);
+    assert_eq!(task.retry_count, 1);
+    assert_eq!(
+        task.error.as_deref(),
+        Some("upstream unavailable (attempt 1)")
+    );
+    assert!(matches!(
+        task.outcome,
+        Some(TaskOutcome::Failed { exit_code: 2, .. })
+    ));
+}
+
+#[tokio::test]
+async fn panicking_executor_fails_the_attempt() {
+    let h = harness(OperativeConfig::default());
+    h.operative
+        .start(&fetch_type(), Arc::new(Panicky))
+        .await
+        .unwrap();
+
+    let job_id = create_job(&h, 0).await;
+    let task = wait_for_state(&h, &job_id, TaskState::Failed).await;
+    assert!(task.error.unwrap().starts_with("executor panicked"));
+}
+
+#[tokio::test]
+async fn stale_and_undecodable_messages_are_not_executed() {
+    let h = harness(OperativeConfig::default());
+    let (_q, mut queue) = watch(&h.transport, &tasks::queue("data-fetch")).await;
+    let job_id = create_job(&h, 0).await;
+    let _queued: TaskQueued = recv(&mut queue).await;
+    let task = the_task(&h, &job_id).await;
+    assert!(
+        h.store
+            .compare_and_swap(
+                &task.key(),
+                fields::task::STATE,
+                Bytes::from("pending"),
+                Bytes::from("cancelled"),
+            )
+            .await
+            .unwrap()
+    );
+    h.transport
+        .publish(
+            &tasks::queue("data-fetch"),
+            Bytes::from("not a payload"),
+            None,
+        )
+        .await
+        .unwrap();
+
+    let executor = Arc::new(Flaky {
+        attempts: AtomicU32::new(0),
+    });
+    h.operative
+        .start(&fetch_type(), executor.clone())
+        .await
+        .unwrap();
+
+    let mut dead = Vec::new();
+    for _ in 0..100 {
+        dead = h.transport.list_dead_letters("tasks", None).await.unwrap();
+        if !dead.is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    assert_eq!(dead.len(), 1);
+    assert!(dead[0].reason.starts_with("undecodable payload"));
+    assert_eq!(executor.attempts.load(Ordering::SeqCst), 0);
+    assert_eq!(the_task(&h, &job_id).await.state, TaskState::Cancelled);
+}
+
+#[tokio::test]
+async fn lost_claim_cancels_context_and_drops_outcome() {
+    let h = harness(OperativeConfig::default());
+    let (executor, mut results) = gated(false);
+    h.operative
+        .start(&fetch_type(), executor.clone())
+        .await
+        .unwrap();
+
+    let job_id = create_job(&h, 3).await;
+    executor.started.notified().await;
+    let task = the_task(&h, &job_id).await;
+    assert_eq!(task.state, TaskState::Running);
+    assert_eq!(h.operative.running(), vec![task.task_id.clone()]);
+
+    // Play the watcher taking the task back.
+    assert!(
+        h.store
+            .compare_and_swap(
+                &task.key(),
+                fields::task::STATE,
+                Bytes::from("running"),
+                Bytes::from("pending"),
+            )
+            .await
+            .unwrap()
+    );
+    executor.proceed.notify_one();
+
+    let extended = results.recv().await.unwrap();
+    assert!(matches!(extended, Err(OperativeError::ClaimLost(id)) if id == task.task_id));
+    for _ in 0..100 {
+        if h.operative.running().is_empty() {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    let task = the_task(&h, &job_id).await;
+    assert_eq!(task.state, TaskState::Pending);
+    assert_eq!(task.outcome, None);
+}
+
+#[tokio::test]
+async fn concurrency_runs_tasks_side_by_side() {
+    let h = harness(OperativeConfig {
+        concurrency: 2,
+        ..Default::default()
+    });
+    let (executor, mut results) = gated(false);
+    h.operative
+        .start(&fetch_type(), executor.clone())
+        .await
+        .unwrap();
+
+    let mut jobs = Vec::new();
+    for _ in 0..3 {
+        jobs.push(create_job(&h, 0).await);
+    }
+    for _ in 0..100 {
+        if h.operative.running().len() == 2 {
+            break;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    assert_eq!(h.operative.running().len(), 2);
+
+    for _ in 0..3 {
+        executor.proceed.notify_one();
+        assert!(results.recv().await.unwrap().is_ok());
+    }
+    for job_id in &jobs {
+        wait_for_state(&h, job_id, TaskState::Completed).await;
+    }
+}
+
+#[tokio::test]
+async fn shutdown_lets_running_task_finish() {
+    let h = harness(OperativeConfig::default());
+    let (executor, mut results) = gated(true);
+    h.operative
+        .start(&fetch_type(), executor.clone())
+        .await
+        .unwrap();
+
+    let job_id = create_job(&h, 0).await;
+    executor.started.notified().await;
+
+    let released = h.operative.shutdown().await.unwrap();
+    assert!(released.is_empty());
+    assert!(results.recv().await.unwrap().is_ok());
+    let task = the_task(&h, &job_id).await;
+    assert_eq!(task.state, TaskState::Completed);
+    assert_eq!(
+        task.outcome,
+        Some(completed(serde_json::json!({"cancelled": true})))
+    );
+}
+
+#[tokio::test]
+async fn shutdown_releases_task_past_deadline() {
+    let h = harness(OperativeConfig {
+        shutdown_timeout: Duration::from_millis(100),
+        ..Default::default()
+    });
+    let (_q, mut queue) = watch(&h.transport, &tasks::queue("data-fetch")).await;
+    let (executor, _results) = gated(false);
+    h.operative
+        .start(&fetch_type(), executor.clone())
+        .await
+        .unwrap();
+
+    let job_id = create_job(&h, 2).await;
+    let first: TaskQueued = recv(&mut queue).await;
+    executor.started.notified().await;
+
+    let released = h.operative.shutdown().await.unwrap();
+    assert_eq!(released, vec![first.task_id.clone()]);
+    let task = the_task(&h, &job_id).await;
+    assert_eq!(task.state, TaskState::Pending);
+    assert_eq!(task.retry_count, 1);
+    assert!(task.error.unwrap().contains("shut down"));
+    let retry: TaskQueued = recv(&mut queue).await;
+    assert_eq!(retry.task_id, first.task_id);
+    assert_eq!(retry.retry_count, 1);
+
+    // The abandoned execution finishing later changes nothing.
+    executor.proceed.notify_one();
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    let task = the_task(&h, &job_id).await;
+    assert_eq!(task.state, TaskState::Pending);
+    assert_eq!(task.outcome, None);
+}
@@ -0,0 +1,317 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+use gbe_state_store::{Record, ScanFilter, ScanOp, StateStoreError};
+
+/// A stored record: field map plus optional absolute expiry.
+struct Entry {
+    fields: HashMap<String, Bytes>,
+    expires_at: Option<Instant>,
+}
+
+impl Entry {
+    fn new() -> Self {
+        Self {
+            fields: HashMap::new(),
+            expires_at: None,
+        }
+    }
+
+    fn is_expired(&self, now: Instant) -> bool {
+        self.expires_at.is_some_and(|at| at <= now)
+    }
+}
+
+/// In-process `StateStore` with the same semantics as the Redis backend.
+///
+/// Records behave like Redis hashes: `put` and `set_field(s)` merge fields
+/// into any existing record, TTL is only changed by `put` with `Some(ttl)`,
+/// and expired keys disappear lazily on the next access.
+pub struct MemoryStateStore {
+    entries: Mutex<BTreeMap<String, Entry>>,
+    closed: AtomicBool,
+}
+
+impl MemoryStateStore {
+    #[must_use]
+    pub fn new() -> Self {
+        Self {
+            entries: Mutex::new(BTreeMap::new()),
+            closed: AtomicBool::new(false),
+        }
+    }
+
+    fn check_closed(&self) -> Result<(), StateStoreError> {
+        if self.closed.load(Ordering::Acquire) {
+            return Err(StateStoreError::Other("store is closed".to_string()));
+        }
+        Ok(())
+    }
+}
+
+impl Default for MemoryStateStore {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Look up a live entry, dropping it first if it has expired.
+fn live_entry<'a>(entries: &'a mut BTreeMap<String, Entry>, key: &str) -> Option<&'a mut Entry> {
+    if entries
+        .get(key)
+        .is_some_and(|e| e.is_expired(Instant::now()))
+    {
+        entries.remove(key);
+    }
+    entries.get_mut(key)
+}
+
+/// Look up a live entry, creating an empty one if missing or expired.
+fn live_entry_or_insert<'a>(entries: &'a mut BTreeMap<String, Entry>, key: &str) -> &'a mut Entry {
+    if live_entry(entries, key).is_none() {
+        entries.insert(key.to_string(), Entry::new());
+    }
+    entries.get_mut(key).expect("entry just inserted")
+}
+
+fn matches_filter(fields: &HashMap<String, Bytes>, filter: &ScanFilter) -> bool {
+    let Some(val) = fields.get(&filter.field) else {
+        return false;
+    };
+    match filter.op {
+        ScanOp::Eq => val.as_ref() == filter.value.as_ref(),
+        ScanOp::Lt => val.as_ref() < filter.value.as_ref(),
+        ScanOp::Gt => val.as_ref() > filter.value.as_ref(),
+    }
+}
+
+#[async_trait]
+impl gbe_state_store::StateStore for MemoryStateStore {
+    async fn get(&self, key: &str) -> Result<Option<Record>, StateStoreError> {
+        self.check_closed()?;
+        let mut entries = self.entries.lock().await;
+        Ok(live_entry(&mut entries, key)
+            .filter(|e| !e.fields.is_empty())
+            .map(|e| Record {
+                fields: e.fields.clone(),
+                ttl: None,
+            }))
+    }
+
+    async fn put(
+        &self,
+        key: &str,
+        record: Record,
+        ttl: Option<Duration>,
+    ) -> Result<(), StateStoreError> {
+        self.check_closed()?;
+        if record.fields.is_empty() {
+            return Ok(());
+        }
+
+        let mut entries = self.entries.lock().await;
+        let entry = live_entry_or_insert(&mut entries, key);
+        entry.fields.extend(record.fields);
+        if let Some(ttl) = ttl {
+            entry.expires_at = Some(Instant::now() + ttl);
+        }
+        Ok(())
+    }
+
+    async fn delete(&self, key: &str) -> Result<(), StateStoreError> {
+        self.check_closed()?;
+        self.entries.lock().await.remove(key);
+        Ok(())
+    }
+
+    async fn get_field(&self, key: &str, field: &str) -> Result<Option<Bytes>, StateStoreError> {
+        self.check_closed()?;
+        let mut entries = self.entries.lock().await;
+        Ok(live_entry(&mut entries, key).and_then(|e| e.fields.get(field).cloned()))
+    }
+
+    async fn set_field(&self, key: &str, field: &str, value: Bytes) -> Result<(), StateStoreError> {
+        self.check_closed()?;
+        let mut entries = self.entries.lock().await;
+        live_entry_or_insert(&mut entries, key)
+            .fields
+            .insert(field.to_string(), value);
+        Ok(())
+    }
+
+    async fn set_fields(
+        &self,
+        key: &str,
+        fields: HashMap<String, Bytes>,
+    ) -> Result<(), StateStoreError> {
+        self.check_closed()?;
+        if fields.is_empty() {
+            return Ok(());
+        }
+
+        let mut entries = self.entries.lock().await;
+        live_entry_or_insert(&mut entries, key)
+            .fields
+            .extend(fields);
+        Ok(())
+    }
+
+    async fn compare_and_swap(
+        &self,
+        key: &str,
+        field: &str,
+        expected: Bytes,
+        new: Bytes,
+    ) -> Result<bool, StateStoreError> {
+        self.check_closed()?;
+        let mut entries = self.entries.lock().await;
+
+        // A missing key or field never matches, mirroring HGET returning nil.
+        let Some(entry) = live_entry(&mut entries, key) else {
+            return Ok(false);
+        };
+        match entry.fields.get_mut(field) {
+            Some(cur) if *cur == expected => {
+                *cur = new;
+                Ok(true)
+            }
+            _ => Ok(false),
+        }
+    }
+
+    async fn compare_and_set_fields(
+        &self,
+        key: &str,
+        expected: HashMap<String, Bytes>,
+        fields: HashMap<String, Bytes>,
+        remove: &[&str],
+    ) -> Result<bool, StateStoreError> {
+        self.check_closed()?;
+        let mut entries = self.entries.lock().await;
+
+        let matches = match live_entry(&mut entries, key) {
+            Some(entry) => expected
+                .iter()
+                .all(|(field, value)| entry.fields.get(field) == Some(value)),
+            None => expected.is_empty(),
+        };
+        if !matches {
+            return Ok(false);
+        }
+        let entry = live_entry_or_insert(&mut entries, key);
+        entry.fields.extend(fields);
+        for field in remove {
+            entry.fields.remove(*field);
+        }
+        Ok(true)
+    }
+
+    async fn scan(
+        &self,
+        prefix: &str,
+        filter: Option<ScanFilter>,
+    ) -> Result<Vec<(String, Record)>, StateStoreError> {
+        self.check_closed()?;
+        let mut entries = self.entries.lock().await;
+        let now = Instant::now();
+        let max_results = filter.as_ref().and_then(|f| f.max_results);
+
+        // Purge expired keys under the prefix so they never show up in results.
+        let expired: Vec<String> = entries
+            .range(prefix.to_string()..)
+            .take_while(|(k, _)| k.starts_with(prefix))
+            .filter(|(_, e)| e.is_expired(now))
+            .map(|(k, _)| k.clone())
+            .collect();
+        for key in expired {
+            entries.remove(&key);
+        }
+
+        let mut results = Vec::new();
+        for (key, entry) in entries
+            .range(prefix.to_string()..)
+            .take_while(|(k, _)| k.starts_with(prefix))
+        {
+            if entry.fields.is_empty() {
+                continue;
+            }
+            if let Some(ref f) = filter
+                && !matches_filter(&entry.fields, f)
+            {
+                continue;
+            }
+
+            results.push((
+                key.clone(),
+                Record {
+                    fields: entry.fields.clone(),
+                    ttl: None,
+                },
+            ));
+
+            if let Some(max) = max_results
+                && results.len() >= max as usize
+            {
+                break;
+            }
+        }
+
+        Ok(results)
+    }
+
+    async fn ping(&self) -> Result<bool, StateStoreError> {
+        Ok(true)
+    }
+
+    async fn close(&self) -> Result<(), StateStoreError> {
+        self.closed.store(true, Ordering::Release);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry_with_expiry(expires_at: Option<Instant>) -> Entry {
+        Entry {
+            fields: HashMap::from([("x".to_string(), Bytes::from("1"))]),
+            expires_at,
+        }
+    }
+
+    #[test]
+    fn entry_expiry() {
+        let now = Instant::now();
+        assert!(!entry_with_expiry(None).is_expired(now));
+        assert!(!entry_with_expiry(Some(now + Duration::from_secs(1))).is_expired(now));
+        assert!(entry_with_expiry(Some(now)).is_expired(now));
+    }
+
+    #[test]
+    fn filter_ops_compare_bytes() {
+        let fields = HashMap::from([("timeout_at".to_string(), Bytes::from("200"))]);
+        let filter = |op, value: &'static str| ScanFilter {
+            field: "timeout_at".to_string(),
+            op,
+            value: Bytes::from(value),
+            max_results: None,
+        };
+
+        assert!(matches_filter(&fields, &filter(ScanOp::Eq, "200")));
+        assert!(matches_filter(&fields, &filter(ScanOp::Lt, "300")));
+        assert!(matches_filter(&fields, &filter(ScanOp::Gt, "100")));
+        assert!(!matches_filter(&fields, &filter(ScanOp::Gt, "300")));
+
+        let missing = ScanFilter {
+            field: "other".to_string(),
+            ..filter(ScanOp::Eq, "200")
+        };
+        assert!(!matches_filter(&fields, &missing));
+    }
+}
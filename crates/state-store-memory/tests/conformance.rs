@@ -0,0 +1,13 @@
+//! Shared state store conformance suite against the in-memory backend.
+
+use std::sync::Arc;
+
+use gbe_state_store::StateStore;
+use gbe_state_store_memory::MemoryStateStore;
+
+#[allow(clippy::unused_async)] // factory signature is fixed by the suite
+async fn store() -> Option<Arc<dyn StateStore>> {
+    Some(Arc::new(MemoryStateStore::new()))
+}
+
+gbe_nexus_testkit::state_store_conformance_tests!(store);
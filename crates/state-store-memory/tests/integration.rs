@@ -0,0 +1,41 @@
+//! Memory-specific cases; the shared behaviour is covered by the
+//! conformance suite in `conformance.rs`.
+
+use bytes::Bytes;
+use std::time::Duration;
+
+use gbe_state_store::{Record, StateStore};
+use gbe_state_store_memory::MemoryStateStore;
+
+fn make_record(fields: &[(&str, &str)]) -> Record {
+    Record {
+        fields: fields
+            .iter()
+            .map(|(k, v)| (k.to_string(), Bytes::from(v.to_string())))
+            .collect(),
+        ttl: None,
+    }
+}
+
+#[tokio::test]
+async fn test_set_field_keeps_ttl() {
+    let store = MemoryStateStore::new();
+    let key = "gbe:test:state:ttl-keep";
+
+    store
+        .put(
+            key,
+            make_record(&[("state", "temp")]),
+            Some(Duration::from_millis(200)),
+        )
+        .await
+        .unwrap();
+    store
+        .set_field(key, "state", Bytes::from("updated"))
+        .await
+        .unwrap();
+
+    tokio::time::sleep(Duration::from_millis(300)).await;
+
+    assert!(store.get(key).await.unwrap().is_none());
+}
@@ -18,6 +18,31 @@ else
 end
 ";
 
+/// Write and remove fields if every expected field matches, or change
+/// nothing. HGET of a missing field is false, so it never matches.
+///
+/// KEYS: record key.
+/// ARGV: expected pair count, new pair count, the expected pairs, the new
+/// pairs, then the fields to remove.
+const CAS_FIELDS_SCRIPT: &str = r"
+local n_expected, n_new = tonumber(ARGV[1]), tonumber(ARGV[2])
+local i = 3
+for _ = 1, n_expected do
+    if redis.call('HGET', KEYS[1], ARGV[i]) ~= ARGV[i + 1] then
+        return 0
+    end
+    i = i + 2
+end
+for _ = 1, n_new do
+    redis.call('HSET', KEYS[1], ARGV[i], ARGV[i + 1])
+    i = i + 2
+end
+for j = i, #ARGV do
+    redis.call('HDEL', KEYS[1], ARGV[j])
+end
+return 1
+";
+
 pub struct RedisStateStore {
     conn: redis::aio::ConnectionManager,
     closed: AtomicBool,
@@ -95,9 +120,12 @@ impl gbe_state_store::StateStore for RedisStateStore {
             .map_err(map_redis_err)?;
 
         if let Some(ttl) = ttl {
-            redis::cmd("EXPIRE")
+            // Safety: millis fit in u64 for any practical TTL
+            #[allow(clippy::cast_possible_truncation)]
+            let ttl_ms = ttl.as_millis() as u64;
+            redis::cmd("PEXPIRE")
                 .arg(key)
-                .arg(ttl.as_secs().cast_signed())
+                .arg(ttl_ms)
                 .query_async::<()>(&mut conn)
                 .await
                 .map_err(map_redis_err)?;
@@ -188,6 +216,31 @@ impl gbe_state_store::StateStore for RedisStateStore {
         Ok(result == 1)
     }
 
+    async fn compare_and_set_fields(
+        &self,
+        key: &str,
+        expected: HashMap<String, Bytes>,
+        fields: HashMap<String, Bytes>,
+        remove: &[&str],
+    ) -> Result<bool, StateStoreError> {
+        self.check_closed()?;
+        let mut conn = self.conn.clone();
+        let script = redis::Script::new(CAS_FIELDS_SCRIPT);
+        let mut invocation = script.prepare_invoke();
+        invocation.key(key).arg(expected.len()).arg(fields.len());
+        for (field, value) in expected.iter().chain(&fields) {
+            invocation.arg(field).arg(value.as_ref());
+        }
+        for field in remove {
+            invocation.arg(*field);
+        }
+        let result: i32 = invocation
+            .invoke_async(&mut conn)
+            .await
+            .map_err(map_redis_err)?;
+        Ok(result == 1)
+    }
+
     async fn scan(
         &self,
         prefix: &str,
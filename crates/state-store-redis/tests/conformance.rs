@@ -0,0 +1,20 @@
+//! Shared state store conformance suite against the Redis backend.
+//!
+//! Requires a running Redis instance. Set `REDIS_URL` to enable these tests.
+//!
+//! Run with: `REDIS_URL=redis://localhost:6379` cargo test --package gbe-state-store-redis
+
+use std::sync::Arc;
+
+use gbe_state_store::{StateStore, StateStoreConfig};
+use gbe_state_store_redis::RedisStateStore;
+
+async fn store() -> Option<Arc<dyn StateStore>> {
+    let url = std::env::var("REDIS_URL").ok()?;
+    let store = RedisStateStore::connect(StateStoreConfig { url })
+        .await
+        .expect("failed to connect to Redis");
+    Some(Arc::new(store))
+}
+
+gbe_nexus_testkit::state_store_conformance_tests!(store);
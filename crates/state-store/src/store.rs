@@ -35,6 +35,16 @@ pub trait StateStore: Send + Sync {
         expected: Bytes,
         new: Bytes,
     ) -> Result<bool, StateStoreError>;
+    /// Write `fields` and remove `remove` if every field in `expected`
+    /// holds its expected value, all in one atomic step. Returns `false`,
+    /// changing nothing, otherwise; a missing key or field never matches.
+    async fn compare_and_set_fields(
+        &self,
+        key: &str,
+        expected: HashMap<String, Bytes>,
+        fields: HashMap<String, Bytes>,
+        remove: &[&str],
+    ) -> Result<bool, StateStoreError>;
 
     // Scan (for sweeper)
     async fn scan(
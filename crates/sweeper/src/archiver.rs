@@ -0,0 +1,422 @@
+use bytes::Bytes;
+use flate2::Compression;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use std::collections::HashMap;
+use std::io::{Read, Write};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+use tokio_util::sync::CancellationToken;
+
+use gbe_nexus::{
+    Envelope, Message, MessageStream, StartPosition, SubscribeOpts, Transport, now_millis,
+};
+use gbe_state_store::StateStore;
+
+use crate::error::ArchiveError;
+use crate::sink::BlobSink;
+use crate::watermark::{read_watermark, write_watermark};
+
+/// Configuration for an `Archiver`.
+pub struct ArchiverConfig {
+    /// Consumer group the archiver reads through.
+    pub group: String,
+    /// Flush once this many envelopes are staged...
+    pub batch_size: usize,
+    /// ...or once the oldest staged envelope has waited this long.
+    pub batch_timeout: Duration,
+    /// How long the envelopes of a batch that could not be written are
+    /// held back before they are redelivered.
+    pub retry_delay: Duration,
+}
+
+impl Default for ArchiverConfig {
+    fn default() -> Self {
+        Self {
+            group: "archiver".to_string(),
+            batch_size: 500,
+            batch_timeout: Duration::from_secs(60),
+            retry_delay: Duration::from_secs(5),
+        }
+    }
+}
+
+/// Drains streams to a `BlobSink` as gzipped JSONL batches, one envelope
+/// (as serialized on the wire) per line.
+///
+/// Batches are keyed `{subject}/{yyyy}/{mm}/{dd}/{hh}/{batch_ulid}.jsonl.gz`,
+/// partitioned by the UTC hour of their first envelope. Envelopes are acked
+/// only once their batch is in the sink and the watermark recorded, so a
+/// crash or a failed write leaves them pending for redelivery; the
+/// watermark keeps the sweeper off entries not archived yet.
+pub struct Archiver {
+    transport: Arc<dyn Transport>,
+    state: Arc<dyn StateStore>,
+    sink: Arc<dyn BlobSink>,
+    config: ArchiverConfig,
+}
+
+impl Archiver {
+    pub fn new(
+        transport: Arc<dyn Transport>,
+        state: Arc<dyn StateStore>,
+        sink: Arc<dyn BlobSink>,
+        config: ArchiverConfig,
+    ) -> Self {
+        Self {
+            transport,
+            state,
+            sink,
+            config,
+        }
+    }
+
+    /// Start draining `subject` from its earliest entry (or wherever the
+    /// archiver's group left off).
+    ///
+    /// # Errors
+    /// Returns an error if the watermark cannot be read or initialised, or
+    /// the subscription fails.
+    pub async fn start(&self, subject: &str) -> Result<ArchiveHandle, ArchiveError> {
+        // Register the stream as archived before reading, so the sweeper
+        // holds back from the first entry on.
+        let archived_through = match read_watermark(&self.state, subject).await? {
+            Some(through) => through,
+            None => {
+                write_watermark(&self.state, subject, 0, "", now_millis()).await?;
+                0
+            }
+        };
+
+        let batcher = Arc::new(Batcher {
+            subject: subject.to_string(),
+            state: self.state.clone(),
+            sink: self.sink.clone(),
+            retry_delay: self.config.retry_delay,
+            batch: Mutex::new(Batch {
+                archived_through,
+                ..Default::default()
+            }),
+        });
+        let stream = self
+            .transport
+            .pull(
+                subject,
+                &self.config.group,
+                Some(SubscribeOpts {
+                    start_from: StartPosition::Earliest,
+                    // A full batch is staged unacked.
+                    max_inflight: u32::try_from(self.config.batch_size)
+                        .unwrap_or(u32::MAX)
+                        .max(1),
+                    // Keep staged envelopes from being reclaimed while they
+                    // wait out the batch timeout.
+                    ack_timeout: (self.config.batch_timeout * 2)
+                        .max(SubscribeOpts::default().ack_timeout),
+                    ..Default::default()
+                }),
+            )
+            .await?;
+
+        let token = CancellationToken::new();
+        let task = tokio::spawn(stage(
+            batcher.clone(),
+            stream,
+            self.config.batch_size,
+            self.config.batch_timeout,
+            token.clone(),
+        ));
+
+        Ok(ArchiveHandle {
+            batcher,
+            token,
+            task,
+        })
+    }
+}
+
+/// A running archive of one stream.
+pub struct ArchiveHandle {
+    batcher: Arc<Batcher>,
+    token: CancellationToken,
+    task: JoinHandle<()>,
+}
+
+impl ArchiveHandle {
+    /// Write whatever is staged now.
+    ///
+    /// # Errors
+    /// Returns an error if the batch cannot be written or the watermark
+    /// cannot be recorded; its envelopes are then redelivered.
+    pub async fn flush(&self) -> Result<(), ArchiveError> {
+        let mut batch = self.batcher.batch.lock().await;
+        self.batcher.flush(&mut batch).await
+    }
+
+    /// Stop reading and write whatever is staged.
+    ///
+    /// Envelopes read ahead but not staged yet are handed back to the group.
+    ///
+    /// # Errors
+    /// Returns an error if the final batch cannot be written.
+    pub async fn stop(self) -> Result<(), ArchiveError> {
+        self.token.cancel();
+        let _ = self.task.await;
+
+        let mut batch = self.batcher.batch.lock().await;
+        self.batcher.flush(&mut batch).await
+    }
+}
+
+#[derive(Default)]
+struct Batch {
+    messages: Vec<Box<dyn Message>>,
+    started: Option<Instant>,
+    /// Last watermark recorded.
+    archived_through: u64,
+    /// Publish times of envelopes whose batch failed, by message ID, until
+    /// they are archived on redelivery.
+    held_back: HashMap<String, u64>,
+}
+
+struct Batcher {
+    subject: String,
+    state: Arc<dyn StateStore>,
+    sink: Arc<dyn BlobSink>,
+    retry_delay: Duration,
+    batch: Mutex<Batch>,
+}
+
+impl Batcher {
+    /// Write the staged batch and ack its messages, or nak them all if it
+    /// cannot be written.
+    async fn flush(&self, batch: &mut Batch) -> Result<(), ArchiveError> {
+        let messages = std::mem::take(&mut batch.messages);
+        batch.started = None;
+        if messages.is_empty() {
+            return Ok(());
+        }
+
+        match self.write(&messages, batch).await {
+            Ok(key) => {
+                for msg in &messages {
+                    if let Err(e) = msg.ack().await {
+                        // Redelivered and archived again; duplicates are harmless.
+                        tracing::warn!(subject = %self.subject, "acking archived envelope failed: {e}");
+                    }
+                }
+                tracing::info!(subject = %self.subject, key = %key, count = messages.len(), "archived batch");
+                Ok(())
+            }
+            Err(e) => {
+                for msg in &messages {
+                    let env = msg.envelope();
+                    batch
+                        .held_back
+                        .insert(env.message_id.clone(), env.timestamp);
+                    let _ = msg.nak(Some(self.retry_delay)).await;
+                }
+                Err(e)
+            }
+        }
+    }
+
+    /// Put a non-empty batch in the sink, then record the watermark.
+    /// Returns the batch's key.
+    ///
+    /// The watermark advances to the newest envelope archived, but no
+    /// further than the oldest one held back after a failed write, and
+    /// never moves backwards.
+    async fn write(
+        &self,
+        messages: &[Box<dyn Message>],
+        batch: &mut Batch,
+    ) -> Result<String, ArchiveError> {
+        let envelopes: Vec<&Envelope> = messages.iter().map(|msg| msg.envelope()).collect();
+        let (first, last) = (envelopes[0], envelopes[envelopes.len() - 1]);
+        let key = batch_key(&self.subject, first.timestamp, &ulid::Ulid::new());
+
+        self.sink
+            .put(&key, encode_batch(envelopes.iter().copied())?)
+            .await?;
+
+        for env in &envelopes {
+            batch.held_back.remove(&env.message_id);
+        }
+        let newest = envelopes
+            .iter()
+            .map(|env| env.timestamp)
+            .max()
+            .unwrap_or_default();
+        let archived_through = batch
+            .held_back
+            .values()
+            .min()
+            .map_or(newest, |&oldest| oldest.min(newest))
+            .max(batch.archived_through);
+        write_watermark(
+            &self.state,
+            &self.subject,
+            archived_through,
+            &last.message_id,
+            now_millis(),
+        )
+        .await?;
+        batch.archived_through = archived_through;
+        Ok(key)
+    }
+}
+
+/// Stage pulled messages, flushing by size and by age, until cancelled.
+async fn stage(
+    batcher: Arc<Batcher>,
+    mut stream: MessageStream,
+    batch_size: usize,
+    timeout: Duration,
+    token: CancellationToken,
+) {
+    loop {
+        let due = batcher
+            .batch
+            .lock()
+            .await
+            .started
+            .map(|started| started + timeout);
+        tokio::select! {
+            () = token.cancelled() => break,
+            msg = stream.recv() => {
+                let Some(msg) = msg else { break };
+                let mut batch = batcher.batch.lock().await;
+                batch.messages.push(msg);
+                batch.started.get_or_insert_with(Instant::now);
+                if batch.messages.len() >= batch_size
+                    && let Err(e) = batcher.flush(&mut batch).await
+                {
+                    tracing::warn!(subject = %batcher.subject, "archive flush failed: {e}");
+                }
+            }
+            () = tokio::time::sleep_until(due.unwrap_or_else(Instant::now)), if due.is_some() => {
+                let mut batch = batcher.batch.lock().await;
+                if batch.started.is_some_and(|started| started.elapsed() >= timeout)
+                    && let Err(e) = batcher.flush(&mut batch).await
+                {
+                    tracing::warn!(subject = %batcher.subject, "archive flush failed: {e}");
+                }
+            }
+        }
+    }
+    if let Err(e) = stream.close().await {
+        tracing::warn!(subject = %batcher.subject, "closing archive stream failed: {e}");
+    }
+}
+
+/// Sink key for a batch whose first envelope was published at `first_ms`.
+fn batch_key(subject: &str, first_ms: u64, batch_id: &ulid::Ulid) -> String {
+    format!("{subject}/{}/{batch_id}.jsonl.gz", partition(first_ms))
+}
+
+/// Partition path (`yyyy/mm/dd/hh`, UTC) of a unix millis timestamp.
+/// Zero-padded, so partitions sort chronologically as strings.
+pub(crate) fn partition(unix_ms: u64) -> String {
+    let (year, month, day, hour) = utc_hour(unix_ms);
+    format!("{year:04}/{month:02}/{day:02}/{hour:02}")
+}
+
+/// Split a batch key relative to its subject (`yyyy/mm/dd/hh/{ulid}.jsonl.gz`)
+/// into its partition and batch ID.
+pub(crate) fn parse_batch_key(relative: &str) -> Option<(&str, ulid::Ulid)> {
+    let (partition, file) = relative.rsplit_once('/')?;
+    let batch_id = file.strip_suffix(".jsonl.gz")?;
+    Some((partition, ulid::Ulid::from_string(batch_id).ok()?))
+}
+
+/// Read back a batch written by `encode_batch`.
+pub(crate) fn decode_batch(data: &[u8]) -> Result<Vec<Envelope>, ArchiveError> {
+    let mut text = String::new();
+    GzDecoder::new(data).read_to_string(&mut text)?;
+    text.lines()
+        .map(|line| serde_json::from_str(line).map_err(ArchiveError::from))
+        .collect()
+}
+
+/// Gzipped JSONL, one serialized envelope per line.
+fn encode_batch<'a>(
+    envelopes: impl IntoIterator<Item = &'a Envelope>,
+) -> Result<Bytes, ArchiveError> {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    for env in envelopes {
+        serde_json::to_writer(&mut encoder, env)?;
+        encoder.write_all(b"\n")?;
+    }
+    Ok(Bytes::from(encoder.finish()?))
+}
+
+/// (year, month, day, hour) in UTC of a unix millis timestamp.
+#[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)] // days since epoch fit in i64
+fn utc_hour(unix_ms: u64) -> (i64, u32, u32, u64) {
+    let secs = unix_ms / 1000;
+    let hour = (secs % 86_400) / 3600;
+
+    // Civil-from-days (Howard Hinnant's algorithm)
+    let z = (secs / 86_400) as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day, hour)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_utc_hour() {
+        assert_eq!(utc_hour(0), (1970, 1, 1, 0));
+        // 2024-02-29T23:59:59Z (leap day)
+        assert_eq!(utc_hour(1_709_251_199_000), (2024, 2, 29, 23));
+        // 2026-02-14T09:00:00Z
+        assert_eq!(utc_hour(1_771_059_600_000), (2026, 2, 14, 9));
+    }
+
+    #[test]
+    fn test_batch_key_partitions_by_hour() {
+        let id = ulid::Ulid::from_parts(0, 1);
+        assert_eq!(
+            batch_key("gbe.events.audit", 1_771_059_600_000, &id),
+            format!("gbe.events.audit/2026/02/14/09/{id}.jsonl.gz")
+        );
+    }
+
+    #[test]
+    fn test_parse_batch_key() {
+        let id = ulid::Ulid::from_parts(1_771_059_600_000, 7);
+        let key = batch_key("gbe.events.audit", 1_771_059_600_000, &id);
+        let relative = key.strip_prefix("gbe.events.audit/").unwrap();
+        assert_eq!(parse_batch_key(relative), Some(("2026/02/14/09", id)));
+        assert_eq!(parse_batch_key("2026/02/14/09/notes.txt"), None);
+    }
+
+    #[test]
+    fn test_batch_round_trip() {
+        let envelopes: Vec<Envelope> = ["a", "b"]
+            .iter()
+            .map(|p| Envelope::new("gbe.events.audit".to_string(), Bytes::from(*p), None))
+            .collect();
+
+        let encoded = encode_batch(&envelopes).unwrap();
+        assert_eq!(&encoded[..2], [0x1f, 0x8b], "gzip magic");
+
+        let decoded = decode_batch(&encoded).unwrap();
+        assert_eq!(decoded.len(), 2);
+        assert_eq!(decoded[0].message_id, envelopes[0].message_id);
+        assert_eq!(decoded[1].payload, envelopes[1].payload);
+    }
+}
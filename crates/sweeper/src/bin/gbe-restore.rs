@@ -0,0 +1,38 @@
+//! `gbe-restore`: replay archived envelopes from a local archive into Redis.
+//!
+//! ```text
+//! gbe-restore <archive-root> <subject> <from-ms> <to-ms> [target-subject]
+//! ```
+//!
+//! Environment: `REDIS_URL` (default `redis://127.0.0.1:6379`)
+
+use std::sync::Arc;
+
+use gbe_nexus_redis::{RedisTransport, RedisTransportConfig};
+use gbe_sweeper::{LocalFsSink, Restorer};
+
+const USAGE: &str =
+    "usage: gbe-restore <archive-root> <subject> <from-ms> <to-ms> [target-subject]";
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tracing_subscriber::fmt::init();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let [root, subject, from, to, rest @ ..] = args.as_slice() else {
+        return Err(USAGE.into());
+    };
+    let target = rest.first().map(String::as_str);
+    let range = from.parse()?..to.parse()?;
+
+    let mut transport_config = RedisTransportConfig::default();
+    if let Ok(url) = std::env::var("REDIS_URL") {
+        transport_config.url = url;
+    }
+    let transport = RedisTransport::connect(transport_config).await?;
+
+    let restorer = Restorer::new(Arc::new(LocalFsSink::new(root)));
+    let replayed = restorer.replay(subject, range, &transport, target).await?;
+    println!("replayed {replayed} envelopes");
+    Ok(())
+}
@@ -0,0 +1,22 @@
+use thiserror::Error;
+
+use gbe_nexus::TransportError;
+use gbe_state_store::StateStoreError;
+
+#[derive(Debug, Error)]
+pub enum ArchiveError {
+    #[error("transport error: {0}")]
+    Transport(#[from] TransportError),
+
+    #[error("state store error: {0}")]
+    State(#[from] StateStoreError),
+
+    #[error("blob sink error: {0}")]
+    Sink(String),
+
+    #[error("io error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("serialization error: {0}")]
+    Serialization(#[from] serde_json::Error),
+}
@@ -0,0 +1,23 @@
+//! Stream retention and archival.
+//!
+//! The sweeper periodically trims every stream recorded in the transport's
+//! stream config registry to its `max_age`, stopping at the oldest entry any
+//! consumer group has not acknowledged so slow consumers never lose data.
+//!
+//! The archiver drains streams to a `BlobSink` as compressed batches and
+//! records a watermark per stream; a sweeper given the same state
+//! store never trims past it. The restorer reads those batches back and
+//! replays them, keeping each envelope's original identity.
+
+mod archiver;
+mod error;
+mod restore;
+mod sink;
+mod sweeper;
+pub mod watermark;
+
+pub use archiver::{ArchiveHandle, Archiver, ArchiverConfig};
+pub use error::ArchiveError;
+pub use restore::Restorer;
+pub use sink::{BlobSink, LocalFsSink};
+pub use sweeper::{COMPONENT, StreamSweep, Sweeper, SweeperConfig};
@@ -0,0 +1,67 @@
+//! `gbe-sweeper` binary: sweeps the Redis transport until SIGINT or
+//! SIGTERM, publishing its lifecycle events.
+//!
+//! Environment:
+//! - `REDIS_URL` (default `redis://127.0.0.1:6379`), for both the transport
+//!   and the state store holding archive watermarks
+//! - `SWEEP_INTERVAL_SECS` (default 60)
+//! - `ARCHIVE_WATERMARKS` (default `true`): set to `false` to trim without
+//!   consulting archive watermarks, when nothing is archived
+
+use std::sync::Arc;
+use std::time::Duration;
+
+use gbe_lifecycle::{Lifecycle, LifecycleConfig};
+use gbe_nexus::EventEmitter;
+use gbe_nexus_redis::{RedisTransport, RedisTransportConfig};
+use gbe_state_store::StateStoreConfig;
+use gbe_state_store_redis::RedisStateStore;
+use gbe_sweeper::{COMPONENT, Sweeper, SweeperConfig};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    tracing_subscriber::fmt::init();
+
+    let mut transport_config = RedisTransportConfig::default();
+    if let Ok(url) = std::env::var("REDIS_URL") {
+        transport_config.url = url;
+    }
+    let mut config = SweeperConfig::default();
+    if let Ok(secs) = std::env::var("SWEEP_INTERVAL_SECS") {
+        config.interval = Duration::from_secs(secs.parse()?);
+    }
+    let watermarks = match std::env::var("ARCHIVE_WATERMARKS") {
+        Ok(value) => value.parse()?,
+        Err(_) => true,
+    };
+
+    let store = if watermarks {
+        Some(
+            RedisStateStore::connect(StateStoreConfig {
+                url: transport_config.url.clone(),
+            })
+            .await?,
+        )
+    } else {
+        None
+    };
+    let transport = Arc::new(RedisTransport::connect(transport_config).await?);
+    let emitter = EventEmitter::new(transport.clone(), COMPONENT, config.instance_id.clone());
+    let lifecycle = Arc::new(Lifecycle::new(
+        emitter,
+        LifecycleConfig {
+            version: Some(env!("CARGO_PKG_VERSION").to_string()),
+            ..Default::default()
+        },
+    ));
+    let mut sweeper = Sweeper::new(transport, config);
+    if let Some(store) = store {
+        sweeper = sweeper.with_archive_watermarks(Arc::new(store));
+    }
+
+    lifecycle.stop_on_signals();
+    tracing::info!("sweeper started");
+    tokio::join!(lifecycle.run(), sweeper.run(lifecycle.token()));
+    tracing::info!("sweeper stopped");
+    Ok(())
+}
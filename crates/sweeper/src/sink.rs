@@ -0,0 +1,92 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use std::path::{Path, PathBuf};
+
+use crate::error::ArchiveError;
+
+/// Destination for archive batches (S3 or similar in production).
+///
+/// Keys are `/`-separated paths such as
+/// `gbe.events.audit/2026/02/14/09/01HQ....jsonl.gz`.
+#[async_trait]
+pub trait BlobSink: Send + Sync {
+    /// Store `data` under `key`, replacing any existing blob.
+    async fn put(&self, key: &str, data: Bytes) -> Result<(), ArchiveError>;
+
+    /// Read the blob at `key`, or `None` if there is none.
+    async fn get(&self, key: &str) -> Result<Option<Bytes>, ArchiveError>;
+
+    /// Keys starting with `prefix`, sorted.
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, ArchiveError>;
+}
+
+/// `BlobSink` writing each blob to a file under `root`.
+pub struct LocalFsSink {
+    root: PathBuf,
+}
+
+impl LocalFsSink {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    /// Sink key of a file under `root`.
+    fn key_of(&self, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(&self.root).ok()?;
+        let parts: Vec<_> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect();
+        Some(parts.join("/"))
+    }
+}
+
+#[async_trait]
+impl BlobSink for LocalFsSink {
+    async fn put(&self, key: &str, data: Bytes) -> Result<(), ArchiveError> {
+        let path = self.root.join(key);
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        // Write then rename, so a reader never sees a partial blob
+        let tmp = path.with_extension("tmp");
+        tokio::fs::write(&tmp, &data).await?;
+        tokio::fs::rename(&tmp, &path).await?;
+        Ok(())
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Bytes>, ArchiveError> {
+        match tokio::fs::read(self.root.join(key)).await {
+            Ok(data) => Ok(Some(Bytes::from(data))),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, ArchiveError> {
+        // Walk only the directory the prefix names, then match the rest
+        let dir = prefix.rsplit_once('/').map_or("", |(dir, _)| dir);
+        let mut keys = Vec::new();
+        let mut pending = vec![self.root.join(dir)];
+        while let Some(dir) = pending.pop() {
+            let mut entries = match tokio::fs::read_dir(&dir).await {
+                Ok(entries) => entries,
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(e) => return Err(e.into()),
+            };
+            while let Some(entry) = entries.next_entry().await? {
+                let path = entry.path();
+                if entry.file_type().await?.is_dir() {
+                    pending.push(path);
+                } else if let Some(key) = self.key_of(&path)
+                    && key.starts_with(prefix)
+                    && !key.ends_with(".tmp")
+                {
+                    keys.push(key);
+                }
+            }
+        }
+        keys.sort();
+        Ok(keys)
+    }
+}
@@ -0,0 +1,157 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use tokio_util::sync::CancellationToken;
+
+use gbe_lifecycle::emit_degraded;
+use gbe_nexus::{EventEmitter, StreamConfig, Transport, TransportError, now_millis};
+use gbe_state_store::StateStore;
+
+use crate::watermark::read_watermark;
+
+/// Component name used in lifecycle events.
+pub const COMPONENT: &str = "sweeper";
+
+/// Configuration for a `Sweeper`.
+pub struct SweeperConfig {
+    /// Time between sweeps.
+    pub interval: Duration,
+    /// Instance ID reported in lifecycle events.
+    pub instance_id: String,
+}
+
+impl Default for SweeperConfig {
+    fn default() -> Self {
+        Self {
+            interval: Duration::from_secs(60),
+            instance_id: format!("swp-{}", ulid::Ulid::new().to_string().to_lowercase()),
+        }
+    }
+}
+
+/// Outcome of sweeping one stream.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StreamSweep {
+    pub subject: String,
+    /// Entries removed.
+    pub trimmed: u64,
+    /// Publish time (unix millis) of the oldest entry a consumer group still
+    /// needs, when that entry is past `max_age`. Trimming stopped there.
+    pub blocked_at: Option<u64>,
+}
+
+/// Trims every stream in the config registry to its `max_age`, but never
+/// past the oldest entry some consumer group has not acknowledged, nor past
+/// the archive watermark of an archived stream.
+///
+/// `max_msgs` and `max_bytes` are enforced by the transport on publish; the
+/// sweeper covers age retention on streams that have gone quiet.
+pub struct Sweeper {
+    transport: Arc<dyn Transport>,
+    emitter: EventEmitter,
+    watermarks: Option<Arc<dyn StateStore>>,
+    config: SweeperConfig,
+}
+
+impl Sweeper {
+    pub fn new(transport: Arc<dyn Transport>, config: SweeperConfig) -> Self {
+        let emitter = EventEmitter::new(transport.clone(), COMPONENT, config.instance_id.clone());
+        Self {
+            transport,
+            emitter,
+            watermarks: None,
+            config,
+        }
+    }
+
+    /// Also respect the archive watermarks kept in `store`.
+    #[must_use]
+    pub fn with_archive_watermarks(mut self, store: Arc<dyn StateStore>) -> Self {
+        self.watermarks = Some(store);
+        self
+    }
+
+    /// Sweep every registered stream with an age limit once.
+    ///
+    /// A failure on one stream is logged and does not stop the others.
+    ///
+    /// # Errors
+    /// Returns an error if the stream config registry cannot be read.
+    pub async fn sweep(&self) -> Result<Vec<StreamSweep>, TransportError> {
+        let mut swept = Vec::new();
+        for config in self.transport.stream_configs().await? {
+            if config.max_age.is_zero() {
+                continue;
+            }
+            match self.sweep_stream(&config).await {
+                Ok(sweep) => swept.push(sweep),
+                Err(e) => tracing::warn!(subject = %config.subject, "sweep failed: {e}"),
+            }
+        }
+        Ok(swept)
+    }
+
+    /// Sweep every `interval` until `token` is cancelled.
+    pub async fn run(&self, token: CancellationToken) {
+        let mut ticker = tokio::time::interval(self.config.interval);
+        loop {
+            tokio::select! {
+                () = token.cancelled() => break,
+                _ = ticker.tick() => {
+                    if let Err(e) = self.sweep().await {
+                        tracing::warn!("sweep failed: {e}");
+                    }
+                }
+            }
+        }
+    }
+
+    #[allow(clippy::cast_possible_truncation)] // millis since epoch fits in u64 until year 584556
+    async fn sweep_stream(&self, config: &StreamConfig) -> Result<StreamSweep, TransportError> {
+        let subject = &config.subject;
+        let cutoff_ms = now_millis().saturating_sub(config.max_age.as_millis() as u64);
+
+        let blocked_at = self
+            .transport
+            .oldest_unacked(subject)
+            .await?
+            .filter(|&oldest| oldest < cutoff_ms);
+        let mut before = blocked_at.unwrap_or(cutoff_ms);
+
+        // Entries published at the watermark may not all be archived yet,
+        // so the watermark itself is kept too. An idle archived stream sits
+        // at its watermark indefinitely, so this is not reported as lag.
+        if let Some(store) = &self.watermarks
+            && let Some(archived_through) = read_watermark(store, subject)
+                .await
+                .map_err(|e| TransportError::Other(format!("archive watermark: {e}")))?
+        {
+            before = before.min(archived_through);
+        }
+
+        let trimmed = self.transport.trim_stream_before(subject, before).await?;
+
+        tracing::info!(subject = %subject, trimmed, "swept stream");
+        if let Some(oldest) = blocked_at {
+            let lag = Duration::from_millis(cutoff_ms - oldest);
+            tracing::warn!(subject = %subject, lag_secs = lag.as_secs(), "consumer lag is holding back retention");
+            self.report_degraded(format!(
+                "consumer lag on {subject} holds back retention by {}s",
+                lag.as_secs()
+            ))
+            .await;
+        }
+
+        Ok(StreamSweep {
+            subject: subject.clone(),
+            trimmed,
+            blocked_at,
+        })
+    }
+
+    async fn report_degraded(&self, reason: String) {
+        if let Err(e) = emit_degraded(&self.emitter, reason).await {
+            tracing::warn!("failed to emit degraded event: {e}");
+        }
+    }
+}
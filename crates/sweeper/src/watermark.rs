@@ -0,0 +1,75 @@
+//! Archive watermarks.
+//!
+//! After each batch reaches the sink, the archiver records the publish time
+//! every earlier entry it has read is archived by: that of the newest
+//! archived entry, held back to the oldest entry whose batch failed and
+//! awaits redelivery. It never moves backwards. The sweeper never trims a
+//! stream with a watermark past it, so unarchived entries stay in the stream.
+
+use bytes::Bytes;
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use gbe_state_store::{StateStore, StateStoreError};
+
+/// State store key of a stream's archive watermark:
+/// `gbe.state.archive.{subject}`
+#[must_use]
+pub fn watermark_key(subject: &str) -> String {
+    format!("gbe.state.archive.{subject}")
+}
+
+/// Field names of a watermark record.
+pub mod fields {
+    /// Publish time (unix millis) before which every entry read is
+    /// archived; `0` before the first batch.
+    pub const ARCHIVED_THROUGH: &str = "archived_through";
+    pub const LAST_MESSAGE_ID: &str = "last_message_id";
+    pub const UPDATED_AT: &str = "updated_at";
+}
+
+/// Read the watermark of `subject`, or `None` if it is not archived.
+///
+/// # Errors
+/// Returns the state store error, or `StateStoreError::Other` for an
+/// unparseable value.
+pub async fn read_watermark(
+    store: &Arc<dyn StateStore>,
+    subject: &str,
+) -> Result<Option<u64>, StateStoreError> {
+    let Some(value) = store
+        .get_field(&watermark_key(subject), fields::ARCHIVED_THROUGH)
+        .await?
+    else {
+        return Ok(None);
+    };
+    std::str::from_utf8(&value)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .map(Some)
+        .ok_or_else(|| StateStoreError::Other(format!("invalid archive watermark for {subject}")))
+}
+
+pub(crate) async fn write_watermark(
+    store: &Arc<dyn StateStore>,
+    subject: &str,
+    archived_through: u64,
+    last_message_id: &str,
+    now_ms: u64,
+) -> Result<(), StateStoreError> {
+    let fields = HashMap::from([
+        (
+            fields::ARCHIVED_THROUGH.to_string(),
+            Bytes::from(archived_through.to_string()),
+        ),
+        (
+            fields::LAST_MESSAGE_ID.to_string(),
+            Bytes::from(last_message_id.to_string()),
+        ),
+        (
+            fields::UPDATED_AT.to_string(),
+            Bytes::from(now_ms.to_string()),
+        ),
+    ]);
+    store.set_fields(&watermark_key(subject), fields).await
+}
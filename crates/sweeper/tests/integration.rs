@@ -0,0 +1,479 @@
+use async_trait::async_trait;
+use bytes::Bytes;
+use flate2::read::GzDecoder;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::sync::mpsc;
+
+use gbe_jobs_domain::ComponentDegraded;
+use gbe_jobs_domain::subjects::lifecycle;
+use gbe_nexus::{
+    DomainPayload, Envelope, Message, MessageHandler, PublishOpts, StartPosition, StreamConfig,
+    SubscribeOpts, Transport, TransportError,
+};
+use gbe_nexus_memory::{MemoryTransport, MemoryTransportConfig};
+use gbe_state_store::StateStore;
+use gbe_state_store_memory::MemoryStateStore;
+use gbe_sweeper::watermark::read_watermark;
+use gbe_sweeper::{
+    ArchiveError, Archiver, ArchiverConfig, BlobSink, COMPONENT, LocalFsSink, Restorer, Sweeper,
+    SweeperConfig,
+};
+
+const MAX_AGE: Duration = Duration::from_millis(200);
+
+fn create_transport() -> Arc<dyn Transport> {
+    Arc::new(MemoryTransport::new(MemoryTransportConfig::default()))
+}
+
+fn create_sweeper(transport: &Arc<dyn Transport>) -> Sweeper {
+    Sweeper::new(transport.clone(), SweeperConfig::default())
+}
+
+async fn ensure(transport: &Arc<dyn Transport>, subject: &str, max_age: Duration) {
+    transport
+        .ensure_stream(StreamConfig {
+            subject: subject.to_string(),
+            max_age,
+            max_bytes: None,
+            max_msgs: None,
+        })
+        .await
+        .unwrap();
+}
+
+async fn publish_all(transport: &Arc<dyn Transport>, subject: &str, payloads: &[&str]) {
+    for p in payloads {
+        transport
+            .publish(subject, Bytes::from(p.to_string()), None)
+            .await
+            .unwrap();
+    }
+}
+
+/// Acks only the payloads it is told to; every other message stays pending.
+struct SelectiveAckHandler {
+    ack: Vec<&'static str>,
+    tx: mpsc::UnboundedSender<Envelope>,
+}
+
+#[async_trait]
+impl MessageHandler for SelectiveAckHandler {
+    async fn handle(&self, msg: &dyn Message) -> Result<(), TransportError> {
+        if self
+            .ack
+            .iter()
+            .any(|p| msg.envelope().payload == p.as_bytes())
+        {
+            msg.ack().await?;
+        }
+        let _ = self.tx.send(msg.envelope().clone());
+        Ok(())
+    }
+}
+
+async fn recv(rx: &mut mpsc::UnboundedReceiver<Envelope>) -> Envelope {
+    tokio::time::timeout(Duration::from_secs(2), rx.recv())
+        .await
+        .expect("timed out waiting for delivery")
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_sweep_trims_expired_entries() {
+    let transport = create_transport();
+    let subject = "gbe.test.sweep-expired";
+    ensure(&transport, subject, MAX_AGE).await;
+    publish_all(&transport, subject, &["m0", "m1", "m2"]).await;
+
+    let sweeper = create_sweeper(&transport);
+    let swept = sweeper.sweep().await.unwrap();
+    assert_eq!(swept[0].trimmed, 0, "nothing has expired yet");
+
+    tokio::time::sleep(MAX_AGE + Duration::from_millis(100)).await;
+    let swept = sweeper.sweep().await.unwrap();
+    assert_eq!(swept.len(), 1);
+    assert_eq!(swept[0].subject, subject);
+    assert_eq!(swept[0].trimmed, 3);
+    assert_eq!(swept[0].blocked_at, None);
+}
+
+#[tokio::test]
+async fn test_sweep_skips_streams_without_age_limit() {
+    let transport = create_transport();
+    ensure(&transport, "gbe.test.sweep-forever", Duration::ZERO).await;
+    publish_all(&transport, "gbe.test.sweep-forever", &["m0"]).await;
+
+    let swept = create_sweeper(&transport).sweep().await.unwrap();
+    assert!(swept.is_empty());
+}
+
+#[tokio::test]
+async fn test_sweep_stops_at_oldest_unacked_and_reports_degraded() {
+    let transport = create_transport();
+    let subject = "gbe.test.sweep-lagging";
+    ensure(&transport, subject, MAX_AGE).await;
+    publish_all(&transport, subject, &["m0"]).await;
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    publish_all(&transport, subject, &["m1", "m2"]).await;
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let sub = transport
+        .subscribe(
+            subject,
+            "slow",
+            Box::new(SelectiveAckHandler {
+                ack: vec!["m0"],
+                tx,
+            }),
+            Some(SubscribeOpts {
+                start_from: StartPosition::Earliest,
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+    for _ in 0..3 {
+        recv(&mut rx).await;
+    }
+
+    tokio::time::sleep(MAX_AGE + Duration::from_millis(100)).await;
+    let swept = create_sweeper(&transport).sweep().await.unwrap();
+    assert_eq!(swept[0].trimmed, 1, "only the acked entry may go");
+    assert!(swept[0].blocked_at.is_some());
+    sub.unsubscribe().await.unwrap();
+
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let events = transport
+        .subscribe(
+            &lifecycle::degraded(COMPONENT),
+            "test",
+            Box::new(SelectiveAckHandler { ack: vec![], tx }),
+            Some(SubscribeOpts {
+                start_from: StartPosition::Earliest,
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+    let env = recv(&mut rx).await;
+    let event = DomainPayload::<ComponentDegraded>::from_bytes(&env.payload).unwrap();
+    assert_eq!(event.data.component, COMPONENT);
+    assert!(event.data.reason.contains(subject));
+    events.unsubscribe().await.unwrap();
+}
+
+// --- Archiver ---
+
+fn temp_root() -> PathBuf {
+    std::env::temp_dir().join(format!("gbe-archive-{}", ulid::Ulid::new()))
+}
+
+fn create_archiver(
+    transport: &Arc<dyn Transport>,
+    state: &Arc<dyn StateStore>,
+    root: &Path,
+    batch_size: usize,
+    batch_timeout: Duration,
+) -> Archiver {
+    Archiver::new(
+        transport.clone(),
+        state.clone(),
+        Arc::new(LocalFsSink::new(root)),
+        ArchiverConfig {
+            batch_size,
+            batch_timeout,
+            ..Default::default()
+        },
+    )
+}
+
+fn batch_files(root: &Path) -> Vec<PathBuf> {
+    fn walk(dir: &Path, out: &mut Vec<PathBuf>) {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return;
+        };
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.is_dir() {
+                walk(&path, out);
+            } else if path.to_string_lossy().ends_with(".jsonl.gz") {
+                out.push(path);
+            }
+        }
+    }
+    let mut out = Vec::new();
+    walk(root, &mut out);
+    out.sort();
+    out
+}
+
+async fn wait_for_batches(root: &Path, n: usize) -> Vec<PathBuf> {
+    for _ in 0..100 {
+        let files = batch_files(root);
+        if files.len() >= n {
+            return files;
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("timed out waiting for {n} archive batches");
+}
+
+fn read_batch(path: &Path) -> Vec<Envelope> {
+    let mut text = String::new();
+    GzDecoder::new(std::fs::File::open(path).unwrap())
+        .read_to_string(&mut text)
+        .unwrap();
+    text.lines()
+        .map(|l| serde_json::from_str(l).unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn test_archiver_writes_batches_and_watermark() {
+    let transport = create_transport();
+    let state: Arc<dyn StateStore> = Arc::new(MemoryStateStore::new());
+    let root = temp_root();
+    let subject = "gbe.test.archive-batches";
+    publish_all(&transport, subject, &["m0", "m1", "m2"]).await;
+
+    let archiver = create_archiver(&transport, &state, &root, 2, Duration::from_millis(200));
+    let handle = archiver.start(subject).await.unwrap();
+
+    // Two by size, then the remainder once it has waited out the timeout
+    let files = wait_for_batches(&root, 2).await;
+    let archived: Vec<Envelope> = files.iter().flat_map(|f| read_batch(f)).collect();
+    let payloads: Vec<&[u8]> = archived.iter().map(|e| e.payload.as_ref()).collect();
+    assert_eq!(payloads, [b"m0", b"m1", b"m2"]);
+    assert!(
+        files[0].strip_prefix(&root).unwrap().starts_with(subject),
+        "batches are partitioned under the subject"
+    );
+
+    let watermark = read_watermark(&state, subject).await.unwrap();
+    assert_eq!(watermark, Some(archived[2].timestamp));
+
+    handle.stop().await.unwrap();
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_sweeper_respects_archive_watermark() {
+    let transport = create_transport();
+    let state: Arc<dyn StateStore> = Arc::new(MemoryStateStore::new());
+    let root = temp_root();
+    let subject = "gbe.test.archive-sweep";
+    ensure(&transport, subject, MAX_AGE).await;
+    publish_all(&transport, subject, &["m0"]).await;
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    publish_all(&transport, subject, &["m1"]).await;
+
+    // Staged but not yet written to the sink, so still unacked
+    let archiver = create_archiver(&transport, &state, &root, 100, Duration::from_secs(60));
+    let handle = archiver.start(subject).await.unwrap();
+    tokio::time::sleep(MAX_AGE + Duration::from_millis(100)).await;
+    assert!(transport.oldest_unacked(subject).await.unwrap().is_some());
+
+    let sweeper = create_sweeper(&transport).with_archive_watermarks(state.clone());
+    let swept = sweeper.sweep().await.unwrap();
+    assert_eq!(swept[0].trimmed, 0, "nothing is archived yet");
+
+    handle.flush().await.unwrap();
+    assert_eq!(transport.oldest_unacked(subject).await.unwrap(), None);
+    let swept = sweeper.sweep().await.unwrap();
+    assert_eq!(swept[0].trimmed, 1, "the entry at the watermark is kept");
+
+    handle.stop().await.unwrap();
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+/// Fails the first `put`, then writes through to `inner`.
+struct FlakySink {
+    inner: LocalFsSink,
+    failed: AtomicBool,
+}
+
+#[async_trait]
+impl BlobSink for FlakySink {
+    async fn put(&self, key: &str, data: Bytes) -> Result<(), ArchiveError> {
+        if !self.failed.swap(true, Ordering::AcqRel) {
+            return Err(ArchiveError::Sink("unavailable".to_string()));
+        }
+        self.inner.put(key, data).await
+    }
+
+    async fn get(&self, key: &str) -> Result<Option<Bytes>, ArchiveError> {
+        self.inner.get(key).await
+    }
+
+    async fn list(&self, prefix: &str) -> Result<Vec<String>, ArchiveError> {
+        self.inner.list(prefix).await
+    }
+}
+
+#[tokio::test]
+async fn test_archiver_redelivers_batch_after_failed_write() {
+    let transport = create_transport();
+    let state: Arc<dyn StateStore> = Arc::new(MemoryStateStore::new());
+    let root = temp_root();
+    let subject = "gbe.test.archive-retry";
+    publish_all(&transport, subject, &["m0", "m1"]).await;
+
+    let archiver = Archiver::new(
+        transport.clone(),
+        state.clone(),
+        Arc::new(FlakySink {
+            inner: LocalFsSink::new(&root),
+            failed: AtomicBool::new(false),
+        }),
+        ArchiverConfig {
+            batch_size: 2,
+            retry_delay: Duration::from_millis(50),
+            ..Default::default()
+        },
+    );
+    let handle = archiver.start(subject).await.unwrap();
+
+    let files = wait_for_batches(&root, 1).await;
+    let payloads: Vec<Bytes> = read_batch(&files[0])
+        .into_iter()
+        .map(|e| e.payload)
+        .collect();
+    assert_eq!(payloads, [Bytes::from("m0"), Bytes::from("m1")]);
+    assert_eq!(transport.oldest_unacked(subject).await.unwrap(), None);
+
+    handle.stop().await.unwrap();
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+#[tokio::test]
+async fn test_watermark_stays_behind_failed_batch() {
+    let transport = create_transport();
+    let state: Arc<dyn StateStore> = Arc::new(MemoryStateStore::new());
+    let root = temp_root();
+    let subject = "gbe.test.archive-held-back";
+    publish_all(&transport, subject, &["m0", "m1"]).await;
+
+    let archiver = Archiver::new(
+        transport.clone(),
+        state.clone(),
+        Arc::new(FlakySink {
+            inner: LocalFsSink::new(&root),
+            failed: AtomicBool::new(false),
+        }),
+        ArchiverConfig {
+            batch_size: 4,
+            batch_timeout: Duration::from_millis(50),
+            retry_delay: Duration::from_secs(60),
+            ..Default::default()
+        },
+    );
+    let handle = archiver.start(subject).await.unwrap();
+
+    // m0 and m1 fail and wait out the retry delay; m2 and m3 go through
+    tokio::time::sleep(Duration::from_millis(150)).await;
+    publish_all(&transport, subject, &["m2", "m3"]).await;
+    wait_for_batches(&root, 1).await;
+
+    let originals = read_all(transport.as_ref(), subject, 4).await;
+    let watermark = read_watermark(&state, subject).await.unwrap();
+    assert_eq!(watermark, Some(originals[0].timestamp));
+
+    handle.stop().await.unwrap();
+    std::fs::remove_dir_all(&root).unwrap();
+}
+
+// --- Restore ---
+
+async fn read_all(transport: &dyn Transport, subject: &str, n: usize) -> Vec<Envelope> {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let sub = transport
+        .subscribe(
+            subject,
+            "reader",
+            Box::new(SelectiveAckHandler { ack: vec![], tx }),
+            Some(SubscribeOpts {
+                start_from: StartPosition::Earliest,
+                ..Default::default()
+            }),
+        )
+        .await
+        .unwrap();
+    let mut out = Vec::new();
+    for _ in 0..n {
+        out.push(recv(&mut rx).await);
+    }
+    sub.unsubscribe().await.unwrap();
+    out
+}
+
+#[tokio::test]
+async fn test_restore_reads_range_and_replays_with_identity() {
+    let transport = create_transport();
+    let state: Arc<dyn StateStore> = Arc::new(MemoryStateStore::new());
+    let root = temp_root();
+    let subject = "gbe.test.archive-restore";
+
+    for (payload, trace_id) in [("m0", None), ("m1", Some("trace-1")), ("m2", None)] {
+        transport
+            .publish(
+                subject,
+                Bytes::from(payload),
+                Some(PublishOpts {
+                    trace_id: trace_id.map(String::from),
+                    ..Default::default()
+                }),
+            )
+            .await
+            .unwrap();
+        tokio::time::sleep(Duration::from_millis(5)).await;
+    }
+    let archiver = create_archiver(&transport, &state, &root, 2, Duration::from_millis(100));
+    let handle = archiver.start(subject).await.unwrap();
+    wait_for_batches(&root, 2).await;
+    handle.stop().await.unwrap();
+    let originals = read_all(transport.as_ref(), subject, 3).await;
+
+    let restorer = Restorer::new(Arc::new(LocalFsSink::new(&root)));
+    let all = restorer.read(subject, 0..u64::MAX).await.unwrap();
+    let ids =
+        |envs: &[Envelope]| -> Vec<String> { envs.iter().map(|e| e.message_id.clone()).collect() };
+    assert_eq!(ids(&all), ids(&originals));
+
+    let middle = restorer
+        .read(subject, originals[1].timestamp..originals[2].timestamp)
+        .await
+        .unwrap();
+    assert_eq!(ids(&middle), [originals[1].message_id.clone()]);
+
+    // Replay to another subject on a fresh transport
+    let target = create_transport();
+    let replayed = restorer
+        .replay(
+            subject,
+            0..u64::MAX,
+            target.as_ref(),
+            Some("gbe.test.restored"),
+        )
+        .await
+        .unwrap();
+    assert_eq!(replayed, 3);
+    let restored = read_all(target.as_ref(), "gbe.test.restored", 3).await;
+    assert_eq!(ids(&restored), ids(&originals));
+    assert_eq!(restored[1].timestamp, originals[1].timestamp);
+    assert_eq!(restored[1].trace_id.as_deref(), Some("trace-1"));
+    assert_eq!(restored[1].subject, "gbe.test.restored");
+
+    // Offline analysis under the original subject
+    let memory = restorer
+        .load_into_memory(subject, 0..u64::MAX)
+        .await
+        .unwrap();
+    let loaded = read_all(&memory, subject, 3).await;
+    assert_eq!(ids(&loaded), ids(&originals));
+
+    std::fs::remove_dir_all(&root).unwrap();
+}